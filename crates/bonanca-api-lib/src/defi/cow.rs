@@ -1,3 +1,4 @@
+use alloy_primitives::U256;
 use anyhow::Result;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
@@ -69,7 +70,7 @@ impl CowApi {
             .await?;
 
         // Fee has to be zero even though they give it non-zero
-        resp.quote.fee_amount = 0;
+        resp.quote.fee_amount = U256::ZERO;
 
         Ok(resp)
     }
@@ -100,7 +101,7 @@ pub struct CowSwapData {
     pub sell_token: String,
     pub buy_token: String,
     #[serde_as(as = "DisplayFromStr")]
-    pub sell_amount_before_fee: u64,
+    pub sell_amount_before_fee: U256,
     pub kind: String,
     pub from: String,
     pub receiver: String,
@@ -150,14 +151,14 @@ pub struct CowQuote {
     pub buy_token: String,
     pub receiver: Option<String>,
     #[serde_as(as = "DisplayFromStr")]
-    pub sell_amount: u64,
+    pub sell_amount: U256,
     #[serde_as(as = "DisplayFromStr")]
-    pub buy_amount: u64,
+    pub buy_amount: U256,
     pub valid_to: u32,
     pub app_data: String,
     pub app_data_hash: String,
     #[serde_as(as = "DisplayFromStr")]
-    pub fee_amount: u64,
+    pub fee_amount: U256,
     pub kind: String,
     pub partially_fillable: bool,
     pub sell_token_balance: String,
@@ -170,8 +171,8 @@ impl CowQuote {
         sell_token: &str,
         buy_token: &str,
         receiver: Option<String>,
-        sell_amount: u64,
-        buy_amount: u64,
+        sell_amount: U256,
+        buy_amount: U256,
         valid_to: u32,
     ) -> Self {
         Self {
@@ -184,7 +185,7 @@ impl CowQuote {
             app_data: "{}".to_string(), // hash below is keccak256("{}")
             app_data_hash: "0xb48d38f93eaa084033fc5970bf96e559c33c4cdc07d889ab00b4d63f9590739d"
                 .to_string(),
-            fee_amount: 0,
+            fee_amount: U256::ZERO,
             kind: "sell".to_string(),
             partially_fillable: true,
             sell_token_balance: "erc20".to_string(),
@@ -223,14 +224,14 @@ pub struct CowOrder {
     pub buy_token: String,
     pub receiver: Option<String>,
     #[serde_as(as = "DisplayFromStr")]
-    pub sell_amount: u64,
+    pub sell_amount: U256,
     #[serde_as(as = "DisplayFromStr")]
-    pub buy_amount: u64,
+    pub buy_amount: U256,
     pub valid_to: u32,
     pub app_data: String,
     pub app_data_hash: String,
     #[serde_as(as = "DisplayFromStr")]
-    pub fee_amount: u64,
+    pub fee_amount: U256,
     pub kind: String,
     pub partially_fillable: bool,
     pub sell_token_balance: String,
@@ -1,3 +1,4 @@
+use alloy_primitives::U256;
 use anyhow::Result;
 use reqwest::Client;
 use serde::Deserialize;
@@ -23,7 +24,7 @@ impl ZeroXApi {
         &self,
         sell: &str,
         buy: &str,
-        amount: u64,
+        amount: U256,
     ) -> Result<ZeroXPriceQuote> {
         let url = format!(
             "{}/swap/allowance-holder/price?chainId={}&sellToken={}&sellAmount={}&buyToken={}",
@@ -48,7 +49,7 @@ impl ZeroXApi {
         &self,
         sell: &str,
         buy: &str,
-        amount: u64,
+        amount: U256,
         taker: &str,
     ) -> Result<ZeroXSwapQuote> {
         let url = format!(
@@ -69,6 +70,34 @@ impl ZeroXApi {
 
         Ok(quote)
     }
+
+    /// Swap quote that pulls the sell token through Permit2, the returned
+    /// `permit2` message must be signed and appended to the calldata
+    pub async fn get_permit2_quote(
+        &self,
+        sell: &str,
+        buy: &str,
+        amount: U256,
+        taker: &str,
+    ) -> Result<ZeroXSwapQuote> {
+        let url = format!(
+            "{}/swap/permit2/quote?chainId={}&sellToken={}&sellAmount={}&buyToken={}&taker={}",
+            &self.base_url, &self.chain_id, sell, amount, buy, taker,
+        );
+
+        let quote: ZeroXSwapQuote = self
+            .client
+            .get(&url)
+            .header("0x-api-key", &self.api_key)
+            .header("0x-version", "v2")
+            .header("Accept", "application/json")
+            .send()
+            .await?
+            .json::<ZeroXSwapQuote>()
+            .await?;
+
+        Ok(quote)
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -108,6 +137,8 @@ pub struct ZeroXSwapQuote {
     pub total_network_fee: String,
     pub transaction: Transaction,
     pub zid: String,
+    /// Only set on Permit2 quotes
+    pub permit2: Option<Permit2>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -192,3 +223,32 @@ pub struct Transaction {
     pub gas_price: String,
     pub value: String,
 }
+
+#[derive(Debug, Deserialize)]
+pub struct Permit2 {
+    #[serde(rename = "type")]
+    pub permit_type: String,
+    pub hash: String,
+    pub eip712: Permit2TypedData,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Permit2TypedData {
+    pub primary_type: String,
+    pub message: Permit2Message,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Permit2Message {
+    pub permitted: Permit2TokenPermissions,
+    pub spender: String,
+    pub nonce: String,
+    pub deadline: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Permit2TokenPermissions {
+    pub token: String,
+    pub amount: String,
+}
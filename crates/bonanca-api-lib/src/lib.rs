@@ -1,3 +1,55 @@
 pub mod block_explorer;
 pub mod defi;
+pub mod nft;
 pub mod price_feeds;
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::nft::{NftMetadataApi, parse_data_uri};
+
+    #[test]
+    fn test_data_uris() {
+        // Prefix already stripped by `get_metadata`
+        let base64 = "application/json;base64,eyJuYW1lIjoiQm9uYW5jYSAjMSIsImltYWdlIjoiaXBmczovL1FtSGFzaCIsImF0dHJpYnV0ZXMiOlt7InRyYWl0X3R5cGUiOiJMZXZlbCIsInZhbHVlIjozfV0sImVkaXRpb24iOjd9";
+        let metadata = parse_data_uri(base64).unwrap();
+        assert_eq!(metadata.name.as_deref(), Some("Bonanca #1"));
+        assert_eq!(metadata.image.as_deref(), Some("ipfs://QmHash"));
+        assert_eq!(metadata.attributes.len(), 1);
+        assert_eq!(metadata.attributes[0].trait_type.as_deref(), Some("Level"));
+        assert_eq!(metadata.attributes[0].value, json!(3));
+        assert_eq!(metadata.extra["edition"], json!(7));
+
+        let utf8 = r#"application/json;utf8,{"name":"Bonanca #2","description":"a, b"}"#;
+        let metadata = parse_data_uri(utf8).unwrap();
+        assert_eq!(metadata.name.as_deref(), Some("Bonanca #2"));
+        assert_eq!(metadata.description.as_deref(), Some("a, b"));
+        assert!(metadata.attributes.is_empty());
+
+        // No comma, not JSON, bad base64 and bad JSON
+        assert!(parse_data_uri("application/json;base64").is_err());
+        assert!(parse_data_uri("image/svg+xml;base64,PHN2Zz48L3N2Zz4=").is_err());
+        assert!(parse_data_uri("application/json;base64,not base64!").is_err());
+        assert!(parse_data_uri("application/json,{\"name\":").is_err());
+    }
+
+    #[test]
+    fn test_resolve_uris() {
+        let api = NftMetadataApi::default();
+
+        assert_eq!(
+            api.resolve("ipfs://QmHash/1.json"),
+            "https://ipfs.io/ipfs/QmHash/1.json"
+        );
+        assert_eq!(
+            api.resolve("ipfs://ipfs/QmHash/1.json"),
+            "https://ipfs.io/ipfs/QmHash/1.json"
+        );
+        assert_eq!(api.resolve("ar://TxId"), "https://arweave.net/TxId");
+        assert_eq!(
+            api.resolve("https://example.com/1.json"),
+            "https://example.com/1.json"
+        );
+    }
+}
@@ -0,0 +1,94 @@
+use anyhow::{Context, Result, bail};
+use base64::{Engine, engine::general_purpose::STANDARD};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Fetches NFT metadata JSON from the URIs returned by `tokenURI`/`uri`
+pub struct NftMetadataApi {
+    ipfs_gateway: String,
+    client: Client,
+}
+
+impl Default for NftMetadataApi {
+    fn default() -> Self {
+        Self::new("https://ipfs.io/ipfs/".to_string())
+    }
+}
+
+impl NftMetadataApi {
+    pub fn new(ipfs_gateway: String) -> Self {
+        Self {
+            ipfs_gateway,
+            client: Client::new(),
+        }
+    }
+
+    pub async fn get_metadata(&self, uri: &str) -> Result<NftMetadata> {
+        if let Some(data) = uri.strip_prefix("data:") {
+            return parse_data_uri(data);
+        }
+
+        let url = self.resolve(uri);
+        let metadata = self
+            .client
+            .get(&url)
+            .header("Accept", "application/json")
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<NftMetadata>()
+            .await?;
+
+        Ok(metadata)
+    }
+
+    /// Maps `ipfs://` and `ar://` URIs onto HTTP gateways
+    pub fn resolve(&self, uri: &str) -> String {
+        if let Some(path) = uri.strip_prefix("ipfs://") {
+            let path = path.strip_prefix("ipfs/").unwrap_or(path);
+            format!("{}{}", self.ipfs_gateway, path)
+        } else if let Some(path) = uri.strip_prefix("ar://") {
+            format!("https://arweave.net/{path}")
+        } else {
+            uri.to_string()
+        }
+    }
+}
+
+// On-chain metadata, e.g. `data:application/json;base64,eyJuYW1lIjo...`
+pub(crate) fn parse_data_uri(data: &str) -> Result<NftMetadata> {
+    let (media_type, payload) = data.split_once(',').context("Malformed data URI")?;
+
+    if !media_type.starts_with("application/json") {
+        bail!("Unsupported metadata type {media_type}");
+    }
+
+    let json = if media_type.ends_with(";base64") {
+        STANDARD.decode(payload)?
+    } else {
+        payload.as_bytes().to_vec()
+    };
+
+    Ok(serde_json::from_slice(&json)?)
+}
+
+/// ERC-721/ERC-1155 metadata JSON, fields outside the common schema are
+/// kept in `extra`
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct NftMetadata {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub image: Option<String>,
+    pub external_url: Option<String>,
+    #[serde(default)]
+    pub attributes: Vec<NftAttribute>,
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct NftAttribute {
+    pub trait_type: Option<String>,
+    pub value: Value,
+}
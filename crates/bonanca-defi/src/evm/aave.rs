@@ -1,13 +1,21 @@
 use alloy::{
     primitives::{Address, U256},
     providers::DynProvider,
-    rpc::types::TransactionReceipt,
+    rpc::types::{TransactionReceipt, TransactionRequest},
     sol,
 };
 use anyhow::Result;
 use bonanca_api_lib::defi::aave::{AaveV3Api, AaveV3ReserveData};
-use bonanca_wallets::wallets::evm::EvmWallet;
-use std::str::FromStr;
+use bonanca_wallets::{
+    amount::Amount,
+    multicall::Multicall,
+    simulate::{Revert, SendOutcome},
+    wallets::evm::EvmWallet,
+};
+use std::{
+    str::FromStr,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
 sol! {
     #[allow(missing_docs)]
@@ -43,15 +51,16 @@ impl AaveV3 {
 
         let reserves = pool.getReservesList().call().await?;
 
-        let mut reserve_data: Vec<DataTypes::ReserveDataLegacy> = Vec::new();
-
-        for reserve_address in reserves.iter() {
-            let data = pool.getReserveData(*reserve_address).call().await?;
-
-            reserve_data.push(data);
+        let mut multicall = Multicall::new();
+        for asset in reserves {
+            multicall.add(self.pool, &PoolV3::getReserveDataCall { asset });
         }
 
-        Ok(reserve_data)
+        let results = multicall.call(client).await?;
+
+        (0..multicall.len())
+            .map(|i| results.decode::<PoolV3::getReserveDataCall>(i))
+            .collect()
     }
 
     pub async fn get_user_data(&self, user: &str, client: &DynProvider) -> Result<AaveV3UserData> {
@@ -80,96 +89,265 @@ impl AaveV3 {
         &self,
         wallet: &EvmWallet,
         token: &str,
-        amount: f64,
-    ) -> Result<TransactionReceipt> {
+        amount: impl Into<Amount>,
+    ) -> Result<SendOutcome<TransactionReceipt>> {
         let poolv3 = PoolV3::new(self.pool, &wallet.client);
         let asset = Address::from_str(token)?;
         let amnt = wallet.format_token(amount, token).await?;
 
-        let sig = poolv3
-            .supply(asset, U256::from(amnt), wallet.pubkey, 0)
-            .send()
-            .await?
-            .get_receipt()
-            .await?;
+        let txn = poolv3
+            .supply(asset, amnt.raw, wallet.pubkey, 0)
+            .into_transaction_request();
 
-        Ok(sig)
+        send(wallet, txn).await
     }
 
-    pub async fn borrow(
+    /// Supplies with an EIP-2612 permit instead of a prior approval
+    pub async fn supply_with_permit(
         &self,
         wallet: &EvmWallet,
         token: &str,
-        amount: f64,
-    ) -> Result<TransactionReceipt> {
+        amount: impl Into<Amount>,
+    ) -> Result<SendOutcome<TransactionReceipt>> {
         let poolv3 = PoolV3::new(self.pool, &wallet.client);
         let asset = Address::from_str(token)?;
-        let variable_interest_rate = U256::from(2);
         let amnt = wallet.format_token(amount, token).await?;
+        let permit = wallet
+            .sign_permit(token, &self.pool.to_string(), amnt, permit_deadline()?)
+            .await?;
 
-        let sig = poolv3
-            .borrow(
+        let txn = poolv3
+            .supplyWithPermit(
                 asset,
-                U256::from(amnt),
-                variable_interest_rate,
-                0,
+                amnt.raw,
                 wallet.pubkey,
+                0,
+                permit.permit.deadline,
+                permit.v(),
+                permit.r(),
+                permit.s(),
             )
-            .send()
-            .await?
-            .get_receipt()
-            .await?;
+            .into_transaction_request();
+
+        send(wallet, txn).await
+    }
+
+    pub async fn borrow(
+        &self,
+        wallet: &EvmWallet,
+        token: &str,
+        amount: impl Into<Amount>,
+    ) -> Result<SendOutcome<TransactionReceipt>> {
+        let poolv3 = PoolV3::new(self.pool, &wallet.client);
+        let asset = Address::from_str(token)?;
+        let variable_interest_rate = U256::from(2);
+        let amnt = wallet.format_token(amount, token).await?;
+
+        let txn = poolv3
+            .borrow(asset, amnt.raw, variable_interest_rate, 0, wallet.pubkey)
+            .into_transaction_request();
 
-        Ok(sig)
+        send(wallet, txn).await
     }
 
     pub async fn repay(
         &self,
         wallet: &EvmWallet,
         token: &str,
-        amount: f64,
-    ) -> Result<TransactionReceipt> {
+        amount: impl Into<Amount>,
+    ) -> Result<SendOutcome<TransactionReceipt>> {
+        let poolv3 = PoolV3::new(self.pool, &wallet.client);
+        let asset = Address::from_str(token)?;
+        let variable_interest_rate = U256::from(2);
+        let amnt = wallet.format_token(amount, token).await?;
+
+        let txn = poolv3
+            .repay(asset, amnt.raw, variable_interest_rate, wallet.pubkey)
+            .into_transaction_request();
+
+        send(wallet, txn).await
+    }
+
+    /// Repays variable rate debt with an EIP-2612 permit instead of a
+    /// prior approval
+    pub async fn repay_with_permit(
+        &self,
+        wallet: &EvmWallet,
+        token: &str,
+        amount: impl Into<Amount>,
+    ) -> Result<SendOutcome<TransactionReceipt>> {
         let poolv3 = PoolV3::new(self.pool, &wallet.client);
         let asset = Address::from_str(token)?;
         let variable_interest_rate = U256::from(2);
         let amnt = wallet.format_token(amount, token).await?;
+        let permit = wallet
+            .sign_permit(token, &self.pool.to_string(), amnt, permit_deadline()?)
+            .await?;
 
-        let sig = poolv3
-            .repay(
+        let txn = poolv3
+            .repayWithPermit(
                 asset,
-                U256::from(amnt),
+                amnt.raw,
                 variable_interest_rate,
                 wallet.pubkey,
+                permit.permit.deadline,
+                permit.v(),
+                permit.r(),
+                permit.s(),
             )
-            .send()
-            .await?
-            .get_receipt()
-            .await?;
+            .into_transaction_request();
 
-        Ok(sig)
+        send(wallet, txn).await
     }
 
     pub async fn withdraw(
         &self,
         wallet: &EvmWallet,
         token: &str,
-        amount: f64,
-    ) -> Result<TransactionReceipt> {
+        amount: impl Into<Amount>,
+    ) -> Result<SendOutcome<TransactionReceipt>> {
         let poolv3 = PoolV3::new(self.pool, &wallet.client);
         let asset = Address::from_str(token)?;
         let amnt = wallet.format_token(amount, token).await?;
 
-        let sig = poolv3
-            .withdraw(asset, U256::from(amnt), wallet.pubkey)
-            .send()
-            .await?
-            .get_receipt()
-            .await?;
+        let txn = poolv3
+            .withdraw(asset, amnt.raw, wallet.pubkey)
+            .into_transaction_request();
 
-        Ok(sig)
+        send(wallet, txn).await
     }
 }
 
+// The pool reverts with the numeric codes of Aave's Errors library, this is
+// the full table as of v3.1 (48 was retired in v3.0)
+const POOL_ERRORS: &[(&str, &str)] = &[
+    ("1", "CALLER_NOT_POOL_ADMIN"),
+    ("2", "CALLER_NOT_EMERGENCY_ADMIN"),
+    ("3", "CALLER_NOT_POOL_OR_EMERGENCY_ADMIN"),
+    ("4", "CALLER_NOT_RISK_OR_POOL_ADMIN"),
+    ("5", "CALLER_NOT_ASSET_LISTING_OR_POOL_ADMIN"),
+    ("6", "CALLER_NOT_BRIDGE"),
+    ("7", "ADDRESSES_PROVIDER_NOT_REGISTERED"),
+    ("8", "INVALID_ADDRESSES_PROVIDER_ID"),
+    ("9", "NOT_CONTRACT"),
+    ("10", "CALLER_NOT_POOL_CONFIGURATOR"),
+    ("11", "CALLER_NOT_ATOKEN"),
+    ("12", "INVALID_ADDRESSES_PROVIDER"),
+    ("13", "INVALID_FLASHLOAN_EXECUTOR_RETURN"),
+    ("14", "RESERVE_ALREADY_ADDED"),
+    ("15", "NO_MORE_RESERVES_ALLOWED"),
+    ("16", "EMODE_CATEGORY_RESERVED"),
+    ("17", "INVALID_EMODE_CATEGORY_ASSIGNMENT"),
+    ("18", "RESERVE_LIQUIDITY_NOT_ZERO"),
+    ("19", "FLASHLOAN_PREMIUM_INVALID"),
+    ("20", "INVALID_RESERVE_PARAMS"),
+    ("21", "INVALID_EMODE_CATEGORY_PARAMS"),
+    ("22", "BRIDGE_PROTOCOL_FEE_INVALID"),
+    ("23", "CALLER_MUST_BE_POOL"),
+    ("24", "INVALID_MINT_AMOUNT"),
+    ("25", "INVALID_BURN_AMOUNT"),
+    ("26", "INVALID_AMOUNT"),
+    ("27", "RESERVE_INACTIVE"),
+    ("28", "RESERVE_FROZEN"),
+    ("29", "RESERVE_PAUSED"),
+    ("30", "BORROWING_NOT_ENABLED"),
+    ("31", "STABLE_BORROWING_NOT_ENABLED"),
+    ("32", "NOT_ENOUGH_AVAILABLE_USER_BALANCE"),
+    ("33", "INVALID_INTEREST_RATE_MODE_SELECTED"),
+    ("34", "COLLATERAL_BALANCE_IS_ZERO"),
+    ("35", "HEALTH_FACTOR_LOWER_THAN_LIQUIDATION_THRESHOLD"),
+    ("36", "COLLATERAL_CANNOT_COVER_NEW_BORROW"),
+    ("37", "COLLATERAL_SAME_AS_BORROWING_CURRENCY"),
+    ("38", "AMOUNT_BIGGER_THAN_MAX_LOAN_SIZE_STABLE"),
+    ("39", "NO_DEBT_OF_SELECTED_TYPE"),
+    ("40", "NO_EXPLICIT_AMOUNT_TO_REPAY_ON_BEHALF"),
+    ("41", "NO_OUTSTANDING_STABLE_DEBT"),
+    ("42", "NO_OUTSTANDING_VARIABLE_DEBT"),
+    ("43", "UNDERLYING_BALANCE_ZERO"),
+    ("44", "INTEREST_RATE_REBALANCE_CONDITIONS_NOT_MET"),
+    ("45", "HEALTH_FACTOR_NOT_BELOW_THRESHOLD"),
+    ("46", "COLLATERAL_CANNOT_BE_LIQUIDATED"),
+    ("47", "SPECIFIED_CURRENCY_NOT_BORROWED_BY_USER"),
+    ("49", "INCONSISTENT_FLASHLOAN_PARAMS"),
+    ("50", "BORROW_CAP_EXCEEDED"),
+    ("51", "SUPPLY_CAP_EXCEEDED"),
+    ("52", "UNBACKED_MINT_CAP_EXCEEDED"),
+    ("53", "DEBT_CEILING_EXCEEDED"),
+    ("54", "UNDERLYING_CLAIMABLE_RIGHTS_NOT_ZERO"),
+    ("55", "STABLE_DEBT_NOT_ZERO"),
+    ("56", "VARIABLE_DEBT_SUPPLY_NOT_ZERO"),
+    ("57", "LTV_VALIDATION_FAILED"),
+    ("58", "INCONSISTENT_EMODE_CATEGORY"),
+    ("59", "PRICE_ORACLE_SENTINEL_CHECK_FAILED"),
+    ("60", "ASSET_NOT_BORROWABLE_IN_ISOLATION"),
+    ("61", "RESERVE_ALREADY_INITIALIZED"),
+    ("62", "USER_IN_ISOLATION_MODE_OR_LTV_ZERO"),
+    ("63", "INVALID_LTV"),
+    ("64", "INVALID_LIQ_THRESHOLD"),
+    ("65", "INVALID_LIQ_BONUS"),
+    ("66", "INVALID_DECIMALS"),
+    ("67", "INVALID_RESERVE_FACTOR"),
+    ("68", "INVALID_BORROW_CAP"),
+    ("69", "INVALID_SUPPLY_CAP"),
+    ("70", "INVALID_LIQUIDATION_PROTOCOL_FEE"),
+    ("71", "INVALID_EMODE_CATEGORY"),
+    ("72", "INVALID_UNBACKED_MINT_CAP"),
+    ("73", "INVALID_DEBT_CEILING"),
+    ("74", "INVALID_RESERVE_INDEX"),
+    ("75", "ACL_ADMIN_CANNOT_BE_ZERO"),
+    ("76", "INCONSISTENT_PARAMS_LENGTH"),
+    ("77", "ZERO_ADDRESS_NOT_VALID"),
+    ("78", "INVALID_EXPIRATION"),
+    ("79", "INVALID_SIGNATURE"),
+    ("80", "OPERATION_NOT_SUPPORTED"),
+    ("81", "DEBT_CEILING_NOT_ZERO"),
+    ("82", "ASSET_NOT_LISTED"),
+    ("83", "INVALID_OPTIMAL_USAGE_RATIO"),
+    ("84", "INVALID_OPTIMAL_STABLE_TO_TOTAL_DEBT_RATIO"),
+    ("85", "UNDERLYING_CANNOT_BE_RESCUED"),
+    ("86", "ADDRESSES_PROVIDER_ALREADY_ADDED"),
+    ("87", "POOL_ADDRESSES_DO_NOT_MATCH"),
+    ("88", "STABLE_BORROWING_ENABLED"),
+    ("89", "SILOED_BORROWING_VIOLATION"),
+    ("90", "RESERVE_DEBT_NOT_ZERO"),
+    ("91", "FLASHLOAN_DISABLED"),
+    ("92", "INVALID_MAXRATE"),
+    ("93", "WITHDRAW_TO_ATOKEN"),
+    ("94", "SUPPLY_TO_ATOKEN"),
+    ("95", "SLOPE_2_MUST_BE_GTE_SLOPE_1"),
+    ("96", "CALLER_NOT_RISK_OR_POOL_OR_EMERGENCY_ADMIN"),
+    ("97", "LIQUIDATION_GRACE_SENTINEL_CHECK_FAILED"),
+    ("98", "INVALID_GRACE_PERIOD"),
+    ("99", "INVALID_FREEZE_STATE"),
+    ("100", "NOT_BORROWABLE_IN_EMODE"),
+];
+
+const PERMIT_LIFETIME: Duration = Duration::from_secs(20 * 60);
+
+fn permit_deadline() -> Result<u64> {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?;
+
+    Ok((now + PERMIT_LIFETIME).as_secs())
+}
+
+async fn send(
+    wallet: &EvmWallet,
+    txn: TransactionRequest,
+) -> Result<SendOutcome<TransactionReceipt>> {
+    wallet.sign_and_send(txn).await.map_err(|e| {
+        let name = e
+            .downcast_ref::<Revert>()
+            .and_then(|revert| revert.reason.as_deref())
+            .and_then(|code| POOL_ERRORS.iter().find(|(c, _)| *c == code))
+            .map(|(code, name)| format!("Aave error {code}: {name}"));
+
+        match name {
+            Some(name) => e.context(name),
+            None => e,
+        }
+    })
+}
+
 pub struct AaveV3UserData {
     pub total_collateral: f64,
     pub total_debt: f64,
@@ -3,12 +3,15 @@ use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use alloy::{
     sol,
-    sol_types::{SolStruct, eip712_domain},
+    sol_types::{Eip712Domain, eip712_domain},
 };
-use alloy_primitives::{Address, FixedBytes, U256, address, keccak256};
+use alloy_primitives::{Address, address, keccak256};
 use anyhow::Result;
 use bonanca_api_lib::defi::cow::{CowApi, CowQuote, CowSwapData, CowSwapOrder, CowSwapPlacedOrder};
-use bonanca_wallets::wallets::evm::EvmWallet;
+use bonanca_wallets::{
+    amount::{Amount, PRICE_DECIMALS},
+    wallets::evm::EvmWallet,
+};
 
 sol! {
     #[allow(missing_docs)]
@@ -34,11 +37,11 @@ impl Order {
             sellToken: Address::from_str(&quote.sell_token)?,
             buyToken: Address::from_str(&quote.buy_token)?,
             receiver: Address::from_str(&quote.receiver.as_ref().unwrap())?,
-            sellAmount: U256::from(quote.sell_amount),
-            buyAmount: U256::from(quote.buy_amount),
+            sellAmount: quote.sell_amount,
+            buyAmount: quote.buy_amount,
             validTo: quote.valid_to,
             appData: keccak256(quote.app_data.as_bytes()),
-            feeAmount: U256::from(quote.fee_amount),
+            feeAmount: quote.fee_amount,
             kind: quote.kind.clone(),
             partiallyFillable: quote.partially_fillable,
             sellTokenBalance: quote.sell_token_balance.clone(),
@@ -72,17 +75,13 @@ impl CoW {
         Ok(Self { api, chain_id })
     }
 
-    fn get_signing_hash(&self, order: Order) -> Result<FixedBytes<32>> {
-        let domain = eip712_domain! {
+    fn domain(&self) -> Eip712Domain {
+        eip712_domain! {
             name: "Gnosis Protocol",
             version: "v2",
             chain_id: self.chain_id,
             verifying_contract: address!("0x9008D19f58AAbD9eD0D60971565AA8510560ab41"),
-        };
-
-        let eip712_hash = order.eip712_signing_hash(&domain);
-
-        Ok(eip712_hash)
+        }
     }
 
     pub async fn get_order_info(&self, uid: &str) -> Result<CowSwapPlacedOrder> {
@@ -102,10 +101,10 @@ impl CoW {
         wallet: &EvmWallet,
         sell: &str,
         buy: &str,
-        amount: f64,
+        amount: impl Into<Amount>,
     ) -> Result<CowSwapOrder> {
         let taker = wallet.pubkey.to_string();
-        let big_amount = wallet.format_token(amount, sell).await?;
+        let big_amount = wallet.format_token(amount, sell).await?.raw;
 
         let data = CowSwapData {
             sell_token: sell.to_string(),
@@ -130,8 +129,7 @@ impl CoW {
         quote: CowSwapOrder,
     ) -> Result<String> {
         let order = Order::new(&quote.quote)?;
-        let hash = self.get_signing_hash(order)?;
-        let sig = wallet.sign_hash(&hash).await?;
+        let sig = wallet.sign_typed(&order, &self.domain()).await?;
         let signed_order = quote.sign(sig.to_string());
 
         let uid = self.api.post_swap_order(&signed_order).await?;
@@ -144,13 +142,13 @@ impl CoW {
         wallet: &EvmWallet,
         sell: &str,
         buy: &str,
-        sell_amount: f64,
-        buy_amount: f64,
+        sell_amount: impl Into<Amount>,
+        buy_amount: impl Into<Amount>,
         lifetime: Duration,
     ) -> Result<String> {
         let taker = wallet.pubkey.to_string();
-        let sell_amnt = wallet.format_token(sell_amount, sell).await?;
-        let buy_amnt = wallet.format_token(buy_amount, buy).await?;
+        let sell_amnt = wallet.format_token(sell_amount, sell).await?.raw;
+        let buy_amnt = wallet.format_token(buy_amount, buy).await?.raw;
         let valid_to = SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .unwrap()
@@ -167,8 +165,7 @@ impl CoW {
         );
 
         let order = Order::new(&quote)?;
-        let hash = self.get_signing_hash(order)?;
-        let sig = wallet.sign_hash(&hash).await?;
+        let sig = wallet.sign_typed(&order, &self.domain()).await?;
         let signed_order = quote.sign(sig.to_string(), taker);
 
         let uid = self.api.post_swap_order(&signed_order).await?;
@@ -181,13 +178,14 @@ impl CoW {
         wallet: &EvmWallet,
         sell: &str,
         buy: &str,
-        amount: f64,
-        price: f64,
+        amount: impl Into<Amount>,
+        price: impl Into<Amount>,
         lifetime: Duration,
     ) -> Result<String> {
         let taker = wallet.pubkey.to_string();
         let sell_amount = wallet.format_token(amount, sell).await?;
-        let buy_amount = wallet.format_token(amount / price, buy).await?;
+        let price = price.into().resolve(PRICE_DECIMALS)?;
+        let buy_amount = sell_amount.at_price(&price, wallet.token_decimals(buy).await?)?;
         let valid_to = SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .unwrap()
@@ -198,14 +196,13 @@ impl CoW {
             sell,
             buy,
             Some(taker.clone()),
-            sell_amount,
-            buy_amount,
+            sell_amount.raw,
+            buy_amount.raw,
             valid_to as u32,
         );
 
         let order = Order::new(&quote)?;
-        let hash = self.get_signing_hash(order)?;
-        let sig = wallet.sign_hash(&hash).await?;
+        let sig = wallet.sign_typed(&order, &self.domain()).await?;
         let signed_order = quote.sign(sig.to_string(), taker);
 
         let uid = self.api.post_swap_order(&signed_order).await?;
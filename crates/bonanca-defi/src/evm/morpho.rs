@@ -1,6 +1,7 @@
 use alloy::{
-    primitives::{Address, U256},
-    rpc::types::TransactionReceipt,
+    json_abi::JsonAbi,
+    primitives::Address,
+    rpc::types::{TransactionReceipt, TransactionRequest},
     sol,
 };
 use anyhow::Result;
@@ -8,8 +9,12 @@ use bonanca_api_lib::defi::morpho::{
     MorphoApi, user_data_query::UserDataQueryUserByAddressVaultPositions,
     vaults_v1_query::VaultsV1QueryVaultsItems,
 };
-use bonanca_wallets::wallets::evm::EvmWallet;
-use std::str::FromStr;
+use bonanca_wallets::{
+    amount::Amount,
+    simulate::{Revert, RevertDecoder, SendOutcome},
+    wallets::evm::EvmWallet,
+};
+use std::{str::FromStr, sync::LazyLock};
 
 sol! {
     #[allow(missing_docs)]
@@ -18,6 +23,16 @@ sol! {
     "src/evm/ABI/morpho_vault_v1.json"
 }
 
+// The vault reverts with custom errors, decoded here rather than on the wallet
+static VAULT_ERRORS: LazyLock<RevertDecoder> = LazyLock::new(|| {
+    let abi: JsonAbi = serde_json::from_str(include_str!("ABI/morpho_vault_v1.json"))
+        .expect("Morpho vault ABI is valid JSON");
+    let errors = RevertDecoder::new();
+    errors.register_abi(&abi);
+
+    errors
+});
+
 pub struct MorphoVaultV1 {
     api: MorphoApi,
 }
@@ -53,41 +68,53 @@ impl MorphoVaultV1 {
         &self,
         wallet: &EvmWallet,
         vault_address: &str,
-        amount: f64,
-    ) -> Result<TransactionReceipt> {
-        let addy = Address::from_str(vault_address).unwrap();
+        amount: impl Into<Amount>,
+    ) -> Result<SendOutcome<TransactionReceipt>> {
+        let addy = Address::from_str(vault_address)?;
         let vault = VaultV1::new(addy, &wallet.client);
         let token = vault.asset().call().await?;
         let amnt = wallet.format_token(amount, &token.to_string()).await?;
 
-        let sig = vault
-            .deposit(U256::from(amnt), wallet.pubkey)
-            .send()
-            .await?
-            .get_receipt()
-            .await?;
+        let txn = vault
+            .deposit(amnt.raw, wallet.pubkey)
+            .into_transaction_request();
 
-        Ok(sig)
+        send(wallet, txn).await
     }
 
     pub async fn withdraw(
         &self,
         wallet: &EvmWallet,
         vault_address: &str,
-        amount: f64,
-    ) -> Result<TransactionReceipt> {
-        let addy = Address::from_str(vault_address).unwrap();
+        amount: impl Into<Amount>,
+    ) -> Result<SendOutcome<TransactionReceipt>> {
+        let addy = Address::from_str(vault_address)?;
         let vault = VaultV1::new(addy, &wallet.client);
         let token = vault.asset().call().await?;
         let amnt = wallet.format_token(amount, &token.to_string()).await?;
 
-        let sig = vault
-            .withdraw(U256::from(amnt), wallet.pubkey, wallet.pubkey)
-            .send()
-            .await?
-            .get_receipt()
-            .await?;
+        let txn = vault
+            .withdraw(amnt.raw, wallet.pubkey, wallet.pubkey)
+            .into_transaction_request();
 
-        Ok(sig)
+        send(wallet, txn).await
     }
 }
+
+async fn send(
+    wallet: &EvmWallet,
+    txn: TransactionRequest,
+) -> Result<SendOutcome<TransactionReceipt>> {
+    wallet.sign_and_send(txn).await.map_err(|e| {
+        let name = e
+            .downcast_ref::<Revert>()
+            .filter(|revert| revert.reason.is_none())
+            .and_then(|revert| VAULT_ERRORS.decode(&revert.data))
+            .map(|reason| format!("Morpho vault error: {reason}"));
+
+        match name {
+            Some(name) => e.context(name),
+            None => e,
+        }
+    })
+}
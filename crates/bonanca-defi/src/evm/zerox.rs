@@ -1,21 +1,28 @@
 use alloy::{
     network::TransactionBuilder,
     rpc::types::{TransactionInput, TransactionReceipt, TransactionRequest},
+    sol_types::SolStruct,
 };
-use alloy_primitives::{Address, Bytes, Uint, hex::decode};
-use anyhow::Result;
+use alloy_primitives::{Address, B256, Bytes, U256, Uint, hex::decode};
+use anyhow::{Context, Result, bail};
 use bonanca_api_lib::defi::zerox::{Issues, ZeroXApi, ZeroXSwapQuote};
-use bonanca_wallets::wallets::evm::EvmWallet;
+use bonanca_wallets::{
+    amount::Amount,
+    permit::{PermitTransferFrom, TokenPermissions, permit2_domain},
+    simulate::SendOutcome,
+    wallets::evm::EvmWallet,
+};
 use std::str::FromStr;
 
 pub struct ZeroX {
     api: ZeroXApi,
+    chain_id: u16,
 }
 
 impl ZeroX {
     pub fn new(api_key: String, chain_id: u16) -> Self {
         let api = ZeroXApi::new(api_key, chain_id);
-        Self { api }
+        Self { api, chain_id }
     }
 
     pub async fn check_swap(
@@ -23,11 +30,11 @@ impl ZeroX {
         wallet: &EvmWallet,
         sell: &str,
         buy: &str,
-        amount: f64,
+        amount: impl Into<Amount>,
     ) -> Result<Issues> {
         let taker = wallet.get_pubkey()?;
 
-        let big_amount = wallet.format_token(amount, sell).await?;
+        let big_amount = wallet.format_token(amount, sell).await?.raw;
 
         let quote = self
             .api
@@ -42,11 +49,11 @@ impl ZeroX {
         wallet: &EvmWallet,
         sell: &str,
         buy: &str,
-        amount: f64,
+        amount: impl Into<Amount>,
     ) -> Result<ZeroXSwapQuote> {
         let taker = wallet.get_pubkey()?;
 
-        let big_amount = wallet.format_token(amount, sell).await?;
+        let big_amount = wallet.format_token(amount, sell).await?.raw;
 
         self.api.get_swap_quote(sell, buy, big_amount, &taker).await
     }
@@ -55,14 +62,8 @@ impl ZeroX {
         &self,
         wallet: &EvmWallet,
         quote: ZeroXSwapQuote,
-    ) -> Result<TransactionReceipt> {
-        if let Some(issues) = quote.issues.allowance {
-            panic!("Allowance issues: {:?}", issues);
-        };
-
-        if let Some(issues) = quote.issues.balance {
-            panic!("Balance issues: {:?}", issues);
-        };
+    ) -> Result<SendOutcome<TransactionReceipt>> {
+        check_quote(&quote)?;
 
         let taker = wallet.pubkey;
         let to_addy = Address::from_str(&quote.transaction.to)?;
@@ -70,7 +71,6 @@ impl ZeroX {
         let data = Bytes::copy_from_slice(&tmp);
         let value: Uint<256, 4> = quote.transaction.value.parse()?;
         let gas_limit: u64 = quote.transaction.gas.parse()?;
-        let gas_price: u128 = quote.transaction.gas_price.parse()?;
 
         let input = TransactionInput::new(data);
 
@@ -80,37 +80,97 @@ impl ZeroX {
             .with_from(taker)
             .with_to(to_addy)
             .with_value(value)
-            .with_gas_limit(gas_limit)
-            .with_max_fee_per_gas(gas_price);
+            .with_gas_limit(gas_limit);
 
-        let sig = wallet.sign_and_send(txn).await.unwrap();
+        let sig = wallet.sign_and_send(txn).await?;
 
         Ok(sig)
     }
 
-    pub async fn quick_swap(
+    /// Swaps through 0x's Permit2 flow, signing the transfer of the sell
+    /// token instead of approving the 0x contracts. Permit2 itself must be
+    /// approved once per token, see `EvmWallet::approve_permit2`.
+    pub async fn permit2_swap(
         &self,
         wallet: &EvmWallet,
         sell: &str,
         buy: &str,
-        amount: f64,
-    ) -> Result<TransactionReceipt> {
+        amount: impl Into<Amount>,
+    ) -> Result<SendOutcome<TransactionReceipt>> {
         let taker = wallet.get_pubkey()?;
 
-        let big_amount = wallet.format_token(amount, sell).await?;
+        let big_amount = wallet.format_token(amount, sell).await?.raw;
 
         let quote = self
             .api
-            .get_swap_quote(sell, buy, big_amount, &taker)
+            .get_permit2_quote(sell, buy, big_amount, &taker)
             .await?;
 
-        if let Some(issues) = quote.issues.allowance {
-            panic!("Allowance issues: {:?}", issues);
+        check_quote(&quote)?;
+
+        let permit2 = quote
+            .permit2
+            .as_ref()
+            .context("0x quote has no Permit2 message to sign")?;
+        let message = &permit2.eip712.message;
+
+        let permit = PermitTransferFrom {
+            permitted: TokenPermissions {
+                token: Address::from_str(&message.permitted.token)?,
+                amount: message.permitted.amount.parse()?,
+            },
+            spender: Address::from_str(&message.spender)?,
+            nonce: message.nonce.parse()?,
+            deadline: message.deadline.parse()?,
         };
 
-        if let Some(issues) = quote.issues.balance {
-            panic!("Balance issues: {:?}", issues);
-        };
+        // Only sign what we can rebuild ourselves
+        let hash = permit.eip712_signing_hash(&permit2_domain(self.chain_id as u64));
+        if hash != B256::from_str(&permit2.hash)? {
+            bail!("0x Permit2 message does not match its hash, refusing to sign");
+        }
+
+        let signed = wallet.sign_permit2_transfer(permit).await?;
+        let signature = signed.signature_bytes();
+
+        // The settler reads the signature from the end of the calldata,
+        // prefixed by its length as a uint256
+        let mut data = decode(&quote.transaction.data)?;
+        data.extend_from_slice(&U256::from(signature.len()).to_be_bytes::<32>());
+        data.extend_from_slice(&signature);
+
+        let to_addy = Address::from_str(&quote.transaction.to)?;
+        let value: U256 = quote.transaction.value.parse()?;
+        let gas_limit: u64 = quote.transaction.gas.parse()?;
+
+        let txn = TransactionRequest::default()
+            .input(TransactionInput::new(Bytes::from(data)))
+            .with_input_and_data()
+            .with_from(wallet.pubkey)
+            .with_to(to_addy)
+            .with_value(value)
+            .with_gas_limit(gas_limit);
+
+        wallet.sign_and_send(txn).await
+    }
+
+    pub async fn quick_swap(
+        &self,
+        wallet: &EvmWallet,
+        sell: &str,
+        buy: &str,
+        amount: impl Into<Amount>,
+    ) -> Result<SendOutcome<TransactionReceipt>> {
+        let taker = wallet.get_pubkey()?;
+
+        let big_amount = wallet.format_token(amount, sell).await?.raw;
+
+        let quote = self
+            .api
+            .get_swap_quote(sell, buy, big_amount, &taker)
+            .await?;
+
+        check_quote(&quote)?;
 
         let taker_addy = Address::from_str(&taker)?;
         let to_addy = Address::from_str(&quote.transaction.to)?;
@@ -118,7 +178,6 @@ impl ZeroX {
         let data = Bytes::copy_from_slice(&tmp);
         let value: Uint<256, 4> = quote.transaction.value.parse()?;
         let gas_limit: u64 = quote.transaction.gas.parse()?;
-        let gas_price: u128 = quote.transaction.gas_price.parse()?;
 
         let input = TransactionInput::new(data);
 
@@ -128,11 +187,36 @@ impl ZeroX {
             .with_from(taker_addy)
             .with_to(to_addy)
             .with_value(value)
-            .with_gas_limit(gas_limit)
-            .with_max_fee_per_gas(gas_price);
+            .with_gas_limit(gas_limit);
 
-        let sig = wallet.sign_and_send(txn).await.unwrap();
+        let sig = wallet.sign_and_send(txn).await?;
 
         Ok(sig)
     }
 }
+
+fn check_quote(quote: &ZeroXSwapQuote) -> Result<()> {
+    if !quote.liquidity_available {
+        bail!("0x found no liquidity for this swap");
+    }
+
+    if let Some(allowance) = &quote.issues.allowance {
+        bail!(
+            "Sell token allowance too low: {} may spend {} but the swap sells {}, approve it first",
+            allowance.spender,
+            allowance.actual,
+            quote.sell_amount
+        );
+    }
+
+    if let Some(balance) = &quote.issues.balance {
+        bail!(
+            "Balance of {} too low: have {} but the swap needs {}",
+            balance.token,
+            balance.actual,
+            balance.expected
+        );
+    }
+
+    Ok(())
+}
@@ -7,7 +7,10 @@ use bincode::deserialize;
 use bonanca_api_lib::defi::jupiter::{
     JupEarnInput, JupLimitOrder, JupLimitParams, JupiterApi, JupiterLendMarket, JupiterSwapQuote,
 };
-use bonanca_wallets::wallets::solana::{SolTxnReceipt, SolWallet};
+use bonanca_wallets::{
+    amount::{Amount, PRICE_DECIMALS},
+    wallets::solana::{SolTxnReceipt, SolWallet},
+};
 use solana_sdk::transaction::VersionedTransaction;
 
 fn make_txn(encoded_txn: String) -> Result<VersionedTransaction> {
@@ -43,9 +46,9 @@ impl Jupiter {
         wallet: &SolWallet,
         sell: &str,
         buy: &str,
-        amount: f64,
+        amount: impl Into<Amount>,
     ) -> Result<JupiterSwapQuote> {
-        let big_amount = wallet.format_token(amount, sell).await?;
+        let big_amount = wallet.format_token(amount, sell).await?.to_u64()?;
         self.api.get_swap_quote(sell, buy, big_amount).await
     }
 
@@ -65,10 +68,10 @@ impl Jupiter {
         wallet: &SolWallet,
         sell: &str,
         buy: &str,
-        amount: f64,
+        amount: impl Into<Amount>,
     ) -> Result<SolTxnReceipt> {
         let taker = wallet.get_pubkey()?;
-        let big_amount = wallet.format_token(amount, sell).await?;
+        let big_amount = wallet.format_token(amount, sell).await?.to_u64()?;
         let swap_quote = self.api.get_swap_quote(sell, buy, big_amount).await?;
 
         let swap_order = self.api.get_swap_order(&taker, swap_quote).await?;
@@ -84,12 +87,12 @@ impl Jupiter {
         wallet: &SolWallet,
         sell: &str,
         buy: &str,
-        sell_amount: f64,
-        buy_amount: f64,
+        sell_amount: impl Into<Amount>,
+        buy_amount: impl Into<Amount>,
         lifetime: Duration,
     ) -> Result<SolTxnReceipt> {
-        let make = wallet.format_token(sell_amount, sell).await?;
-        let take = wallet.format_token(buy_amount, buy).await?;
+        let make = wallet.format_token(sell_amount, sell).await?.to_u64()?;
+        let take = wallet.format_token(buy_amount, buy).await?.to_u64()?;
         let now = wallet.get_timestamp().await? as u64;
 
         let body = JupLimitOrder {
@@ -116,12 +119,16 @@ impl Jupiter {
         wallet: &SolWallet,
         sell: &str,
         buy: &str,
-        amount: f64,
-        price: f64,
+        amount: impl Into<Amount>,
+        price: impl Into<Amount>,
         lifetime: Duration,
     ) -> Result<SolTxnReceipt> {
         let make = wallet.format_token(amount, sell).await?;
-        let take = wallet.format_token(amount / price, buy).await?;
+        let price = price.into().resolve(PRICE_DECIMALS)?;
+        let take = make
+            .at_price(&price, wallet.token_decimals(buy).await?)?
+            .to_u64()?;
+        let make = make.to_u64()?;
         let now = wallet.get_timestamp().await? as u64;
 
         let body = JupLimitOrder {
@@ -151,9 +158,9 @@ impl Jupiter {
         &self,
         wallet: &SolWallet,
         token: &str,
-        amount: f64,
+        amount: impl Into<Amount>,
     ) -> Result<SolTxnReceipt> {
-        let big_amount = wallet.format_token(amount, token).await?;
+        let big_amount = wallet.format_token(amount, token).await?.to_u64()?;
         let body = JupEarnInput {
             asset: token.to_string(),
             signer: wallet.pubkey.to_string(),
@@ -171,9 +178,9 @@ impl Jupiter {
         &self,
         wallet: &SolWallet,
         token: &str,
-        amount: f64,
+        amount: impl Into<Amount>,
     ) -> Result<SolTxnReceipt> {
-        let big_amount = wallet.format_token(amount, token).await?;
+        let big_amount = wallet.format_token(amount, token).await?.to_u64()?;
         let body = JupEarnInput {
             asset: token.to_string(),
             signer: wallet.pubkey.to_string(),
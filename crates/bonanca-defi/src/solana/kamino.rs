@@ -8,7 +8,7 @@ use anchor_client::{
 use anchor_lang::prelude::*;
 use anyhow::Result;
 use bonanca_api_lib::defi::kamino::{KVaultInfo, KVaultPosition, KaminoApi};
-use bonanca_wallets::wallets::solana::SolWallet;
+use bonanca_wallets::{amount::Amount, wallets::solana::SolWallet};
 use std::{rc::Rc, str::FromStr};
 
 const SYSVAR: Pubkey = Pubkey::from_str_const("Sysvar1nstructions1111111111111111111111111");
@@ -86,7 +86,7 @@ impl Kamino {
         &self,
         wallet: &SolWallet,
         vault_data: &KVaultInfo,
-        amount: f64,
+        amount: impl Into<Amount>,
     ) -> Result<()> {
         // These two conversion are because Anchor and solana_sdk use different versions
         let payer = Keypair::from_seed(wallet.key_pair.as_ref().unwrap().secret_bytes())
@@ -110,7 +110,8 @@ impl Kamino {
 
         let amnt = wallet
             .format_token(amount, &vault_data.state.token_mint)
-            .await?;
+            .await?
+            .to_u64()?;
 
         let mut remaining_accounts = Vec::new();
 
@@ -169,7 +170,7 @@ impl Kamino {
         &self,
         wallet: &SolWallet,
         vault_data: &KVaultInfo,
-        amount: f64,
+        amount: impl Into<Amount>,
     ) -> Result<()> {
         // These two conversion are because Anchor and solana_sdk use different versions
         let payer = Keypair::from_seed(wallet.key_pair.as_ref().unwrap().secret_bytes())
@@ -199,7 +200,8 @@ impl Kamino {
 
         let amnt = wallet
             .format_token(amount, &vault_data.state.token_mint)
-            .await?;
+            .await?
+            .to_u64()?;
 
         let global_config =
             Pubkey::find_program_address(&["global_config".as_bytes()], &program.id()).0;
@@ -0,0 +1,100 @@
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::chains::Chain;
+
+/// A derived account recorded in `KeyVault::chain_keys`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Account {
+    pub chain: Chain,
+    pub path: String,
+    pub address: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    /// Unix timestamp (seconds), missing for accounts from older vaults
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<u64>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+impl Account {
+    pub fn new(chain: Chain, path: &str, address: &str) -> Self {
+        let created_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .ok();
+
+        Self {
+            chain,
+            path: path.to_string(),
+            address: address.to_string(),
+            label: None,
+            created_at,
+            tags: Vec::new(),
+        }
+    }
+
+    /// Account for a bare `path -> address` entry, guessing the chain from
+    /// the coin type in the path, or else the address format.
+    fn from_legacy(path: &str, address: &str) -> Self {
+        let chain = Chain::from_path(path).unwrap_or(if address.starts_with("0x") {
+            Chain::Evm
+        } else {
+            Chain::Solana
+        });
+
+        Self {
+            chain,
+            path: path.to_string(),
+            address: address.to_string(),
+            label: None,
+            created_at: None,
+            tags: Vec::new(),
+        }
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t == tag)
+    }
+
+    /// EVM addresses compare case-insensitively, since checksums only
+    /// change the case.
+    pub fn matches_address(&self, address: &str) -> bool {
+        match self.chain {
+            Chain::Evm => self.address.eq_ignore_ascii_case(address),
+            Chain::Solana => self.address == address,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum AccountEntry {
+    Legacy(String),
+    Record(Account),
+}
+
+/// Reads `chain_keys` in either the current format or the old flat
+/// `path -> address` map.
+pub(crate) fn deserialize_accounts<'de, D>(
+    deserializer: D,
+) -> std::result::Result<HashMap<String, Account>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let entries: HashMap<String, AccountEntry> = HashMap::deserialize(deserializer)?;
+
+    Ok(entries
+        .into_iter()
+        .map(|(path, entry)| {
+            let account = match entry {
+                AccountEntry::Legacy(address) => Account::from_legacy(&path, &address),
+                AccountEntry::Record(account) => account,
+            };
+            (path, account)
+        })
+        .collect())
+}
@@ -0,0 +1,192 @@
+use std::fmt;
+use std::str::FromStr;
+
+use bip32::{ChildNumber, Prefix, XPub};
+use k256::ecdsa::VerifyingKey;
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Keccak256};
+
+use crate::error::{KeyVaultError, Result};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub enum Chain {
+    Evm,
+    Solana,
+}
+
+impl Chain {
+    pub fn account_path(&self, account: u32) -> String {
+        match self {
+            Self::Evm => format!("m/44'/60'/{account}'"),
+            Self::Solana => format!("m/44'/501'/{account}'"),
+        }
+    }
+
+    pub fn child_path(&self, child: u32) -> String {
+        DerivationScheme::default_for(*self).path(child)
+    }
+
+    /// Chain of a BIP44 path, from its coin type
+    pub fn from_path(path: &str) -> Option<Self> {
+        match path.split('/').nth(2)?.trim_end_matches('\'') {
+            "60" => Some(Self::Evm),
+            "501" => Some(Self::Solana),
+            _ => None,
+        }
+    }
+
+    pub fn address_from_secret(&self, secret: &[u8; 32]) -> Result<String> {
+        match self {
+            Self::Evm => {
+                let signing_key = k256::ecdsa::SigningKey::from_bytes(secret.into())
+                    .map_err(|e| KeyVaultError::Derivation(e.to_string()))?;
+
+                Ok(evm_address(signing_key.verifying_key()))
+            }
+            Self::Solana => {
+                let signing_key = ed25519_dalek_bip32::SigningKey::from_bytes(secret);
+
+                Ok(solana_address(&signing_key.verifying_key().to_bytes()))
+            }
+        }
+    }
+}
+
+impl FromStr for Chain {
+    type Err = KeyVaultError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "evm" => Ok(Self::Evm),
+            "solana" => Ok(Self::Solana),
+            _ => Err(KeyVaultError::UnknownChain(s.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for Chain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Evm => write!(f, "EVM"),
+            Self::Solana => write!(f, "Solana"),
+        }
+    }
+}
+
+/// Path conventions used by common wallets, `child` is the index that
+/// changes from one account to the next.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub enum DerivationScheme {
+    /// `m/44'/60'/{child}'/0/0`
+    LedgerLive,
+    /// `m/44'/60'/0'/0/{child}`
+    MetaMask,
+    /// `m/44'/501'/{child}'`
+    SolanaCli,
+    /// `m/44'/501'/{child}'/0'`
+    Phantom,
+}
+
+impl DerivationScheme {
+    pub fn default_for(chain: Chain) -> Self {
+        match chain {
+            Chain::Evm => Self::LedgerLive,
+            Chain::Solana => Self::Phantom,
+        }
+    }
+
+    pub fn chain(&self) -> Chain {
+        match self {
+            Self::LedgerLive | Self::MetaMask => Chain::Evm,
+            Self::SolanaCli | Self::Phantom => Chain::Solana,
+        }
+    }
+
+    pub fn path(&self, child: u32) -> String {
+        match self {
+            Self::LedgerLive => format!("m/44'/60'/{child}'/0/0"),
+            Self::MetaMask => format!("m/44'/60'/0'/0/{child}"),
+            Self::SolanaCli => format!("m/44'/501'/{child}'"),
+            Self::Phantom => format!("m/44'/501'/{child}'/0'"),
+        }
+    }
+}
+
+impl FromStr for DerivationScheme {
+    type Err = KeyVaultError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().replace(['-', '_', ' '], "").as_str() {
+            "ledgerlive" => Ok(Self::LedgerLive),
+            "metamask" => Ok(Self::MetaMask),
+            "solanacli" => Ok(Self::SolanaCli),
+            "phantom" => Ok(Self::Phantom),
+            _ => Err(KeyVaultError::UnknownScheme(s.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for DerivationScheme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::LedgerLive => write!(f, "Ledger Live"),
+            Self::MetaMask => write!(f, "MetaMask"),
+            Self::SolanaCli => write!(f, "Solana CLI"),
+            Self::Phantom => write!(f, "Phantom"),
+        }
+    }
+}
+
+pub fn encode_xpub(xpub: &XPub) -> String {
+    xpub.to_string(Prefix::XPUB)
+}
+
+/// Derives the EVM address at `path`, relative to the account `xpub`.
+///
+/// Only non-hardened steps (e.g. `0/5`) can be derived from a public key.
+pub fn evm_address_from_xpub(xpub: &str, path: &str) -> Result<String> {
+    let mut key = XPub::from_str(xpub).map_err(|e| KeyVaultError::Derivation(e.to_string()))?;
+
+    for step in path.split('/').filter(|s| !s.is_empty()) {
+        let index: u32 = step.parse().map_err(|_| {
+            KeyVaultError::Derivation(format!(
+                "Cannot derive {step} from an xpub, only non-hardened steps are allowed"
+            ))
+        })?;
+        let child =
+            ChildNumber::new(index, false).map_err(|e| KeyVaultError::Derivation(e.to_string()))?;
+
+        key = key
+            .derive_child(child)
+            .map_err(|e| KeyVaultError::Derivation(e.to_string()))?;
+    }
+
+    Ok(evm_address(key.public_key()))
+}
+
+/// EIP-55 checksummed address of a secp256k1 public key.
+pub fn evm_address(key: &VerifyingKey) -> String {
+    let point = key.to_encoded_point(false);
+    let hash = Keccak256::digest(&point.as_bytes()[1..]);
+    let address = hex::encode(&hash[12..]);
+
+    let checksum = Keccak256::digest(address.as_bytes());
+    let checksummed: String = address
+        .chars()
+        .enumerate()
+        .map(|(i, c)| {
+            let nibble = (checksum[i / 2] >> (4 * (1 - i % 2))) & 0xf;
+            if nibble >= 8 {
+                c.to_ascii_uppercase()
+            } else {
+                c
+            }
+        })
+        .collect();
+
+    format!("0x{checksummed}")
+}
+
+pub fn solana_address(pubkey: &[u8; 32]) -> String {
+    bs58::encode(pubkey).into_string()
+}
@@ -0,0 +1,59 @@
+use std::io::{self, BufRead, Write};
+
+use crate::error::{KeyVaultError, Result};
+
+type DisplayCallback = Box<dyn Fn(&str) -> anyhow::Result<bool> + Send + Sync>;
+
+/// How a newly generated mnemonic is shown to the user
+pub enum MnemonicDisplay {
+    /// Prints the mnemonic, waits for Enter, then clears the screen and
+    /// scrollback
+    Terminal,
+    /// Hands the mnemonic to your own code, which returns whether the user
+    /// confirmed they stored it
+    Callback(DisplayCallback),
+}
+
+impl MnemonicDisplay {
+    pub fn callback<F>(f: F) -> Self
+    where
+        F: Fn(&str) -> anyhow::Result<bool> + Send + Sync + 'static,
+    {
+        Self::Callback(Box::new(f))
+    }
+
+    pub fn show(&self, mnemonic: &str) -> Result<()> {
+        let confirmed = match self {
+            Self::Terminal => show_in_terminal(mnemonic)?,
+            Self::Callback(f) => {
+                f(mnemonic).map_err(|e| KeyVaultError::MnemonicDisplay(e.to_string()))?
+            }
+        };
+
+        if !confirmed {
+            return Err(KeyVaultError::MnemonicDisplay(
+                "Mnemonic backup was not confirmed".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+fn show_in_terminal(mnemonic: &str) -> Result<bool> {
+    let mut stdout = io::stdout().lock();
+
+    writeln!(stdout, "Your mnemonic is:\n")?;
+    writeln!(stdout, "\t{mnemonic}\n")?;
+    write!(stdout, "Safely store this offline, then press Enter")?;
+    stdout.flush()?;
+
+    let mut line = String::new();
+    io::stdin().lock().read_line(&mut line)?;
+
+    // Clear the screen and the scrollback, then home the cursor
+    write!(stdout, "\x1b[2J\x1b[3J\x1b[H")?;
+    stdout.flush()?;
+
+    Ok(true)
+}
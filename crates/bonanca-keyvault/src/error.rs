@@ -0,0 +1,74 @@
+use thiserror::Error;
+
+use crate::chains::Chain;
+
+pub type Result<T> = std::result::Result<T, KeyVaultError>;
+
+#[derive(Debug, Error)]
+pub enum KeyVaultError {
+    #[error("Wrong password")]
+    WrongPassword,
+
+    #[error("Vault cipher text is corrupt")]
+    CorruptCiphertext,
+
+    #[error("Vault is corrupt: {0}")]
+    CorruptVault(String),
+
+    #[error("Unsupported cipher: {0}")]
+    UnsupportedCipher(String),
+
+    #[error("Unsupported kdf: {0}")]
+    UnsupportedKdf(String),
+
+    #[error("Unsupported vault version: {0}")]
+    UnsupportedVersion(u32),
+
+    #[error("Language not supported: {0}")]
+    UnknownLanguage(String),
+
+    #[error("Unknown chain: {0}")]
+    UnknownChain(String),
+
+    #[error("Unknown derivation scheme: {0}")]
+    UnknownScheme(String),
+
+    #[error("No address or xpub in keyvault for {0}, add it with KeyVault::precompute_addresses")]
+    UnknownPath(String),
+
+    #[error("No {0} key labelled {1}")]
+    UnknownKey(Chain, String),
+
+    #[error("A key labelled {0} already exists")]
+    DuplicateLabel(String),
+
+    #[error("Invalid keystore: {0}")]
+    Keystore(String),
+
+    #[error("Keyvault session is locked")]
+    Locked,
+
+    #[error("Could not get password: {0}")]
+    Password(String),
+
+    #[error("Failed to encrypt seed: {0}")]
+    Encryption(String),
+
+    #[error("Could not show mnemonic: {0}")]
+    MnemonicDisplay(String),
+
+    #[error("Invalid mnemonic: {0}")]
+    Mnemonic(#[from] bip39::Error),
+
+    #[error("Invalid shares: {0}")]
+    Shares(String),
+
+    #[error("Key derivation failed: {0}")]
+    Derivation(String),
+
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
+    #[error("Could not parse keyvault: {0}")]
+    Parse(#[from] serde_json::Error),
+}
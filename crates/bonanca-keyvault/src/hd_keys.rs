@@ -1,92 +1,178 @@
 use std::collections::HashMap;
 use std::str::FromStr;
 
-use anyhow::Result;
-use argon2::password_hash::SaltString;
 use bip32::XPrv;
 use bip39::{Language, Mnemonic};
 use ed25519_dalek_bip32::{DerivationPath, ExtendedSigningKey};
-use zeroize::ZeroizeOnDrop;
+use zeroize::{Zeroize, Zeroizing};
 
-use crate::keyvault::{CipherParams, KdfParams, KeyVault, Vault};
-use crate::utils::{encrypt_seed, hash_password};
+use crate::accounts::Account;
+use crate::chains::{Chain, encode_xpub, evm_address, solana_address};
+use crate::display::MnemonicDisplay;
+use crate::error::{KeyVaultError, Result};
+use crate::keyvault::{KdfParams, KeyVault, Vault, new_nonce};
+use crate::password::PasswordProvider;
+use crate::secret::{PrivateKey, Seed};
+use crate::shamir::{combine_shares, split_secret};
 
-#[derive(ZeroizeOnDrop)]
 pub struct HDkeys {
-    pub seed: [u8; 64],
+    pub seed: Seed,
 }
 
 impl HDkeys {
-    pub fn new(language: Language, word_count: usize) -> Self {
+    pub fn new(
+        language: Language,
+        word_count: usize,
+        passphrase: Option<&str>,
+        display: &MnemonicDisplay,
+    ) -> Result<Self> {
         let mut rng = bip39::rand::thread_rng();
-        let mnemonic = Mnemonic::generate_in_with(&mut rng, language, word_count)
-            .expect("Failed to generate mnemonic");
+        let mnemonic = Mnemonic::generate_in_with(&mut rng, language, word_count)?;
 
-        // TODO: Ensure this is safest way of 1-time
-        // display of mnemonic
-        println!("Your mnemonic is:\n");
-        println!("\t{}\n", mnemonic);
-        println!("Safely store this offline, then clear the terminal");
+        display.show(&Zeroizing::new(mnemonic.to_string()))?;
 
-        let seed = mnemonic.to_seed_normalized("");
+        let seed = Seed::new(&mut mnemonic.to_seed_normalized(passphrase.unwrap_or("")));
 
-        Self { seed }
+        Ok(Self { seed })
     }
 
-    pub fn from_mnemonic(mnemonic_str: &str) -> Self {
-        let mnemonic = Mnemonic::from_str(mnemonic_str).unwrap();
+    pub fn from_mnemonic(mnemonic_str: &str, passphrase: Option<&str>) -> Result<Self> {
+        let mnemonic = Mnemonic::from_str(mnemonic_str)?;
 
-        let seed = mnemonic.to_seed_normalized("");
+        let seed = Seed::new(&mut mnemonic.to_seed_normalized(passphrase.unwrap_or("")));
 
-        Self { seed }
+        Ok(Self { seed })
     }
 
-    pub fn get_keyvault(&self, salt: &SaltString, nonce: &str, password: &str) -> Result<KeyVault> {
-        let mac = hash_password(&password, salt)?;
+    pub fn from_shares<S: AsRef<str>>(shares: &[S], passphrase: Option<&str>) -> Result<Self> {
+        let secret = combine_shares(shares, passphrase.unwrap_or(""))?;
 
-        let kdf_params = KdfParams {
-            key_length: 32,
-            n: 600_000, // OWASP recommendation
-            salt: salt.as_str().to_string(),
-        };
+        let seed = Seed::from_slice(&secret).map_err(|_| {
+            KeyVaultError::Shares(format!(
+                "Recovered a {}-byte secret, but vault seeds are 64 bytes",
+                secret.len()
+            ))
+        })?;
 
-        let cipher_text = encrypt_seed(self.seed, &password, nonce, &kdf_params)?;
+        Ok(Self { seed })
+    }
 
-        let cipher_params = CipherParams {
-            nonce: nonce.to_string(),
-        };
+    pub fn split_shares(
+        &self,
+        threshold: u8,
+        count: u8,
+        passphrase: Option<&str>,
+    ) -> Result<Vec<String>> {
+        let mut groups = split_secret(
+            self.seed.expose(),
+            passphrase.unwrap_or(""),
+            1,
+            &[(threshold, count)],
+            true,
+            1,
+        )?;
+
+        Ok(groups.remove(0))
+    }
 
-        let vault = Vault {
-            cipher: "aes256-gcm".to_string(),
-            cipher_params,
-            cipher_text,
-            kdf: "pbkdf2".to_string(),
-            kdf_params,
-            mac,
-            salt: salt.as_str().to_string(),
-        };
+    pub fn new_keyvault(&self, password: &PasswordProvider) -> Result<KeyVault> {
+        let nonce = new_nonce();
 
-        let chain_keys: HashMap<String, String> = HashMap::new();
+        let mut pass = password.get_password("Set Keyvault Password: ")?;
+
+        let key_vault = self.get_keyvault(KdfParams::default(), &nonce, &pass);
+
+        pass.zeroize();
+
+        key_vault
+    }
 
-        let key_vault = KeyVault { vault, chain_keys };
+    pub fn get_keyvault(
+        &self,
+        kdf_params: KdfParams,
+        nonce: &str,
+        password: &str,
+    ) -> Result<KeyVault> {
+        let vault = Vault::encrypt(&self.seed, password, nonce, kdf_params)?;
+
+        let chain_keys: HashMap<String, Account> = HashMap::new();
+
+        // Account xpubs let EVM addresses be viewed without the password
+        let xpubs = (0..XPUB_ACCOUNTS)
+            .map(|account| {
+                let path = Chain::Evm.account_path(account);
+                let xpub = self.derive_xpub(&path)?;
+                Ok((path, xpub))
+            })
+            .collect::<Result<HashMap<String, String>>>()?;
+
+        let key_vault = KeyVault {
+            vault,
+            chain_keys,
+            xpubs,
+            imported_keys: HashMap::new(),
+        };
 
         Ok(key_vault)
     }
 
-    pub fn derive_ed25519_child_prvkey(&self, path: String) -> Result<[u8; 32]> {
-        let master_key = ExtendedSigningKey::from_seed(&self.seed)?;
+    pub fn derive_xpub(&self, path: &str) -> Result<String> {
+        let derivation_path = path.parse().map_err(derivation_error)?;
+        let account_key = XPrv::derive_from_path(self.seed.expose(), &derivation_path)
+            .map_err(derivation_error)?;
+
+        Ok(encode_xpub(&account_key.public_key()))
+    }
+
+    pub fn derive_address(&self, chain: Chain, path: &str) -> Result<String> {
+        match chain {
+            Chain::Evm => {
+                let derivation_path = path.parse().map_err(derivation_error)?;
+                let child_key = XPrv::derive_from_path(self.seed.expose(), &derivation_path)
+                    .map_err(derivation_error)?;
+
+                Ok(evm_address(child_key.public_key().public_key()))
+            }
+            Chain::Solana => {
+                let master_key =
+                    ExtendedSigningKey::from_seed(self.seed.expose()).map_err(derivation_error)?;
+                let derivation_path: DerivationPath = path.parse().map_err(derivation_error)?;
+                let child_key = master_key
+                    .derive(&derivation_path)
+                    .map_err(derivation_error)?;
+
+                Ok(solana_address(&child_key.verifying_key().to_bytes()))
+            }
+        }
+    }
 
-        let derivation_path: DerivationPath = path.parse()?;
+    pub fn derive_ed25519_child_prvkey(&self, path: String) -> Result<PrivateKey> {
+        let master_key =
+            ExtendedSigningKey::from_seed(self.seed.expose()).map_err(derivation_error)?;
 
-        let child_key = master_key.derive(&derivation_path)?;
+        let derivation_path: DerivationPath = path.parse().map_err(derivation_error)?;
+
+        let child_key = master_key
+            .derive(&derivation_path)
+            .map_err(derivation_error)?;
         let secret_key = child_key.signing_key;
 
-        Ok(secret_key.to_bytes())
+        Ok(PrivateKey::new(&mut secret_key.to_bytes()))
     }
 
-    pub fn derive_secp256k1_child_prvkey(&self, path: String) -> Result<[u8; 32]> {
-        let child_key = XPrv::derive_from_path(&self.seed, &path.parse()?)?;
+    pub fn derive_secp256k1_child_prvkey(&self, path: String) -> Result<PrivateKey> {
+        let derivation_path = path.parse().map_err(derivation_error)?;
+        let child_key = XPrv::derive_from_path(self.seed.expose(), &derivation_path)
+            .map_err(derivation_error)?;
 
-        Ok(child_key.to_bytes())
+        Ok(PrivateKey::new(&mut child_key.to_bytes()))
     }
 }
+
+/// Number of EVM accounts whose xpub is stored in a new keyvault. Later
+/// accounts need `KeyVault::precompute_addresses` before they can be viewed.
+pub const XPUB_ACCOUNTS: u32 = 10;
+
+fn derivation_error(e: impl std::fmt::Display) -> KeyVaultError {
+    KeyVaultError::Derivation(e.to_string())
+}
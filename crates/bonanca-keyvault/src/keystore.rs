@@ -0,0 +1,296 @@
+//! Interop with Ethereum V3 keystores (geth, foundry) and Solana CLI
+//! keypair files (`solana-keygen`)
+
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::path::Path;
+
+use aes::Aes128;
+use aes_gcm::aead::{OsRng, rand_core::RngCore};
+use ctr::cipher::{KeyIvInit, StreamCipher};
+use ed25519_dalek_bip32::SigningKey;
+use pbkdf2::pbkdf2_hmac;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use sha3::{Digest, Keccak256};
+use subtle::ConstantTimeEq;
+use zeroize::Zeroizing;
+
+use crate::chains::Chain;
+use crate::error::{KeyVaultError, Result};
+use crate::secret::PrivateKey;
+use crate::storage::write_atomic;
+
+type Aes128Ctr = ctr::Ctr128BE<Aes128>;
+
+// Same scrypt cost as foundry's `cast wallet new`
+const SCRYPT_LOG_N: u8 = 13;
+const SCRYPT_R: u32 = 8;
+const SCRYPT_P: u32 = 1;
+
+// Most scrypt work (N * r * p) accepted from a keystore file, geth's
+// "standard" cost of N = 2^18, r = 8, p = 1. Memory is 128 * N * r bytes.
+const MAX_SCRYPT_WORK: u64 = 1 << 21;
+
+/// Web3 Secret Storage (V3) keystore
+#[derive(Debug, Deserialize, Serialize)]
+pub struct EthKeystore {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+    #[serde(alias = "Crypto")]
+    pub crypto: KeystoreCrypto,
+    pub id: String,
+    pub version: u32,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct KeystoreCrypto {
+    pub cipher: String,
+    pub cipherparams: KeystoreCipherParams,
+    pub ciphertext: String,
+    pub kdf: String,
+    pub kdfparams: KeystoreKdfParams,
+    pub mac: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct KeystoreCipherParams {
+    pub iv: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum KeystoreKdfParams {
+    Scrypt {
+        dklen: u8,
+        n: u32,
+        p: u32,
+        r: u32,
+        salt: String,
+    },
+    Pbkdf2 {
+        c: u32,
+        dklen: u8,
+        prf: String,
+        salt: String,
+    },
+}
+
+impl EthKeystore {
+    pub fn encrypt(secret: &PrivateKey, password: &str) -> Result<Self> {
+        let salt = random_bytes::<32>();
+        let iv = random_bytes::<16>();
+
+        let kdfparams = KeystoreKdfParams::Scrypt {
+            dklen: 32,
+            n: 1 << SCRYPT_LOG_N,
+            p: SCRYPT_P,
+            r: SCRYPT_R,
+            salt: hex::encode(salt),
+        };
+
+        let key = derive_keystore_key(password, &kdfparams)?;
+
+        let mut ciphertext = secret.expose().to_vec();
+        Aes128Ctr::new(key[..16].into(), &iv.into()).apply_keystream(&mut ciphertext);
+
+        let mac = keystore_mac(&key, &ciphertext);
+        let address = Chain::Evm.address_from_secret(secret.expose())?;
+
+        Ok(Self {
+            address: Some(address.trim_start_matches("0x").to_lowercase()),
+            crypto: KeystoreCrypto {
+                cipher: "aes-128-ctr".to_string(),
+                cipherparams: KeystoreCipherParams {
+                    iv: hex::encode(iv),
+                },
+                ciphertext: hex::encode(ciphertext),
+                kdf: "scrypt".to_string(),
+                kdfparams,
+                mac: hex::encode(mac),
+            },
+            id: new_uuid(),
+            version: 3,
+        })
+    }
+
+    pub fn decrypt(&self, password: &str) -> Result<PrivateKey> {
+        if self.version != 3 {
+            return Err(keystore_error(format!(
+                "Unsupported keystore version {}",
+                self.version
+            )));
+        }
+
+        let crypto = &self.crypto;
+
+        if crypto.cipher != "aes-128-ctr" {
+            return Err(KeyVaultError::UnsupportedCipher(crypto.cipher.clone()));
+        }
+
+        let key = derive_keystore_key(password, &crypto.kdfparams)?;
+        let ciphertext = decode_hex(&crypto.ciphertext)?;
+        let mac = decode_hex(&crypto.mac)?;
+
+        if !bool::from(keystore_mac(&key, &ciphertext).ct_eq(mac.as_slice())) {
+            return Err(KeyVaultError::WrongPassword);
+        }
+
+        let iv: [u8; 16] = decode_hex(&crypto.cipherparams.iv)?
+            .try_into()
+            .map_err(|_| keystore_error("IV must be 16 bytes".to_string()))?;
+
+        let mut secret = Zeroizing::new(ciphertext);
+        Aes128Ctr::new(key[..16].into(), &iv.into()).apply_keystream(&mut secret);
+
+        PrivateKey::from_slice(&secret)
+            .map_err(|_| keystore_error("Private key must be 32 bytes".to_string()))
+    }
+
+    pub fn load(filename: &Path) -> Result<Self> {
+        let f = File::open(filename)?;
+        let rdr = BufReader::new(f);
+
+        serde_json::from_reader(rdr).map_err(|e| keystore_error(e.to_string()))
+    }
+
+    pub fn write(&self, fname: &Path) -> Result<()> {
+        let json = serde_json::to_vec(&self)?;
+
+        write_atomic(fname, &json, false)
+    }
+}
+
+/// Solana CLI keypair JSON: the 32-byte secret followed by the public key
+pub fn encode_solana_keypair(secret: &PrivateKey) -> String {
+    let signing_key = SigningKey::from_bytes(secret.expose());
+    let keypair = Zeroizing::new(signing_key.to_keypair_bytes());
+
+    serde_json::to_string(keypair.as_slice()).expect("byte arrays always serialize")
+}
+
+pub fn decode_solana_keypair(json: &str) -> Result<PrivateKey> {
+    let bytes: Zeroizing<Vec<u8>> =
+        Zeroizing::new(serde_json::from_str(json).map_err(|e| keystore_error(e.to_string()))?);
+
+    let keypair: &[u8; 64] = bytes
+        .as_slice()
+        .try_into()
+        .map_err(|_| keystore_error("Solana keypair must be 64 bytes".to_string()))?;
+
+    let signing_key = SigningKey::from_keypair_bytes(keypair)
+        .map_err(|_| keystore_error("Solana keypair does not match its pubkey".to_string()))?;
+
+    Ok(PrivateKey::new(&mut signing_key.to_bytes()))
+}
+
+pub fn read_solana_keypair(filename: &Path) -> Result<PrivateKey> {
+    let json = Zeroizing::new(fs::read_to_string(filename)?);
+
+    decode_solana_keypair(&json)
+}
+
+pub fn write_solana_keypair(fname: &Path, secret: &PrivateKey) -> Result<()> {
+    let json = Zeroizing::new(encode_solana_keypair(secret));
+
+    write_atomic(fname, json.as_bytes(), false)
+}
+
+fn derive_keystore_key(
+    password: &str,
+    kdfparams: &KeystoreKdfParams,
+) -> Result<Zeroizing<[u8; 32]>> {
+    let mut key = Zeroizing::new([0u8; 32]);
+
+    match kdfparams {
+        KeystoreKdfParams::Scrypt {
+            dklen,
+            n,
+            p,
+            r,
+            salt,
+        } => {
+            if *dklen != 32 || !n.is_power_of_two() {
+                return Err(keystore_error("Invalid scrypt params".to_string()));
+            }
+
+            let work = u64::from(*n)
+                .saturating_mul(u64::from(*r))
+                .saturating_mul(u64::from(*p));
+            if work > MAX_SCRYPT_WORK {
+                return Err(keystore_error(format!(
+                    "scrypt cost N * r * p = {work} is above the limit of {MAX_SCRYPT_WORK}"
+                )));
+            }
+
+            let log_n = n.trailing_zeros() as u8;
+            let params = scrypt::Params::new(log_n, *r, *p, 32)
+                .map_err(|e| keystore_error(format!("Invalid scrypt params: {e}")))?;
+
+            scrypt::scrypt(
+                password.as_bytes(),
+                &decode_hex(salt)?,
+                &params,
+                key.as_mut(),
+            )
+            .map_err(|e| KeyVaultError::Encryption(format!("scrypt failed: {e}")))?;
+        }
+        KeystoreKdfParams::Pbkdf2 {
+            c,
+            dklen,
+            prf,
+            salt,
+        } => {
+            if *dklen != 32 || prf != "hmac-sha256" {
+                return Err(keystore_error("Invalid pbkdf2 params".to_string()));
+            }
+
+            pbkdf2_hmac::<Sha256>(password.as_bytes(), &decode_hex(salt)?, *c, key.as_mut());
+        }
+    }
+
+    Ok(key)
+}
+
+fn keystore_mac(key: &[u8; 32], ciphertext: &[u8]) -> [u8; 32] {
+    let mut hasher = Keccak256::new();
+    hasher.update(&key[16..]);
+    hasher.update(ciphertext);
+
+    hasher.finalize().into()
+}
+
+fn decode_hex(value: &str) -> Result<Vec<u8>> {
+    hex::decode(value.trim_start_matches("0x"))
+        .map_err(|e| keystore_error(format!("Invalid hex: {e}")))
+}
+
+fn random_bytes<const N: usize>() -> [u8; N] {
+    let mut bytes = [0u8; N];
+    OsRng.fill_bytes(&mut bytes);
+
+    bytes
+}
+
+fn new_uuid() -> String {
+    let mut bytes = random_bytes::<16>();
+
+    // Random (version 4) UUID
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+
+    let hex = hex::encode(bytes);
+
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
+
+fn keystore_error(msg: String) -> KeyVaultError {
+    KeyVaultError::Keystore(msg)
+}
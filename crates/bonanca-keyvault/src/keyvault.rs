@@ -1,149 +1,515 @@
 use std::collections::HashMap;
 use std::fs::File;
-use std::io::{BufReader, BufWriter};
+use std::io::BufReader;
+use std::ops::Range;
 use std::path::Path;
 
 use aes_gcm::{AeadCore, Aes256Gcm, aead::OsRng};
-use anyhow::{Result, anyhow};
 use argon2::password_hash::SaltString;
 use bip39::Language;
-use rpassword::prompt_password;
 use serde::{Deserialize, Serialize};
 use serde_json;
-use zeroize::Zeroize;
+use zeroize::{Zeroize, Zeroizing};
 
+use crate::accounts::{Account, deserialize_accounts};
+use crate::chains::{Chain, evm_address_from_xpub};
+use crate::display::MnemonicDisplay;
+use crate::error::{KeyVaultError, Result};
 use crate::hd_keys::HDkeys;
-use crate::utils::{decrypt_seed, verify_password};
+use crate::keystore::{EthKeystore, encode_solana_keypair, read_solana_keypair};
+use crate::password::PasswordProvider;
+use crate::secret::{PrivateKey, Seed};
+use crate::storage::{VaultLock, write_atomic};
+use crate::utils::{
+    compute_mac, decrypt_secret, derive_key, encrypt_secret, split_key, verify_mac, verify_password,
+};
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct KeyVault {
     pub vault: Vault,
-    pub chain_keys: HashMap<String, String>,
+    #[serde(deserialize_with = "deserialize_accounts")]
+    pub chain_keys: HashMap<String, Account>,
+    #[serde(default)]
+    pub xpubs: HashMap<String, String>,
+    #[serde(default)]
+    pub imported_keys: HashMap<String, ImportedKey>,
 }
 
 impl KeyVault {
-    pub fn new(lang: &str) -> Self {
-        let language = match lang {
-            "English" => Ok(Language::English),
-            "Simplified Chinese" => Ok(Language::SimplifiedChinese),
-            "Traditional Chinese" => Ok(Language::TraditionalChinese),
-            "French" => Ok(Language::French),
-            "Italian" => Ok(Language::Italian),
-            "Japanese" => Ok(Language::Japanese),
-            "Korean" => Ok(Language::Korean),
-            "Spanish" => Ok(Language::Spanish),
-            _ => Err(anyhow!("Language not supported")),
-        }
-        .unwrap();
+    pub fn new(
+        lang: &str,
+        passphrase: Option<&str>,
+        password: &PasswordProvider,
+        display: &MnemonicDisplay,
+    ) -> Result<Self> {
+        let language = parse_language(lang)?;
 
         let word_count: usize = 24;
-        let salt = SaltString::generate(&mut OsRng);
-        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
-        let nonce = hex::encode(nonce);
+        let hd_key = HDkeys::new(language, word_count, passphrase, display)?;
 
-        let hd_key = HDkeys::new(language, word_count);
+        hd_key.new_keyvault(password)
+    }
 
-        let mut password = prompt_password("Set Keyvault Password: ").unwrap();
+    pub fn from_mnemonic(
+        mnemonic: &str,
+        passphrase: Option<&str>,
+        password: &PasswordProvider,
+    ) -> Result<Self> {
+        let hd_key = HDkeys::from_mnemonic(mnemonic, passphrase)?;
 
-        let key_vault = hd_key.get_keyvault(&salt, &nonce, &password).unwrap();
+        hd_key.new_keyvault(password)
+    }
 
-        password.zeroize();
+    pub fn from_seed(seed: Seed, password: &PasswordProvider) -> Result<Self> {
+        let hd_key = HDkeys { seed };
 
-        key_vault
+        hd_key.new_keyvault(password)
     }
 
-    pub fn from_mnemonic(mnemonic: &str) -> Self {
-        let hd_key = HDkeys::from_mnemonic(mnemonic);
+    pub fn from_shares<S: AsRef<str>>(
+        shares: &[S],
+        passphrase: Option<&str>,
+        password: &PasswordProvider,
+    ) -> Result<Self> {
+        let hd_key = HDkeys::from_shares(shares, passphrase)?;
+
+        hd_key.new_keyvault(password)
+    }
 
-        let salt = SaltString::generate(&mut OsRng);
-        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
-        let nonce = hex::encode(nonce);
+    pub fn split_shares(
+        &self,
+        password: &PasswordProvider,
+        threshold: u8,
+        count: u8,
+        passphrase: Option<&str>,
+    ) -> Result<Vec<String>> {
+        let hd_key = self.decrypt_vault(password)?;
 
-        let mut password = prompt_password("Set Keyvault Password: ").unwrap();
+        hd_key.split_shares(threshold, count, passphrase)
+    }
 
-        let key_vault = hd_key.get_keyvault(&salt, &nonce, &password).unwrap();
+    pub fn get_seed(&self, password: &PasswordProvider) -> Result<Seed> {
+        self.unlock(password)
+    }
 
-        password.zeroize();
+    pub fn load(filename: &Path) -> Result<Self> {
+        let f = File::open(filename)?;
+        let rdr = BufReader::new(f);
 
-        key_vault
+        Ok(serde_json::from_reader(rdr)?)
     }
 
-    pub fn from_seed(seed: [u8; 64]) -> Self {
-        let hd_key = HDkeys { seed };
+    pub fn write(&self, fname: &Path) -> Result<()> {
+        let _lock = VaultLock::acquire(fname)?;
 
-        let salt = SaltString::generate(&mut OsRng);
-        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
-        let nonce = hex::encode(nonce);
+        self.write_locked(fname)
+    }
 
-        let mut password = prompt_password("Set Keyvault Password: ").unwrap();
+    /// Reloads the vault from `fname` under its file lock, applies `f` and
+    /// writes the result back, so changes made by another process in the
+    /// meantime are not lost.
+    pub fn update(fname: &Path, f: impl FnOnce(&mut Self) -> Result<()>) -> Result<Self> {
+        let _lock = VaultLock::acquire(fname)?;
 
-        let key_vault = hd_key.get_keyvault(&salt, &nonce, &password).unwrap();
+        let mut key_vault = Self::load(fname)?;
+        f(&mut key_vault)?;
+        key_vault.write_locked(fname)?;
 
-        password.zeroize();
+        Ok(key_vault)
+    }
 
-        key_vault
+    /// Records the address for `path`, guessing the chain from the path
+    pub fn add_pubkey(&mut self, path: &str, pubkey: &str) {
+        let chain = Chain::from_path(path).unwrap_or(if pubkey.starts_with("0x") {
+            Chain::Evm
+        } else {
+            Chain::Solana
+        });
+
+        self.add_account(chain, path, pubkey);
     }
 
-    pub fn get_seed(&self) -> Result<[u8; 64]> {
-        let mut password = prompt_password("Keyvault Password: ")?;
+    /// Records the address for `path`, keeping the label and tags if the
+    /// account is already known.
+    pub fn add_account(&mut self, chain: Chain, path: &str, address: &str) {
+        match self.chain_keys.get_mut(path) {
+            Some(account) => address.clone_into(&mut account.address),
+            None => {
+                let account = Account::new(chain, path, address);
+                self.chain_keys.insert(path.to_string(), account);
+            }
+        }
+    }
 
-        if !verify_password(&self.vault.mac, &password) {
-            println!("Wrong Password");
-            panic!();
-        };
+    pub fn get_account(&self, path: &str) -> Option<&Account> {
+        self.chain_keys.get(path)
+    }
 
-        let seed = decrypt_seed(
-            &self.vault.cipher_text,
-            &password,
-            &self.vault.cipher_params.nonce,
-            &self.vault.kdf_params,
-        )?;
+    /// Finds an account by its label, or else by its address
+    pub fn find_account(&self, label_or_address: &str) -> Option<&Account> {
+        self.chain_keys
+            .values()
+            .find(|a| a.label.as_deref() == Some(label_or_address))
+            .or_else(|| {
+                self.chain_keys
+                    .values()
+                    .find(|a| a.matches_address(label_or_address))
+            })
+    }
 
-        password.zeroize();
+    pub fn accounts_with_tag(&self, tag: &str) -> Vec<&Account> {
+        let mut accounts: Vec<&Account> = self
+            .chain_keys
+            .values()
+            .filter(|a| a.has_tag(tag))
+            .collect();
+        accounts.sort_by(|a, b| a.path.cmp(&b.path));
 
-        Ok(seed)
+        accounts
     }
 
-    pub fn load(filename: &Path) -> Self {
-        let f = File::open(filename).unwrap();
-        let rdr = BufReader::new(f);
+    /// Labels the account at `path`, labels are unique within a vault.
+    pub fn set_label(&mut self, path: &str, label: Option<&str>) -> Result<()> {
+        if let Some(label) = label
+            && self
+                .chain_keys
+                .values()
+                .any(|a| a.path != path && a.label.as_deref() == Some(label))
+        {
+            return Err(KeyVaultError::DuplicateLabel(label.to_string()));
+        }
+
+        let account = self.account_mut(path)?;
+        account.label = label.map(str::to_string);
 
-        serde_json::from_reader(rdr).unwrap()
+        Ok(())
     }
 
-    pub fn write(&self, fname: &Path) {
-        let file = File::create(fname).unwrap();
-        let writer = BufWriter::new(file);
-        serde_json::to_writer(writer, &self).unwrap();
+    pub fn add_tag(&mut self, path: &str, tag: &str) -> Result<()> {
+        let account = self.account_mut(path)?;
+
+        if !account.has_tag(tag) {
+            account.tags.push(tag.to_string());
+        }
+
+        Ok(())
     }
 
-    pub fn add_pubkey(&mut self, path: &str, pubkey: &str) {
-        self.chain_keys.insert(path.to_string(), pubkey.to_string());
+    pub fn remove_tag(&mut self, path: &str, tag: &str) -> Result<()> {
+        self.account_mut(path)?.tags.retain(|t| t != tag);
+
+        Ok(())
     }
 
-    pub fn decrypt_vault(&self) -> Result<HDkeys> {
-        let mut password = prompt_password("Keyvault Password: ")?;
+    /// Turns an account label into its derivation path. Anything that is
+    /// not a label is taken to be a path already.
+    pub fn resolve_path(&self, chain: Chain, label_or_path: &str) -> Result<String> {
+        let labelled = self
+            .chain_keys
+            .values()
+            .find(|a| a.label.as_deref() == Some(label_or_path));
+
+        match labelled {
+            Some(account) if account.chain == chain => Ok(account.path.clone()),
+            Some(_) => Err(KeyVaultError::UnknownKey(chain, label_or_path.to_string())),
+            None if label_or_path.starts_with("m/") => Ok(label_or_path.to_string()),
+            None => Err(KeyVaultError::UnknownKey(chain, label_or_path.to_string())),
+        }
+    }
 
-        if !verify_password(&self.vault.mac, &password) {
-            println!("Wrong Password");
-            panic!();
-        };
+    /// Looks up the address for `path`, deriving it from a stored account
+    /// xpub when it has not been cached in `chain_keys`.
+    pub fn get_address(&self, path: &str) -> Result<String> {
+        if let Some(account) = self.chain_keys.get(path) {
+            return Ok(account.address.clone());
+        }
 
-        let seed = decrypt_seed(
-            &self.vault.cipher_text,
-            &password,
-            &self.vault.cipher_params.nonce,
-            &self.vault.kdf_params,
-        )?;
+        self.xpubs
+            .iter()
+            .find_map(|(account, xpub)| {
+                let rest = path.strip_prefix(account.as_str())?.strip_prefix('/')?;
+                Some(evm_address_from_xpub(xpub, rest))
+            })
+            .unwrap_or_else(|| Err(KeyVaultError::UnknownPath(path.to_string())))
+    }
 
-        password.zeroize();
+    /// Fills `chain_keys` for `children` with a single unlock. For EVM the
+    /// account xpubs are stored as well.
+    pub fn precompute_addresses(
+        &mut self,
+        password: &PasswordProvider,
+        chain: Chain,
+        children: Range<u32>,
+    ) -> Result<()> {
+        let hd_keys = self.decrypt_vault(password)?;
+
+        for child in children {
+            let path = chain.child_path(child);
+            let address = hd_keys.derive_address(chain, &path)?;
+            self.add_account(chain, &path, &address);
+
+            if chain == Chain::Evm {
+                let account = chain.account_path(child);
+                let xpub = hd_keys.derive_xpub(&account)?;
+                self.xpubs.insert(account, xpub);
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn decrypt_vault(&self, password: &PasswordProvider) -> Result<HDkeys> {
+        let seed = self.unlock(password)?;
 
         Ok(HDkeys { seed })
     }
+
+    pub fn upgrade(&mut self, password: &PasswordProvider, kdf_params: KdfParams) -> Result<()> {
+        let mut pass = password.get_password("Keyvault Password: ")?;
+
+        let updated = self.reencrypt(&pass, &pass, kdf_params);
+
+        pass.zeroize();
+
+        (self.vault, self.imported_keys) = updated?;
+
+        Ok(())
+    }
+
+    pub fn rotate_password(
+        &mut self,
+        old_password: &PasswordProvider,
+        new_password: &PasswordProvider,
+    ) -> Result<()> {
+        let mut old_pass = old_password.get_password("Keyvault Password: ")?;
+
+        let updated = new_password
+            .get_password("New Keyvault Password: ")
+            .and_then(|mut new_pass| {
+                let kdf_params = self.vault.kdf_params.with_new_salt();
+                let updated = self.reencrypt(&old_pass, &new_pass, kdf_params);
+                new_pass.zeroize();
+                updated
+            });
+
+        old_pass.zeroize();
+
+        (self.vault, self.imported_keys) = updated?;
+
+        Ok(())
+    }
+
+    pub fn change_password(
+        fname: &Path,
+        old_password: &PasswordProvider,
+        new_password: &PasswordProvider,
+    ) -> Result<Self> {
+        Self::update(fname, |key_vault| {
+            key_vault.rotate_password(old_password, new_password)
+        })
+    }
+
+    /// Encrypts a standalone private key under the vault password and
+    /// stores it as `label`.
+    pub fn import_key(
+        &mut self,
+        label: &str,
+        chain: Chain,
+        secret: &PrivateKey,
+        password: &PasswordProvider,
+    ) -> Result<()> {
+        if self.imported_keys.contains_key(label) {
+            return Err(KeyVaultError::DuplicateLabel(label.to_string()));
+        }
+
+        let address = chain.address_from_secret(secret.expose())?;
+
+        let mut pass = password.get_password("Keyvault Password: ")?;
+
+        // Decrypting the seed first makes sure every key shares one password
+        let vault = self.vault.decrypt(&pass).and_then(|_| {
+            let kdf_params = self.vault.kdf_params.with_new_salt();
+            Vault::encrypt_secret(secret.expose(), &pass, &new_nonce(), kdf_params)
+        });
+
+        pass.zeroize();
+
+        let imported_key = ImportedKey {
+            chain,
+            address,
+            vault: vault?,
+        };
+        self.imported_keys.insert(label.to_string(), imported_key);
+
+        Ok(())
+    }
+
+    pub fn get_imported_key(
+        &self,
+        label: &str,
+        chain: Chain,
+        password: &PasswordProvider,
+    ) -> Result<PrivateKey> {
+        let imported_key = self
+            .imported_keys
+            .get(label)
+            .filter(|key| key.chain == chain)
+            .ok_or_else(|| KeyVaultError::UnknownKey(chain, label.to_string()))?;
+
+        let mut pass = password.get_password("Keyvault Password: ")?;
+
+        let secret = imported_key.vault.decrypt_secret(&pass);
+
+        pass.zeroize();
+
+        PrivateKey::from_slice(&secret?).map_err(|_| KeyVaultError::CorruptCiphertext)
+    }
+
+    pub fn import_eth_keystore(
+        &mut self,
+        label: &str,
+        keystore: &EthKeystore,
+        keystore_password: &PasswordProvider,
+        password: &PasswordProvider,
+    ) -> Result<()> {
+        let mut pass = keystore_password.get_password("Keystore Password: ")?;
+
+        let secret = keystore.decrypt(&pass);
+
+        pass.zeroize();
+
+        self.import_key(label, Chain::Evm, &secret?, password)
+    }
+
+    pub fn import_solana_keypair(
+        &mut self,
+        label: &str,
+        fname: &Path,
+        password: &PasswordProvider,
+    ) -> Result<()> {
+        let secret = read_solana_keypair(fname)?;
+
+        self.import_key(label, Chain::Solana, &secret, password)
+    }
+
+    /// Exports the secp256k1 key at `path` as an Ethereum V3 keystore
+    /// encrypted with `keystore_password`.
+    pub fn export_eth_keystore(
+        &self,
+        path: &str,
+        password: &PasswordProvider,
+        keystore_password: &PasswordProvider,
+    ) -> Result<EthKeystore> {
+        let secret = self
+            .decrypt_vault(password)?
+            .derive_secp256k1_child_prvkey(path.to_string())?;
+
+        let mut pass = keystore_password.get_password("Set Keystore Password: ")?;
+
+        let keystore = EthKeystore::encrypt(&secret, &pass);
+
+        pass.zeroize();
+
+        keystore
+    }
+
+    /// Exports the ed25519 key at `path` as Solana CLI keypair JSON.
+    pub fn export_solana_keypair(&self, path: &str, password: &PasswordProvider) -> Result<String> {
+        let secret = self
+            .decrypt_vault(password)?
+            .derive_ed25519_child_prvkey(path.to_string())?;
+
+        Ok(encode_solana_keypair(&secret))
+    }
+
+    pub fn remove_imported_key(&mut self, label: &str) -> Option<ImportedKey> {
+        self.imported_keys.remove(label)
+    }
+
+    fn reencrypt(
+        &self,
+        old_password: &str,
+        new_password: &str,
+        kdf_params: KdfParams,
+    ) -> Result<(Vault, HashMap<String, ImportedKey>)> {
+        let seed = self.vault.decrypt(old_password)?;
+        let vault = Vault::encrypt(&seed, new_password, &new_nonce(), kdf_params.clone());
+
+        let imported_keys = self
+            .imported_keys
+            .iter()
+            .map(|(label, key)| {
+                let secret = key.vault.decrypt_secret(old_password)?;
+                let vault = Vault::encrypt_secret(
+                    &secret,
+                    new_password,
+                    &new_nonce(),
+                    kdf_params.with_new_salt(),
+                )?;
+
+                let imported_key = ImportedKey {
+                    chain: key.chain,
+                    address: key.address.clone(),
+                    vault,
+                };
+
+                Ok((label.clone(), imported_key))
+            })
+            .collect::<Result<HashMap<String, ImportedKey>>>()?;
+
+        Ok((vault?, imported_keys))
+    }
+
+    fn write_locked(&self, fname: &Path) -> Result<()> {
+        let json = serde_json::to_vec(&self)?;
+
+        write_atomic(fname, &json, true)
+    }
+
+    fn account_mut(&mut self, path: &str) -> Result<&mut Account> {
+        self.chain_keys
+            .get_mut(path)
+            .ok_or_else(|| KeyVaultError::UnknownPath(path.to_string()))
+    }
+
+    fn unlock(&self, password: &PasswordProvider) -> Result<Seed> {
+        let mut pass = password.get_password("Keyvault Password: ")?;
+
+        let seed = self.vault.decrypt(&pass);
+
+        pass.zeroize();
+
+        seed
+    }
+}
+
+pub fn parse_language(lang: &str) -> Result<Language> {
+    match lang {
+        "English" => Ok(Language::English),
+        "Simplified Chinese" => Ok(Language::SimplifiedChinese),
+        "Traditional Chinese" => Ok(Language::TraditionalChinese),
+        "French" => Ok(Language::French),
+        "Italian" => Ok(Language::Italian),
+        "Japanese" => Ok(Language::Japanese),
+        "Korean" => Ok(Language::Korean),
+        "Spanish" => Ok(Language::Spanish),
+        _ => Err(KeyVaultError::UnknownLanguage(lang.to_string())),
+    }
+}
+
+pub const VAULT_VERSION: u32 = 1;
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ImportedKey {
+    pub chain: Chain,
+    pub address: String,
+    pub vault: Vault,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Vault {
+    #[serde(default)]
+    pub version: u32,
     pub cipher: String,
     pub cipher_params: CipherParams,
     pub cipher_text: String,
@@ -153,8 +519,187 @@ pub struct Vault {
     pub salt: String,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
-pub struct KdfParams {
+impl Vault {
+    pub fn encrypt(
+        seed: &Seed,
+        password: &str,
+        nonce: &str,
+        kdf_params: KdfParams,
+    ) -> Result<Self> {
+        Self::encrypt_secret(seed.expose(), password, nonce, kdf_params)
+    }
+
+    pub fn encrypt_secret(
+        secret: &[u8],
+        password: &str,
+        nonce: &str,
+        kdf_params: KdfParams,
+    ) -> Result<Self> {
+        let cipher = "aes256-gcm".to_string();
+        let kdf = kdf_params.name().to_string();
+
+        let key = derive_key(password, &kdf, &kdf_params)?;
+        let (cipher_key, mac_key) = split_key(&key);
+        let cipher_text = encrypt_secret(secret, &cipher_key, &cipher, nonce)?;
+        let mac = compute_mac(&mac_key, &cipher_text)?;
+
+        Ok(Self {
+            version: VAULT_VERSION,
+            cipher,
+            cipher_params: CipherParams {
+                nonce: nonce.to_string(),
+            },
+            cipher_text,
+            kdf,
+            salt: kdf_params.salt().to_string(),
+            kdf_params,
+            mac,
+        })
+    }
+
+    pub fn decrypt(&self, password: &str) -> Result<Seed> {
+        let secret = self.decrypt_secret(password)?;
+
+        Seed::from_slice(&secret).map_err(|_| KeyVaultError::CorruptCiphertext)
+    }
+
+    pub fn decrypt_secret(&self, password: &str) -> Result<Zeroizing<Vec<u8>>> {
+        match self.version {
+            // v0 vaults store an Argon2 hash of the password as the mac
+            0 => {
+                if !verify_password(&self.mac, password)? {
+                    return Err(KeyVaultError::WrongPassword);
+                }
+
+                let key = derive_key(password, &self.kdf, &self.kdf_params)?;
+
+                decrypt_secret(
+                    &self.cipher_text,
+                    &key,
+                    &self.cipher,
+                    &self.cipher_params.nonce,
+                )
+            }
+            VAULT_VERSION => {
+                let key = derive_key(password, &self.kdf, &self.kdf_params)?;
+                let (cipher_key, mac_key) = split_key(&key);
+
+                if !verify_mac(&mac_key, &self.cipher_text, &self.mac)? {
+                    return Err(KeyVaultError::WrongPassword);
+                }
+
+                decrypt_secret(
+                    &self.cipher_text,
+                    &cipher_key,
+                    &self.cipher,
+                    &self.cipher_params.nonce,
+                )
+            }
+            version => Err(KeyVaultError::UnsupportedVersion(version)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum KdfParams {
+    Argon2id(Argon2idParams),
+    Scrypt(ScryptParams),
+    Pbkdf2(Pbkdf2Params),
+}
+
+impl KdfParams {
+    pub fn argon2id() -> Self {
+        Self::Argon2id(Argon2idParams {
+            key_length: 32,
+            m_cost: 65_536,
+            t_cost: 3,
+            p_cost: 4,
+            salt: new_salt(),
+        })
+    }
+
+    pub fn scrypt() -> Self {
+        Self::Scrypt(ScryptParams {
+            key_length: 32,
+            log_n: 17,
+            r: 8,
+            p: 1,
+            salt: new_salt(),
+        })
+    }
+
+    pub fn pbkdf2() -> Self {
+        Self::Pbkdf2(Pbkdf2Params {
+            key_length: 32,
+            n: 600_000, // OWASP recommendation
+            salt: new_salt(),
+        })
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Argon2id(_) => "argon2id",
+            Self::Scrypt(_) => "scrypt",
+            Self::Pbkdf2(_) => "pbkdf2",
+        }
+    }
+
+    pub fn salt(&self) -> &str {
+        match self {
+            Self::Argon2id(params) => &params.salt,
+            Self::Scrypt(params) => &params.salt,
+            Self::Pbkdf2(params) => &params.salt,
+        }
+    }
+
+    pub fn with_new_salt(&self) -> Self {
+        let mut params = self.clone();
+
+        match &mut params {
+            Self::Argon2id(p) => p.salt = new_salt(),
+            Self::Scrypt(p) => p.salt = new_salt(),
+            Self::Pbkdf2(p) => p.salt = new_salt(),
+        }
+
+        params
+    }
+
+    pub fn key_length(&self) -> u8 {
+        match self {
+            Self::Argon2id(params) => params.key_length,
+            Self::Scrypt(params) => params.key_length,
+            Self::Pbkdf2(params) => params.key_length,
+        }
+    }
+}
+
+impl Default for KdfParams {
+    fn default() -> Self {
+        Self::argon2id()
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Argon2idParams {
+    pub key_length: u8,
+    pub m_cost: u32,
+    pub t_cost: u32,
+    pub p_cost: u32,
+    pub salt: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ScryptParams {
+    pub key_length: u8,
+    pub log_n: u8,
+    pub r: u32,
+    pub p: u32,
+    pub salt: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Pbkdf2Params {
     pub key_length: u8,
     pub n: u32,
     pub salt: String,
@@ -164,3 +709,11 @@ pub struct KdfParams {
 pub struct CipherParams {
     pub nonce: String,
 }
+
+pub(crate) fn new_nonce() -> String {
+    hex::encode(Aes256Gcm::generate_nonce(&mut OsRng))
+}
+
+fn new_salt() -> String {
+    SaltString::generate(&mut OsRng).as_str().to_string()
+}
@@ -1,19 +1,63 @@
+pub mod accounts;
+pub mod chains;
+pub mod display;
+pub mod error;
 pub mod hd_keys;
+pub mod keystore;
 pub mod keyvault;
+pub mod password;
+pub mod secret;
+pub mod session;
+pub mod shamir;
+mod storage;
 mod utils;
 
 #[cfg(test)]
 mod tests {
+    use super::chains::{Chain, DerivationScheme};
+    use super::display::MnemonicDisplay;
+    use super::error::KeyVaultError;
     use super::hd_keys::HDkeys;
-    use super::keyvault::KeyVault;
-    use super::utils::{decrypt_seed, verify_password};
-    use argon2::password_hash::SaltString;
+    use super::keystore::{EthKeystore, decode_solana_keypair, encode_solana_keypair};
+    use super::keyvault::{
+        Argon2idParams, KdfParams, KeyVault, Pbkdf2Params, ScryptParams, VAULT_VERSION,
+    };
+    use super::password::PasswordProvider;
+    use super::secret::PrivateKey;
+    use super::session::UnlockedVault;
+    use super::shamir::{Share, combine_shares, split_secret};
+    use super::utils::verify_password;
+    use super::utils::{expand_key, split_key};
+    use std::path::{Path, PathBuf};
+
+    const TEST_MNEMONIC: &str = "zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo wrong";
+
+    // Writes the TEST_MNEMONIC seed to `dir/keyvault.json` under "password",
+    // with cheap Argon2id parameters so the tests stay fast
+    fn test_vault(dir: &Path) -> PathBuf {
+        let hd_keys = HDkeys::from_mnemonic(TEST_MNEMONIC, None).unwrap();
+        let kdf_params = KdfParams::Argon2id(Argon2idParams {
+            key_length: 32,
+            m_cost: 1024,
+            t_cost: 1,
+            p_cost: 1,
+            salt: "M6lWvNAGuZBSp9fBGAUEqw".to_string(),
+        });
+        let keyvault = hd_keys
+            .get_keyvault(kdf_params, "287189f34a1433d2de201d08", "password")
+            .unwrap();
+
+        std::fs::create_dir_all(dir).unwrap();
+        let path = dir.join("keyvault.json");
+        keyvault.write(&path).unwrap();
+
+        path
+    }
 
     #[test]
     fn test_encrypt_and_decrypt() {
         let password = "password";
-        let mnemonic = "zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo wrong";
-        let hd_keys = HDkeys::from_mnemonic(mnemonic);
+        let hd_keys = HDkeys::from_mnemonic(TEST_MNEMONIC, None).unwrap();
 
         let seed_hex = "b6a6d8921942dd9806607ebc2750416b289adea669198769f2e15ed926c3aa92bf88ece232317b4ea463e84b0fcd3b53577812ee449ccc448eb45e6f544e25b6";
         let seed: [u8; 64] = hex::decode(seed_hex)
@@ -22,23 +66,29 @@ mod tests {
             .try_into()
             .unwrap();
 
-        assert_eq!(hd_keys.seed, seed);
-        assert_eq!(hex::encode(hd_keys.seed), seed_hex);
+        assert_eq!(hd_keys.seed.expose(), &seed);
+        assert_eq!(hex::encode(hd_keys.seed.expose()), seed_hex);
 
-        let salt = SaltString::from_b64("M6lWvNAGuZBSp9fBGAUEqw").unwrap();
+        let kdf_params = KdfParams::Pbkdf2(Pbkdf2Params {
+            key_length: 32,
+            n: 600_000,
+            salt: "M6lWvNAGuZBSp9fBGAUEqw".to_string(),
+        });
         let nonce = "287189f34a1433d2de201d08";
 
-        let keyvault = hd_keys.get_keyvault(&salt, nonce, password).unwrap();
+        let keyvault = hd_keys.get_keyvault(kdf_params, nonce, password).unwrap();
 
-        assert!(verify_password(&keyvault.vault.mac, password));
+        assert_eq!(keyvault.vault.version, VAULT_VERSION);
+        assert_eq!(
+            keyvault.vault.cipher_text,
+            "68b748e7a8edc1783b409b30f4a16aa1c8de3f3fac0108b822ae2ec82b2e812d8746443a5a56e37e8762bdb76ed6266c7a954a6743315df4acbd0478fc895fdb3d569632ef6f27a4421afa361a07d7b6"
+        );
+        assert_eq!(
+            keyvault.vault.mac,
+            "dd61a9c4a1887c79dcab16cacff724a85e139176a9c6b12d5f1bfbebd09eca2b"
+        );
 
-        let decrypted_seed = decrypt_seed(
-            &keyvault.vault.cipher_text,
-            password,
-            &keyvault.vault.cipher_params.nonce,
-            &keyvault.vault.kdf_params,
-        )
-        .unwrap();
+        let decrypted_seed = keyvault.vault.decrypt(password).unwrap();
 
         assert_eq!(hd_keys.seed, decrypted_seed);
     }
@@ -76,16 +126,892 @@ mod tests {
             .try_into()
             .unwrap();
 
-        assert!(verify_password(&keyvault.vault.mac, password));
+        assert_eq!(keyvault.vault.version, 0);
+        assert!(verify_password(&keyvault.vault.mac, password).unwrap());
+
+        let decrypted_seed = keyvault.vault.decrypt(password).unwrap();
+
+        assert_eq!(&seed, decrypted_seed.expose());
+    }
+
+    #[test]
+    fn test_password_providers() {
+        let password = "password";
+
+        let value = PasswordProvider::from(password);
+        assert_eq!(value.get_password("").unwrap(), password);
+
+        let callback = PasswordProvider::callback(|_| Ok("password".to_string()));
+        assert_eq!(callback.get_password("").unwrap(), password);
+
+        let path = std::env::temp_dir().join("bonanca_password_provider_test");
+        std::fs::write(&path, "password\n").unwrap();
+        let file = PasswordProvider::File(path.clone());
+        assert_eq!(file.get_password("").unwrap(), password);
+        std::fs::remove_file(&path).unwrap();
+
+        #[cfg(unix)]
+        {
+            let path = std::env::temp_dir().join("bonanca_password_fd_test");
+            std::fs::write(&path, "password\n").unwrap();
+            let handle = std::fs::File::open(&path).unwrap();
+            let fd = PasswordProvider::fd(handle.into());
+            assert_eq!(fd.get_password("").unwrap(), password);
+            assert_eq!(fd.get_password("").unwrap(), password);
+            std::fs::remove_file(&path).unwrap();
+        }
+
+        let env = PasswordProvider::Env("BONANCA_PASSWORD_PROVIDER_TEST_UNSET".to_string());
+        assert!(env.get_password("").is_err());
+    }
+
+    #[test]
+    fn test_mnemonic_passphrase() {
+        let mnemonic = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+        // BIP39 reference vector
+        let hd_keys = HDkeys::from_mnemonic(mnemonic, Some("TREZOR")).unwrap();
+        assert_eq!(
+            hex::encode(hd_keys.seed.expose()),
+            "c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04"
+        );
+
+        let no_passphrase = HDkeys::from_mnemonic(mnemonic, None).unwrap();
+        let empty_passphrase = HDkeys::from_mnemonic(mnemonic, Some("")).unwrap();
+        assert_eq!(no_passphrase.seed, empty_passphrase.seed);
+        assert_ne!(no_passphrase.seed, hd_keys.seed);
+    }
+
+    #[test]
+    fn test_keyvault_errors() {
+        let keyvault: KeyVault = serde_json::from_str(V0_KEYVAULT).unwrap();
+
+        let wrong = PasswordProvider::from("not the password");
+        assert!(matches!(
+            keyvault.decrypt_vault(&wrong),
+            Err(KeyVaultError::WrongPassword)
+        ));
+
+        let password = PasswordProvider::from("password");
+        assert!(matches!(
+            KeyVault::new("Klingon", None, &password, &MnemonicDisplay::Terminal),
+            Err(KeyVaultError::UnknownLanguage(_))
+        ));
+
+        let missing = KeyVault::load(std::path::Path::new("/does/not/exist.json"));
+        assert!(matches!(missing, Err(KeyVaultError::Io(_))));
+    }
+
+    #[test]
+    fn test_kdf_round_trips() {
+        let password = "password";
+        let hd_keys = HDkeys::from_mnemonic(TEST_MNEMONIC, None).unwrap();
+        let nonce = "287189f34a1433d2de201d08";
+
+        let all_params = [
+            KdfParams::Argon2id(Argon2idParams {
+                key_length: 32,
+                m_cost: 1024,
+                t_cost: 1,
+                p_cost: 1,
+                salt: "M6lWvNAGuZBSp9fBGAUEqw".to_string(),
+            }),
+            KdfParams::Scrypt(ScryptParams {
+                key_length: 32,
+                log_n: 10,
+                r: 8,
+                p: 1,
+                salt: "M6lWvNAGuZBSp9fBGAUEqw".to_string(),
+            }),
+        ];
+
+        for kdf_params in all_params {
+            let kdf = kdf_params.name();
+            let keyvault = hd_keys.get_keyvault(kdf_params, nonce, password).unwrap();
+            assert_eq!(keyvault.vault.kdf, kdf);
+
+            let json = serde_json::to_string(&keyvault).unwrap();
+            let keyvault: KeyVault = serde_json::from_str(&json).unwrap();
+
+            assert_eq!(keyvault.vault.kdf_params.name(), kdf);
+            assert_eq!(keyvault.vault.decrypt(password).unwrap(), hd_keys.seed);
+            assert!(matches!(
+                keyvault.vault.decrypt("wrong"),
+                Err(KeyVaultError::WrongPassword)
+            ));
+
+            let mut tampered: KeyVault = serde_json::from_str(&json).unwrap();
+            tampered.vault.mac = "00".repeat(32);
+            assert!(matches!(
+                tampered.vault.decrypt(password),
+                Err(KeyVaultError::WrongPassword)
+            ));
+        }
+
+        // RFC 5869 test case 1, first block of the OKM
+        let prk: [u8; 32] =
+            hex::decode("077709362c2e32df0ddc3f0dc47bba6390b6c73bb50f9c3122ec844ad7c2b3e5")
+                .unwrap()
+                .try_into()
+                .unwrap();
+        let info = hex::decode("f0f1f2f3f4f5f6f7f8f9").unwrap();
+        assert_eq!(
+            hex::encode(*expand_key(&prk, &info)),
+            "3cb25f25faacd57a90434f64d0362f2a2d2d0a90cf1a5a4c5db02d56ecc4c5bf"
+        );
+
+        let (cipher_key, mac_key) = split_key(&prk);
+        assert_ne!(*cipher_key, prk);
+        assert_ne!(*cipher_key, *mac_key);
+    }
+
+    #[test]
+    fn test_upgrade_keyvault() {
+        let mut keyvault: KeyVault = serde_json::from_str(V0_KEYVAULT).unwrap();
+        let password = PasswordProvider::from("password");
+        let seed = keyvault.get_seed(&password).unwrap();
+
+        let kdf_params = KdfParams::Argon2id(Argon2idParams {
+            key_length: 32,
+            m_cost: 1024,
+            t_cost: 1,
+            p_cost: 1,
+            salt: "c2FsdHNhbHRzYWx0c2FsdA".to_string(),
+        });
+        keyvault.upgrade(&password, kdf_params).unwrap();
+
+        assert_eq!(keyvault.vault.version, VAULT_VERSION);
+        assert_eq!(keyvault.vault.kdf, "argon2id");
+        assert_eq!(keyvault.chain_keys.get("PATH").unwrap().address, "ADDRESS");
+
+        let json = serde_json::to_string(&keyvault).unwrap();
+        let keyvault: KeyVault = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(keyvault.get_seed(&password).unwrap(), seed);
+    }
+
+    #[test]
+    fn test_change_password() {
+        let hd_keys = HDkeys::from_mnemonic(TEST_MNEMONIC, None).unwrap();
+        let dir = std::env::temp_dir().join("bonanca_change_password_test");
+        let path = test_vault(&dir);
+
+        let mut keyvault = KeyVault::load(&path).unwrap();
+        keyvault.add_pubkey("PATH", "ADDRESS");
+        keyvault.write(&path).unwrap();
+
+        let old = PasswordProvider::from("password");
+        let new = PasswordProvider::from("new password");
+        let rotated = KeyVault::change_password(&path, &old, &new).unwrap();
+
+        assert_ne!(rotated.vault.salt, keyvault.vault.salt);
+        assert_ne!(
+            rotated.vault.cipher_params.nonce,
+            keyvault.vault.cipher_params.nonce
+        );
+        assert_eq!(rotated.vault.kdf, "argon2id");
+
+        let reloaded = KeyVault::load(&path).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(reloaded.chain_keys.get("PATH").unwrap().address, "ADDRESS");
+        assert_eq!(reloaded.get_seed(&new).unwrap(), hd_keys.seed);
+        assert!(matches!(
+            reloaded.get_seed(&old),
+            Err(KeyVaultError::WrongPassword)
+        ));
+    }
+
+    #[test]
+    fn test_slip39_vectors() {
+        // Test vectors from SLIP-0039, all with the passphrase "TREZOR"
+        let vectors: [(&[&str], &str); 7] = [
+            (
+                &[
+                    "duckling enlarge academic academic agency result length solution fridge kidney coal piece deal husband erode duke ajar critical decision keyboard",
+                ],
+                "bb54aac4b89dc868ba37d9cc21b2cece",
+            ),
+            (
+                &[
+                    "shadow pistol academic always adequate wildlife fancy gross oasis cylinder mustang wrist rescue view short owner flip making coding armed",
+                    "shadow pistol academic acid actress prayer class unknown daughter sweater depict flip twice unkind craft early superior advocate guest smoking",
+                ],
+                "b43ceb7e57a0ea8766221624d01b0864",
+            ),
+            (
+                &[
+                    "theory painting academic academic armed sweater year military elder discuss acne wildlife boring employer fused large satoshi bundle carbon diagnose anatomy hamster leaves tracks paces beyond phantom capital marvel lips brave detect luck",
+                ],
+                "989baf9dcaad5b10ca33dfd8cc75e42477025dce88ae83e75a230086a0e00e92",
+            ),
+            (
+                &[
+                    "humidity disease academic always aluminum jewelry energy woman receiver strategy amuse duckling lying evidence network walnut tactics forget hairy rebound impulse brother survive clothes stadium mailman rival ocean reward venture always armed unwrap",
+                    "humidity disease academic agency actress jacket gross physics cylinder solution fake mortgage benefit public busy prepare sharp friar change work slow purchase ruler again tricycle involve viral wireless mixture anatomy desert cargo upgrade",
+                ],
+                "c938b319067687e990e05e0da0ecce1278f75ff58d9853f19dcaeed5de104aae",
+            ),
+            (
+                &[
+                    "testify swimming academic academic column loyalty smear include exotic bedroom exotic wrist lobe cover grief golden smart junior estimate learn",
+                ],
+                "1679b4516e0ee5954351d288a838f45e",
+            ),
+            (
+                &[
+                    "enemy favorite academic acid cowboy phrase havoc level response walnut budget painting inside trash adjust froth kitchen learn tidy punish",
+                    "enemy favorite academic always academic sniff script carpet romp kind promise scatter center unfair training emphasis evening belong fake enforce",
+                ],
+                "48b1a4b80b8c209ad42c33672bdaa428",
+            ),
+            (
+                &[
+                    "impulse calcium academic academic alcohol sugar lyrics pajamas column facility finance tension extend space birthday rainbow swimming purple syndrome facility trial warn duration snapshot shadow hormone rhyme public spine counter easy hawk album",
+                ],
+                "8340611602fe91af634a5f4608377b5235fa2d757c51d720c0c7656249a3035f",
+            ),
+        ];
+
+        for (shares, secret) in vectors {
+            let recovered = combine_shares(shares, "TREZOR").unwrap();
+            assert_eq!(hex::encode(recovered.as_slice()), secret);
+        }
+
+        // One share short of the 2-of-3 threshold
+        assert!(matches!(
+            combine_shares(&vectors[1].0[..1], "TREZOR"),
+            Err(KeyVaultError::Shares(_))
+        ));
+
+        // Group index past the group count, with a valid checksum
+        let mut share = Share::from_mnemonic(vectors[0].0[0]).unwrap();
+        share.group_index = share.group_count;
+        assert!(matches!(
+            combine_shares(&[share.to_mnemonic()], "TREZOR"),
+            Err(KeyVaultError::Shares(msg)) if msg.contains("Group index")
+        ));
+    }
+
+    #[test]
+    fn test_keyvault_shares() {
+        let keyvault: KeyVault = serde_json::from_str(V0_KEYVAULT).unwrap();
+        let password = PasswordProvider::from("password");
+        let seed = keyvault.get_seed(&password).unwrap();
+
+        let shares = keyvault
+            .split_shares(&password, 2, 3, Some("passphrase"))
+            .unwrap();
+        assert_eq!(shares.len(), 3);
+        assert_eq!(shares[0].split_whitespace().count(), 59);
+
+        let recovered = KeyVault::from_shares(&shares[1..], Some("passphrase"), &password).unwrap();
+        assert_eq!(recovered.get_seed(&password).unwrap(), seed);
+
+        // A wrong passphrase silently yields a different seed
+        let other = HDkeys::from_shares(&shares[..2], None).unwrap();
+        assert_ne!(other.seed, seed);
+
+        let groups =
+            split_secret(seed.expose(), "", 2, &[(1, 1), (2, 3), (3, 5)], true, 0).unwrap();
+        let quorum = [
+            groups[0][0].as_str(),
+            &groups[2][4],
+            &groups[2][0],
+            &groups[2][2],
+        ];
+        assert_eq!(
+            combine_shares(&quorum, "").unwrap().as_slice(),
+            seed.expose()
+        );
+
+        assert!(matches!(
+            split_secret(seed.expose(), "", 1, &[(1, 3)], true, 0),
+            Err(KeyVaultError::Shares(_))
+        ));
+    }
+
+    #[test]
+    fn test_unlocked_vault() {
+        let hd_keys = HDkeys::from_mnemonic(TEST_MNEMONIC, None).unwrap();
+        let dir = std::env::temp_dir().join("bonanca_unlocked_vault_test");
+        let path = test_vault(&dir);
+
+        // Only the first call may ask for the password
+        let calls = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = calls.clone();
+        let password = PasswordProvider::callback(move |_| {
+            counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Ok("password".to_string())
+        });
+
+        let unlocked = UnlockedVault::open(&path, &password).unwrap();
+        for _ in 0..3 {
+            assert_eq!(
+                unlocked.with_keys(|keys| keys.seed.clone()).unwrap(),
+                hd_keys.seed
+            );
+        }
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 1);
+
+        unlocked.add_pubkey("PATH", "ADDRESS").unwrap();
+        assert_eq!(unlocked.get_pubkey("PATH").unwrap(), "ADDRESS");
+        assert_eq!(
+            KeyVault::load(&path)
+                .unwrap()
+                .chain_keys
+                .get("PATH")
+                .unwrap()
+                .address,
+            "ADDRESS"
+        );
+
+        unlocked.lock();
+        assert!(matches!(
+            unlocked.with_keys(|_| ()),
+            Err(KeyVaultError::Locked)
+        ));
+
+        let timeout = std::time::Duration::from_millis(50);
+        let unlocked = UnlockedVault::open_with_timeout(&path, &password, timeout).unwrap();
+        assert!(!unlocked.is_locked());
+        std::thread::sleep(timeout * 4);
+        assert!(unlocked.is_locked());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_derivation_schemes() {
+        let mnemonic = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        let hd_keys = HDkeys::from_mnemonic(mnemonic, None).unwrap();
+
+        assert_eq!(DerivationScheme::LedgerLive.path(2), "m/44'/60'/2'/0/0");
+        assert_eq!(DerivationScheme::MetaMask.path(2), "m/44'/60'/0'/0/2");
+        assert_eq!(DerivationScheme::SolanaCli.path(2), "m/44'/501'/2'");
+        assert_eq!(DerivationScheme::Phantom.path(2), "m/44'/501'/2'/0'");
+
+        for (chain, scheme) in [
+            (Chain::Evm, DerivationScheme::LedgerLive),
+            (Chain::Solana, DerivationScheme::Phantom),
+        ] {
+            assert_eq!(DerivationScheme::default_for(chain), scheme);
+            assert_eq!(chain.child_path(4), scheme.path(4));
+        }
+
+        for name in ["ledger-live", "MetaMask", "solana_cli", "Phantom"] {
+            let scheme: DerivationScheme = name.parse().unwrap();
+            assert_eq!(Chain::from_path(&scheme.path(0)), Some(scheme.chain()));
+        }
+        assert!(matches!(
+            "trezor".parse::<DerivationScheme>(),
+            Err(KeyVaultError::UnknownScheme(_))
+        ));
+
+        // Both EVM schemes share the first account
+        let metamask = |child| {
+            hd_keys
+                .derive_address(Chain::Evm, &DerivationScheme::MetaMask.path(child))
+                .unwrap()
+        };
+        assert_eq!(metamask(0), "0x9858EfFD232B4033E47d90003D41EC34EcaEda94");
+        assert_eq!(metamask(1), "0x6Fac4D18c912343BF86fa7049364Dd4E424Ab9C0");
+
+        let solana_cli = hd_keys
+            .derive_address(Chain::Solana, &DerivationScheme::SolanaCli.path(0))
+            .unwrap();
+        assert_ne!(
+            solana_cli,
+            hd_keys
+                .derive_address(Chain::Solana, &DerivationScheme::Phantom.path(0))
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_vault_files() {
+        let dir = std::env::temp_dir().join("bonanca_vault_files_test");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("keyvault.json");
+        let backup = |n| dir.join(format!("keyvault.json.bak.{n}"));
+
+        let keyvault: KeyVault = serde_json::from_str(V0_KEYVAULT).unwrap();
+        for _ in 0..5 {
+            keyvault.write(&path).unwrap();
+        }
+
+        assert!((1..=3).all(|n| backup(n).exists()));
+        assert!(!backup(4).exists());
+        assert!(!dir.join("keyvault.json.tmp").exists());
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            for file in [path.clone(), backup(1)] {
+                let mode = std::fs::metadata(file).unwrap().permissions().mode();
+                assert_eq!(mode & 0o777, 0o600);
+            }
+        }
 
-        let decrypted_seed = decrypt_seed(
-            &keyvault.vault.cipher_text,
-            password,
-            &keyvault.vault.cipher_params.nonce,
-            &keyvault.vault.kdf_params,
+        // Concurrent updates all land, none overwrites another
+        std::thread::scope(|s| {
+            for i in 0..8 {
+                let path = &path;
+                s.spawn(move || {
+                    KeyVault::update(path, |keyvault| {
+                        keyvault.add_pubkey(&format!("m/44'/60'/{i}'/0/0"), "0xADDRESS");
+                        Ok(())
+                    })
+                    .unwrap();
+                });
+            }
+        });
+        assert_eq!(KeyVault::load(&path).unwrap().chain_keys.len(), 8 + 1);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_secrets() {
+        let key = PrivateKey::new(&mut [0xab; 32]);
+        assert!(!format!("{key:?}").contains("ab"));
+        assert_eq!(key.clone(), key);
+        assert!(PrivateKey::from_slice(&[0u8; 31]).is_err());
+
+        let mut bytes = [0xcd; 32];
+        let wiped = PrivateKey::new(&mut bytes);
+        assert_eq!(bytes, [0; 32]);
+        assert_eq!(wiped.expose(), &[0xcd; 32]);
+
+        let shown = std::sync::Arc::new(std::sync::Mutex::new(String::new()));
+        let display = {
+            let shown = shown.clone();
+            MnemonicDisplay::callback(move |mnemonic| {
+                *shown.lock().unwrap() = mnemonic.to_string();
+                Ok(true)
+            })
+        };
+        let hd_keys = HDkeys::new(bip39::Language::English, 12, None, &display).unwrap();
+        let restored = HDkeys::from_mnemonic(&shown.lock().unwrap(), None).unwrap();
+        assert_eq!(restored.seed, hd_keys.seed);
+
+        let declined = MnemonicDisplay::callback(|_| Ok(false));
+        assert!(matches!(
+            HDkeys::new(bip39::Language::English, 12, None, &declined),
+            Err(KeyVaultError::MnemonicDisplay(_))
+        ));
+    }
+
+    #[test]
+    fn test_watch_only_addresses() {
+        let hd_keys = HDkeys::from_mnemonic(TEST_MNEMONIC, None).unwrap();
+        let dir = std::env::temp_dir().join("bonanca_watch_only_test");
+        let mut keyvault = KeyVault::load(&test_vault(&dir)).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        // Derived from the stored account xpubs, without the password
+        assert!(keyvault.chain_keys.is_empty());
+        for path in [
+            "m/44'/60'/0'/0/0",
+            "m/44'/60'/0'/0/7",
+            "m/44'/60'/3'/0/0",
+            "m/44'/60'/9'/1/2",
+        ] {
+            assert_eq!(
+                keyvault.get_address(path).unwrap(),
+                hd_keys.derive_address(Chain::Evm, path).unwrap()
+            );
+        }
+
+        // Hardened Solana paths need the seed
+        let sol_path = Chain::Solana.child_path(0);
+        assert!(matches!(
+            keyvault.get_address(&sol_path),
+            Err(KeyVaultError::UnknownPath(_))
+        ));
+
+        // Past the stored xpubs
+        assert!(matches!(
+            keyvault.get_address(&Chain::Evm.child_path(10)),
+            Err(KeyVaultError::UnknownPath(_))
+        ));
+
+        let password = PasswordProvider::from("password");
+        keyvault
+            .precompute_addresses(&password, Chain::Solana, 0..3)
+            .unwrap();
+        keyvault
+            .precompute_addresses(&password, Chain::Evm, 10..12)
+            .unwrap();
+
+        assert_eq!(
+            keyvault.get_address(&sol_path).unwrap(),
+            hd_keys.derive_address(Chain::Solana, &sol_path).unwrap()
+        );
+        assert_eq!(keyvault.chain_keys.len(), 5);
+        assert!(keyvault.xpubs.contains_key("m/44'/60'/11'"));
+        assert_eq!(
+            keyvault.get_address("m/44'/60'/11'/0/4").unwrap(),
+            hd_keys
+                .derive_address(Chain::Evm, "m/44'/60'/11'/0/4")
+                .unwrap()
+        );
+
+        let old: KeyVault = serde_json::from_str(V0_KEYVAULT).unwrap();
+        assert!(old.xpubs.is_empty());
+    }
+
+    #[test]
+    fn test_accounts() {
+        let mut keyvault: KeyVault = serde_json::from_str(
+            r#"
+            {
+                "vault": {
+                    "cipher": "aes256-gcm",
+                    "cipher_params": { "nonce": "287189f34a1433d2de201d08" },
+                    "cipher_text": "00",
+                    "kdf": "pbkdf2",
+                    "kdf_params": { "key_length": 32, "n": 600000, "salt": "M6lWvNAGuZBSp9fBGAUEqw" },
+                    "mac": "",
+                    "salt": "M6lWvNAGuZBSp9fBGAUEqw"
+                },
+                "chain_keys": {
+                    "m/44'/60'/0'/0/0": "0x9858EfFD232B4033E47d90003D41EC34EcaEda94",
+                    "m/44'/501'/0'/0'": "HAgk14JpMQLgt6rVgv7cBQFJWFto5Dqxi472uT3DKpqk",
+                    "m/44'/501'/1'/0'": {
+                        "chain": "Solana",
+                        "path": "m/44'/501'/1'/0'",
+                        "address": "SOLANA_ADDRESS",
+                        "label": "treasury-sol",
+                        "created_at": 1700000000,
+                        "tags": ["ops"]
+                    }
+                }
+            }
+            "#,
+        )
+        .unwrap();
+
+        let evm_path = "m/44'/60'/0'/0/0";
+        let sol_path = "m/44'/501'/0'/0'";
+
+        // Legacy entries get their chain from the path
+        let evm = keyvault.get_account(evm_path).unwrap();
+        assert_eq!(evm.chain, Chain::Evm);
+        assert_eq!(evm.created_at, None);
+        assert_eq!(keyvault.get_account(sol_path).unwrap().chain, Chain::Solana);
+
+        let treasury = keyvault.find_account("treasury-sol").unwrap();
+        assert_eq!(treasury.address, "SOLANA_ADDRESS");
+        assert!(treasury.has_tag("ops"));
+
+        // EVM addresses match regardless of checksum case
+        assert_eq!(
+            keyvault
+                .find_account("0x9858effd232b4033e47d90003d41ec34ecaeda94")
+                .unwrap()
+                .path,
+            evm_path
+        );
+        assert!(keyvault.find_account("unknown").is_none());
+
+        keyvault.set_label(evm_path, Some("hot-evm")).unwrap();
+        assert!(matches!(
+            keyvault.set_label(sol_path, Some("hot-evm")),
+            Err(KeyVaultError::DuplicateLabel(_))
+        ));
+        assert!(matches!(
+            keyvault.set_label("m/44'/60'/9'/0/0", Some("missing")),
+            Err(KeyVaultError::UnknownPath(_))
+        ));
+
+        assert_eq!(
+            keyvault.resolve_path(Chain::Evm, "hot-evm").unwrap(),
+            evm_path
+        );
+        assert_eq!(
+            keyvault.resolve_path(Chain::Solana, sol_path).unwrap(),
+            sol_path
+        );
+        assert!(matches!(
+            keyvault.resolve_path(Chain::Evm, "treasury-sol"),
+            Err(KeyVaultError::UnknownKey(Chain::Evm, _))
+        ));
+
+        keyvault.add_tag(sol_path, "ops").unwrap();
+        keyvault.add_tag(sol_path, "ops").unwrap();
+        let ops = keyvault.accounts_with_tag("ops");
+        assert_eq!(ops.len(), 2);
+        assert_eq!(ops[0].path, sol_path);
+        keyvault.remove_tag(sol_path, "ops").unwrap();
+        assert_eq!(keyvault.accounts_with_tag("ops").len(), 1);
+
+        // Loading a wallet again keeps its label
+        keyvault.add_pubkey(evm_path, "0x9858EfFD232B4033E47d90003D41EC34EcaEda94");
+        keyvault.add_pubkey("m/44'/60'/1'/0/0", "0xNEW");
+        assert_eq!(
+            keyvault.get_account(evm_path).unwrap().label.as_deref(),
+            Some("hot-evm")
+        );
+        assert!(
+            keyvault
+                .get_account("m/44'/60'/1'/0/0")
+                .unwrap()
+                .created_at
+                .is_some()
+        );
+
+        let json = serde_json::to_string(&keyvault).unwrap();
+        let reloaded: KeyVault = serde_json::from_str(&json).unwrap();
+        assert_eq!(reloaded.chain_keys, keyvault.chain_keys);
+    }
+
+    #[test]
+    fn test_imported_keys() {
+        let dir = std::env::temp_dir().join("bonanca_imported_keys_test");
+        let mut keyvault = KeyVault::load(&test_vault(&dir)).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let password = PasswordProvider::from("password");
+        let evm_key = PrivateKey::from_slice(
+            &hex::decode("4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318")
+                .unwrap(),
+        )
+        .unwrap();
+        let sol_key = PrivateKey::new(&mut [7u8; 32]);
+
+        keyvault
+            .import_key("hot-evm", Chain::Evm, &evm_key, &password)
+            .unwrap();
+        keyvault
+            .import_key("hot-sol", Chain::Solana, &sol_key, &password)
+            .unwrap();
+
+        assert_eq!(
+            keyvault.imported_keys["hot-evm"].address,
+            "0x2c7536E3605D9C16a7a3D7b1898e529396a65c23"
+        );
+        assert!(
+            !keyvault.imported_keys["hot-evm"]
+                .vault
+                .cipher_text
+                .contains("4c0883a6")
+        );
+
+        assert!(matches!(
+            keyvault.import_key("hot-evm", Chain::Evm, &evm_key, &password),
+            Err(KeyVaultError::DuplicateLabel(_))
+        ));
+        assert!(matches!(
+            keyvault.import_key(
+                "other",
+                Chain::Evm,
+                &evm_key,
+                &PasswordProvider::from("wrong")
+            ),
+            Err(KeyVaultError::WrongPassword)
+        ));
+        assert!(matches!(
+            keyvault.get_imported_key("hot-evm", Chain::Solana, &password),
+            Err(KeyVaultError::UnknownKey(..))
+        ));
+
+        let new_password = PasswordProvider::from("new password");
+        keyvault.rotate_password(&password, &new_password).unwrap();
+
+        let json = serde_json::to_string(&keyvault).unwrap();
+        let keyvault: KeyVault = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(
+            keyvault
+                .get_imported_key("hot-evm", Chain::Evm, &new_password)
+                .unwrap(),
+            evm_key
+        );
+        assert_eq!(
+            keyvault
+                .get_imported_key("hot-sol", Chain::Solana, &new_password)
+                .unwrap(),
+            sol_key
+        );
+        assert!(matches!(
+            keyvault.get_imported_key("hot-sol", Chain::Solana, &password),
+            Err(KeyVaultError::WrongPassword)
+        ));
+    }
+
+    #[test]
+    fn test_eth_keystore_vectors() {
+        // Test vectors from the Web3 Secret Storage Definition
+        let pbkdf2: EthKeystore = serde_json::from_str(
+            r#"
+            {
+                "crypto": {
+                    "cipher": "aes-128-ctr",
+                    "cipherparams": { "iv": "6087dab2f9fdbbfaddc31a909735c1e6" },
+                    "ciphertext": "5318b4d5bcd28de64ee5559e671353e16f075ecae9f99c7a79a38af5f869aa46",
+                    "kdf": "pbkdf2",
+                    "kdfparams": {
+                        "c": 262144,
+                        "dklen": 32,
+                        "prf": "hmac-sha256",
+                        "salt": "ae3cd4e7013836a3df6bd7241b12db061dbe2c6785853cce422d148a624ce0bd"
+                    },
+                    "mac": "517ead924a9d0dc3124507e3393d175ce3ff7c1e96529c6c555ce9e51205e9b2"
+                },
+                "id": "3198bc9c-6672-5ab3-d995-4942343ae5b6",
+                "version": 3
+            }
+            "#,
         )
         .unwrap();
 
-        assert_eq!(seed, decrypted_seed);
+        let secret = "7a28b5ba57c53603b0b07b56bba752f7784bf506fa95edc395f5cf6c7514fe9d";
+
+        assert_eq!(
+            hex::encode(pbkdf2.decrypt("testpassword").unwrap().expose()),
+            secret
+        );
+        assert!(matches!(
+            pbkdf2.decrypt("wrong"),
+            Err(KeyVaultError::WrongPassword)
+        ));
+
+        // foundry's scrypt cost, built with Python's hashlib.scrypt and
+        // OpenSSL's AES-128-CTR from the same secret and password
+        let scrypt = |n: u32, r: u32| -> EthKeystore {
+            serde_json::from_value(serde_json::json!({
+                "crypto": {
+                    "cipher": "aes-128-ctr",
+                    "cipherparams": { "iv": "83dbcc02d8ccb40e466191a123791e0e" },
+                    "ciphertext": "91c23c03a8b5117a6e3d78dbb5dfc46f037aff9351dff59c2879586f51756502",
+                    "kdf": "scrypt",
+                    "kdfparams": {
+                        "dklen": 32,
+                        "n": n,
+                        "p": 1,
+                        "r": r,
+                        "salt": "ab0c7876052600dd703518d6fc3fe8984592145b591fc8fb5c6d43190334ba19"
+                    },
+                    "mac": "8f8639fe08fe1f43d6c13006d66ad2d993c6674ee2ba03f025596797a73614b9"
+                },
+                "id": "3198bc9c-6672-5ab3-d995-4942343ae5b6",
+                "version": 3
+            }))
+            .unwrap()
+        };
+
+        assert_eq!(
+            hex::encode(scrypt(8192, 8).decrypt("testpassword").unwrap().expose()),
+            secret
+        );
+        assert!(matches!(
+            scrypt(8192, 8).decrypt("wrong"),
+            Err(KeyVaultError::WrongPassword)
+        ));
+
+        // Rejected before any work: N >= 2^(16r), as in the Web3 Secret
+        // Storage vector, and a cost that would need 256 GiB
+        assert!(matches!(
+            scrypt(1 << 18, 1).decrypt("testpassword"),
+            Err(KeyVaultError::Keystore(msg)) if msg.contains("Invalid scrypt params")
+        ));
+        assert!(matches!(
+            scrypt(1 << 31, 1).decrypt("testpassword"),
+            Err(KeyVaultError::Keystore(msg)) if msg.contains("above the limit")
+        ));
+    }
+
+    #[test]
+    fn test_keystore_exports() {
+        let hd_keys = HDkeys::from_mnemonic(TEST_MNEMONIC, None).unwrap();
+        let dir = std::env::temp_dir().join("bonanca_keystore_exports_test");
+        let mut keyvault = KeyVault::load(&test_vault(&dir)).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        let password = PasswordProvider::from("password");
+        let keystore_password = PasswordProvider::from("keystore");
+
+        let evm_path = Chain::Evm.child_path(0);
+        let keystore = keyvault
+            .export_eth_keystore(&evm_path, &password, &keystore_password)
+            .unwrap();
+        let evm_secret = hd_keys
+            .derive_secp256k1_child_prvkey(evm_path.clone())
+            .unwrap();
+
+        let json = serde_json::to_string(&keystore).unwrap();
+        let keystore: EthKeystore = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(keystore.decrypt("keystore").unwrap(), evm_secret);
+        assert_eq!(
+            format!("0x{}", keystore.address.as_ref().unwrap()),
+            hd_keys
+                .derive_address(Chain::Evm, &evm_path)
+                .unwrap()
+                .to_lowercase()
+        );
+
+        let sol_path = Chain::Solana.child_path(0);
+        let sol_json = keyvault
+            .export_solana_keypair(&sol_path, &password)
+            .unwrap();
+        let sol_secret = hd_keys.derive_ed25519_child_prvkey(sol_path).unwrap();
+
+        assert_eq!(decode_solana_keypair(&sol_json).unwrap(), sol_secret);
+        assert_eq!(encode_solana_keypair(&sol_secret), sol_json);
+
+        // The public half must match the secret
+        let mut bytes: Vec<u8> = serde_json::from_str(&sol_json).unwrap();
+        bytes[63] ^= 1;
+        let tampered = serde_json::to_string(&bytes).unwrap();
+        assert!(matches!(
+            decode_solana_keypair(&tampered),
+            Err(KeyVaultError::Keystore(_))
+        ));
+
+        let path = std::env::temp_dir().join("bonanca_solana_keypair_test.json");
+        std::fs::write(&path, &sol_json).unwrap();
+        keyvault
+            .import_solana_keypair("sol", &path, &password)
+            .unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        keyvault
+            .import_eth_keystore("evm", &keystore, &keystore_password, &password)
+            .unwrap();
+
+        assert_eq!(
+            keyvault
+                .get_imported_key("evm", Chain::Evm, &password)
+                .unwrap(),
+            evm_secret
+        );
+        assert_eq!(
+            keyvault
+                .get_imported_key("sol", Chain::Solana, &password)
+                .unwrap(),
+            sol_secret
+        );
     }
+
+    const V0_KEYVAULT: &str = r#"
+        {
+            "vault": {
+                "cipher": "aes256-gcm",
+                "cipher_params": { "nonce": "287189f34a1433d2de201d08" },
+                "cipher_text": "7a34170003c0a7b3ccb75bac28757801a7d9b5e1ff062afa4af5f3c03e7d8982eb1f36ccce87436e42b44ffea6bcf39eba8c15d4e79ee0bf012811fca81ae1e112c0f8ae5d8e43ac8cad1ae961b11207",
+                "kdf": "pbkdf2",
+                "kdf_params": {
+                "key_length": 32,
+                "n": 600000,
+                "salt": "M6lWvNAGuZBSp9fBGAUEqw"
+                },
+                "mac": "$argon2id$v=19$m=19456,t=2,p=1$M6lWvNAGuZBSp9fBGAUEqw$/U5VYPmg3+BQj0ttOyPnOUjH7bP23V9/tgvBpovna/8",
+                "salt": "M6lWvNAGuZBSp9fBGAUEqw"
+            },
+            "chain_keys": {
+                "PATH": "ADDRESS"
+            }
+        }
+    "#;
 }
@@ -0,0 +1,137 @@
+#[cfg(unix)]
+use std::fs::File;
+use std::fs::read_to_string;
+#[cfg(unix)]
+use std::os::fd::{AsFd, BorrowedFd, OwnedFd};
+use std::path::PathBuf;
+#[cfg(unix)]
+use std::sync::OnceLock;
+
+use rpassword::prompt_password;
+use zeroize::{Zeroize, Zeroizing};
+
+use crate::error::{KeyVaultError, Result};
+
+type PasswordCallback = Box<dyn Fn(&str) -> anyhow::Result<String> + Send + Sync>;
+
+pub enum PasswordProvider {
+    Prompt,
+    Env(String),
+    File(PathBuf),
+    /// Built with `PasswordProvider::fd`.
+    #[cfg(unix)]
+    Fd(FdPassword),
+    Callback(PasswordCallback),
+    Value(Zeroizing<String>),
+}
+
+/// A password read from a file descriptor. The descriptor can only be
+/// read to EOF once, so the password is kept for later prompts.
+#[cfg(unix)]
+pub struct FdPassword {
+    file: File,
+    cached: OnceLock<Zeroizing<String>>,
+}
+
+#[cfg(unix)]
+impl AsFd for FdPassword {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.file.as_fd()
+    }
+}
+
+#[cfg(unix)]
+impl FdPassword {
+    fn get(&self) -> Result<String> {
+        match self.cached.get() {
+            Some(password) => Ok(password.to_string()),
+            None => {
+                let password = read_fd(&self.file)?;
+                Ok(self
+                    .cached
+                    .get_or_init(|| Zeroizing::new(password))
+                    .to_string())
+            }
+        }
+    }
+}
+
+impl PasswordProvider {
+    pub fn callback<F>(f: F) -> Self
+    where
+        F: Fn(&str) -> anyhow::Result<String> + Send + Sync + 'static,
+    {
+        Self::Callback(Box::new(f))
+    }
+
+    /// Reads the password from `fd`, e.g. a pipe set up by a supervisor,
+    /// and closes it when the provider is dropped
+    #[cfg(unix)]
+    pub fn fd(fd: OwnedFd) -> Self {
+        Self::Fd(FdPassword {
+            file: File::from(fd),
+            cached: OnceLock::new(),
+        })
+    }
+
+    pub fn get_password(&self, prompt: &str) -> Result<String> {
+        let password = match self {
+            Self::Prompt => prompt_password(prompt)
+                .map_err(|e| KeyVaultError::Password(format!("Could not prompt: {e}")))?,
+            Self::Env(var) => std::env::var(var).map_err(|_| {
+                KeyVaultError::Password(format!("Password variable {var} is not set"))
+            })?,
+            Self::File(path) => {
+                let mut contents = read_to_string(path).map_err(|e| {
+                    KeyVaultError::Password(format!(
+                        "Could not read password file {}: {e}",
+                        path.display()
+                    ))
+                })?;
+                let password = trim_newline(&contents).to_string();
+                contents.zeroize();
+                password
+            }
+            #[cfg(unix)]
+            Self::Fd(fd) => fd.get()?,
+            Self::Callback(f) => f(prompt).map_err(|e| KeyVaultError::Password(e.to_string()))?,
+            Self::Value(password) => password.to_string(),
+        };
+
+        Ok(password)
+    }
+}
+
+impl From<&str> for PasswordProvider {
+    fn from(password: &str) -> Self {
+        Self::Value(Zeroizing::new(password.to_string()))
+    }
+}
+
+impl From<String> for PasswordProvider {
+    fn from(password: String) -> Self {
+        Self::Value(Zeroizing::new(password))
+    }
+}
+
+fn trim_newline(s: &str) -> &str {
+    s.strip_suffix("\r\n")
+        .or_else(|| s.strip_suffix('\n'))
+        .unwrap_or(s)
+}
+
+#[cfg(unix)]
+fn read_fd(file: &File) -> Result<String> {
+    use std::io::Read;
+
+    let mut contents = Zeroizing::new(String::new());
+    // `&File` reads without needing the provider to be mutable
+    let mut reader = file;
+    reader.read_to_string(&mut contents).map_err(|e| {
+        KeyVaultError::Password(format!(
+            "Could not read password from a file descriptor: {e}"
+        ))
+    })?;
+
+    Ok(trim_newline(&contents).to_string())
+}
@@ -0,0 +1,113 @@
+use std::fmt;
+
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+use crate::error::{KeyVaultError, Result};
+
+pub type Seed = Secret<64>;
+pub type PrivateKey = Secret<32>;
+
+/// Secret bytes kept on the heap, so moving the wrapper never leaves copies
+/// behind. The bytes are zeroized on drop, hidden from `Debug`, and with the
+/// `mlock` feature locked in memory so they are never swapped to disk.
+pub struct Secret<const N: usize> {
+    bytes: Box<[u8; N]>,
+}
+
+impl<const N: usize> Secret<N> {
+    pub fn zeroed() -> Self {
+        let bytes = Box::new([0u8; N]);
+        lock_memory(bytes.as_ptr(), N);
+
+        Self { bytes }
+    }
+
+    /// Moves `bytes` into the secret and zeroizes the caller's buffer
+    pub fn new(bytes: &mut [u8; N]) -> Self {
+        let mut secret = Self::zeroed();
+        secret.bytes.copy_from_slice(bytes);
+        bytes.zeroize();
+
+        secret
+    }
+
+    pub fn from_slice(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() != N {
+            return Err(KeyVaultError::Derivation(format!(
+                "Expected a {N}-byte secret, got {} bytes",
+                bytes.len()
+            )));
+        }
+
+        let mut secret = Self::zeroed();
+        secret.bytes.copy_from_slice(bytes);
+
+        Ok(secret)
+    }
+
+    pub fn expose(&self) -> &[u8; N] {
+        &self.bytes
+    }
+
+    pub fn expose_mut(&mut self) -> &mut [u8; N] {
+        &mut self.bytes
+    }
+}
+
+impl<const N: usize> Clone for Secret<N> {
+    fn clone(&self) -> Self {
+        let mut secret = Self::zeroed();
+        secret.bytes.copy_from_slice(self.expose());
+
+        secret
+    }
+}
+
+impl<const N: usize> PartialEq for Secret<N> {
+    fn eq(&self, other: &Self) -> bool {
+        // Constant time, so comparisons don't leak where secrets differ
+        self.bytes
+            .iter()
+            .zip(other.bytes.iter())
+            .fold(0, |acc, (a, b)| acc | (a ^ b))
+            == 0
+    }
+}
+
+impl<const N: usize> Eq for Secret<N> {}
+
+impl<const N: usize> fmt::Debug for Secret<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Secret<{N}>([REDACTED])")
+    }
+}
+
+impl<const N: usize> Drop for Secret<N> {
+    fn drop(&mut self) {
+        self.bytes.zeroize();
+        unlock_memory(self.bytes.as_ptr(), N);
+    }
+}
+
+impl<const N: usize> ZeroizeOnDrop for Secret<N> {}
+
+#[cfg(all(feature = "mlock", unix))]
+fn lock_memory(ptr: *const u8, len: usize) {
+    // Best effort, locking fails when RLIMIT_MEMLOCK is exhausted
+    unsafe {
+        libc::mlock(ptr.cast(), len);
+    }
+}
+
+#[cfg(all(feature = "mlock", unix))]
+fn unlock_memory(ptr: *const u8, len: usize) {
+    unsafe {
+        libc::munlock(ptr.cast(), len);
+    }
+}
+
+#[cfg(not(all(feature = "mlock", unix)))]
+fn lock_memory(_ptr: *const u8, _len: usize) {}
+
+#[cfg(not(all(feature = "mlock", unix)))]
+fn unlock_memory(_ptr: *const u8, _len: usize) {}
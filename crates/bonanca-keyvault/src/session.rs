@@ -0,0 +1,144 @@
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard, Weak};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::chains::Chain;
+use crate::error::{KeyVaultError, Result};
+use crate::hd_keys::HDkeys;
+use crate::keyvault::KeyVault;
+use crate::password::PasswordProvider;
+
+/// A `KeyVault` that has been decrypted once and can hand out child keys
+/// without asking for the password again.
+///
+/// The seed is wiped when the session is dropped, when `lock` is called, or
+/// after it has gone unused for longer than the idle timeout.
+pub struct UnlockedVault {
+    path: PathBuf,
+    key_vault: Mutex<KeyVault>,
+    session: Arc<Mutex<Session>>,
+}
+
+struct Session {
+    hd_keys: Option<HDkeys>,
+    last_used: Instant,
+}
+
+impl UnlockedVault {
+    pub fn open(fname: &Path, password: &PasswordProvider) -> Result<Self> {
+        let key_vault = KeyVault::load(fname)?;
+        let hd_keys = key_vault.decrypt_vault(password)?;
+
+        Ok(Self {
+            path: fname.to_path_buf(),
+            key_vault: Mutex::new(key_vault),
+            session: Arc::new(Mutex::new(Session {
+                hd_keys: Some(hd_keys),
+                last_used: Instant::now(),
+            })),
+        })
+    }
+
+    pub fn open_with_timeout(
+        fname: &Path,
+        password: &PasswordProvider,
+        timeout: Duration,
+    ) -> Result<Self> {
+        let unlocked = Self::open(fname, password)?;
+
+        spawn_idle_timer(Arc::downgrade(&unlocked.session), timeout);
+
+        Ok(unlocked)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn with_keys<R>(&self, f: impl FnOnce(&HDkeys) -> R) -> Result<R> {
+        let mut session = lock(&self.session);
+        session.last_used = Instant::now();
+
+        let hd_keys = session.hd_keys.as_ref().ok_or(KeyVaultError::Locked)?;
+
+        Ok(f(hd_keys))
+    }
+
+    pub fn get_pubkey(&self, path: &str) -> Option<String> {
+        lock(&self.key_vault)
+            .chain_keys
+            .get(path)
+            .map(|account| account.address.clone())
+    }
+
+    /// Address at `path`, from `chain_keys` when it is already known
+    pub fn derive_address(&self, chain: Chain, path: &str) -> Result<String> {
+        if let Some(address) = self.get_pubkey(path) {
+            return Ok(address);
+        }
+
+        self.with_keys(|hd_keys| hd_keys.derive_address(chain, path))?
+    }
+
+    /// Records the pubkey for `path`, writing the vault file if it is new.
+    pub fn add_pubkey(&self, path: &str, pubkey: &str) -> Result<()> {
+        let mut key_vault = lock(&self.key_vault);
+
+        if !key_vault.chain_keys.contains_key(path) {
+            *key_vault = KeyVault::update(&self.path, |key_vault| {
+                key_vault.add_pubkey(path, pubkey);
+                Ok(())
+            })?;
+        }
+
+        Ok(())
+    }
+
+    pub fn resolve_path(&self, chain: Chain, label_or_path: &str) -> Result<String> {
+        lock(&self.key_vault).resolve_path(chain, label_or_path)
+    }
+
+    pub fn lock(&self) {
+        lock(&self.session).hd_keys = None;
+    }
+
+    pub fn is_locked(&self) -> bool {
+        lock(&self.session).hd_keys.is_none()
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn spawn_idle_timer(session: Weak<Mutex<Session>>, timeout: Duration) {
+    thread::spawn(move || {
+        loop {
+            // Stop once the session has been dropped or locked
+            let Some(session) = session.upgrade() else {
+                break;
+            };
+
+            let wait = {
+                let mut session = lock(&session);
+
+                if session.hd_keys.is_none() {
+                    break;
+                }
+
+                let idle = session.last_used.elapsed();
+
+                if idle >= timeout {
+                    session.hd_keys = None;
+                    break;
+                }
+
+                timeout - idle
+            };
+
+            drop(session);
+            thread::sleep(wait);
+        }
+    });
+}
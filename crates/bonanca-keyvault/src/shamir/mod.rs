@@ -0,0 +1,653 @@
+//! SLIP-39 Shamir secret sharing
+//!
+//! https://github.com/satoshilabs/slips/blob/master/slip-0039.md
+
+mod wordlist;
+
+use std::collections::BTreeMap;
+
+use aes_gcm::aead::{OsRng, rand_core::RngCore};
+use hmac::{Hmac, Mac};
+use pbkdf2::pbkdf2_hmac;
+use sha2::Sha256;
+use zeroize::Zeroizing;
+
+use crate::error::{KeyVaultError, Result};
+use wordlist::WORDLIST;
+
+const RADIX_BITS: usize = 10;
+const CHECKSUM_LENGTH_WORDS: usize = 3;
+const METADATA_LENGTH_WORDS: usize = 7;
+const MIN_STRENGTH_BYTES: usize = 16;
+const MIN_MNEMONIC_LENGTH_WORDS: usize = 20;
+const MAX_SHARE_COUNT: usize = 16;
+const DIGEST_LENGTH_BYTES: usize = 4;
+const DIGEST_INDEX: u8 = 254;
+const SECRET_INDEX: u8 = 255;
+const BASE_ITERATION_COUNT: u32 = 10_000;
+const ROUND_COUNT: u8 = 4;
+
+const GF_TABLES: ([u8; 255], [u8; 256]) = gf_tables();
+
+/// Splits `master_secret` into SLIP-39 mnemonic shares.
+///
+/// `groups` holds a `(member_threshold, member_count)` pair per group, and
+/// `group_threshold` of those groups are needed to recover the secret.
+pub fn split_secret(
+    master_secret: &[u8],
+    passphrase: &str,
+    group_threshold: u8,
+    groups: &[(u8, u8)],
+    extendable: bool,
+    iteration_exponent: u8,
+) -> Result<Vec<Vec<String>>> {
+    if master_secret.len() < MIN_STRENGTH_BYTES || !master_secret.len().is_multiple_of(2) {
+        return Err(shares_error(format!(
+            "Master secret must be an even number of bytes, at least {MIN_STRENGTH_BYTES}"
+        )));
+    }
+
+    if groups.is_empty() || groups.len() > MAX_SHARE_COUNT {
+        return Err(shares_error(format!(
+            "Group count must be between 1 and {MAX_SHARE_COUNT}"
+        )));
+    }
+
+    if group_threshold == 0 || usize::from(group_threshold) > groups.len() {
+        return Err(shares_error(format!(
+            "Group threshold must be between 1 and {}",
+            groups.len()
+        )));
+    }
+
+    for &(member_threshold, member_count) in groups {
+        if member_threshold == 0
+            || member_threshold > member_count
+            || usize::from(member_count) > MAX_SHARE_COUNT
+        {
+            return Err(shares_error(format!(
+                "Invalid {member_threshold}-of-{member_count} group"
+            )));
+        }
+
+        if member_threshold == 1 && member_count > 1 {
+            return Err(shares_error(
+                "Use a 1-of-1 group instead of several 1-of-N shares".to_string(),
+            ));
+        }
+    }
+
+    if iteration_exponent > 15 {
+        return Err(shares_error(
+            "Iteration exponent must be at most 15".to_string(),
+        ));
+    }
+
+    check_passphrase(passphrase)?;
+
+    let identifier = (OsRng.next_u32() & 0x7fff) as u16;
+    let encrypted = encrypt(
+        master_secret,
+        passphrase,
+        iteration_exponent,
+        identifier,
+        extendable,
+    );
+
+    let group_shares = share_secret(group_threshold, groups.len() as u8, &encrypted)?;
+
+    groups
+        .iter()
+        .zip(group_shares)
+        .map(
+            |(&(member_threshold, member_count), (group_index, group_secret))| {
+                let member_shares = share_secret(member_threshold, member_count, &group_secret)?;
+
+                Ok(member_shares
+                    .into_iter()
+                    .map(|(member_index, value)| {
+                        Share {
+                            identifier,
+                            extendable,
+                            iteration_exponent,
+                            group_index,
+                            group_threshold,
+                            group_count: groups.len() as u8,
+                            member_index,
+                            member_threshold,
+                            value,
+                        }
+                        .to_mnemonic()
+                    })
+                    .collect())
+            },
+        )
+        .collect()
+}
+
+/// Recovers the master secret from a quorum of SLIP-39 mnemonic shares.
+pub fn combine_shares<S: AsRef<str>>(
+    mnemonics: &[S],
+    passphrase: &str,
+) -> Result<Zeroizing<Vec<u8>>> {
+    let shares = mnemonics
+        .iter()
+        .map(|m| Share::from_mnemonic(m.as_ref()))
+        .collect::<Result<Vec<Share>>>()?;
+
+    let Some(first) = shares.first() else {
+        return Err(shares_error("No shares given".to_string()));
+    };
+
+    let mut groups: BTreeMap<u8, Vec<&Share>> = BTreeMap::new();
+
+    for share in &shares {
+        if share.identifier != first.identifier
+            || share.extendable != first.extendable
+            || share.iteration_exponent != first.iteration_exponent
+            || share.group_threshold != first.group_threshold
+            || share.group_count != first.group_count
+        {
+            return Err(shares_error(
+                "Shares do not belong to the same secret".to_string(),
+            ));
+        }
+
+        let members = groups.entry(share.group_index).or_default();
+
+        if members
+            .iter()
+            .any(|m| m.member_threshold != share.member_threshold)
+        {
+            return Err(shares_error(format!(
+                "Shares in group {} have different thresholds",
+                share.group_index
+            )));
+        }
+
+        match members
+            .iter()
+            .find(|m| m.member_index == share.member_index)
+        {
+            Some(m) if m.value == share.value => continue,
+            Some(_) => {
+                return Err(shares_error(format!(
+                    "Conflicting shares for member {} of group {}",
+                    share.member_index, share.group_index
+                )));
+            }
+            None => members.push(share),
+        }
+    }
+
+    let complete: Vec<(u8, &Vec<&Share>)> = groups
+        .iter()
+        .filter(|(_, members)| members.len() >= usize::from(members[0].member_threshold))
+        .map(|(index, members)| (*index, members))
+        .collect();
+
+    if complete.len() < usize::from(first.group_threshold) {
+        return Err(shares_error(format!(
+            "Need {} complete groups, got {}",
+            first.group_threshold,
+            complete.len()
+        )));
+    }
+
+    let group_secrets = complete
+        .iter()
+        .take(usize::from(first.group_threshold))
+        .map(|(group_index, members)| {
+            let threshold = members[0].member_threshold;
+            let points: Vec<(u8, &[u8])> = members
+                .iter()
+                .take(usize::from(threshold))
+                .map(|m| (m.member_index, m.value.as_slice()))
+                .collect();
+
+            Ok((*group_index, recover_secret(threshold, &points)?))
+        })
+        .collect::<Result<Vec<(u8, Zeroizing<Vec<u8>>)>>>()?;
+
+    let points: Vec<(u8, &[u8])> = group_secrets
+        .iter()
+        .map(|(index, secret)| (*index, secret.as_slice()))
+        .collect();
+    let encrypted = recover_secret(first.group_threshold, &points)?;
+
+    check_passphrase(passphrase)?;
+
+    Ok(decrypt(
+        &encrypted,
+        passphrase,
+        first.iteration_exponent,
+        first.identifier,
+        first.extendable,
+    ))
+}
+
+pub(crate) struct Share {
+    identifier: u16,
+    extendable: bool,
+    iteration_exponent: u8,
+    pub(crate) group_index: u8,
+    group_threshold: u8,
+    pub(crate) group_count: u8,
+    member_index: u8,
+    member_threshold: u8,
+    value: Zeroizing<Vec<u8>>,
+}
+
+impl Share {
+    pub(crate) fn from_mnemonic(mnemonic: &str) -> Result<Self> {
+        let indices = mnemonic
+            .split_whitespace()
+            .map(word_index)
+            .collect::<Result<Vec<u16>>>()?;
+
+        if indices.len() < MIN_MNEMONIC_LENGTH_WORDS {
+            return Err(shares_error(format!(
+                "Share must be at least {MIN_MNEMONIC_LENGTH_WORDS} words"
+            )));
+        }
+
+        let padding = (RADIX_BITS * (indices.len() - METADATA_LENGTH_WORDS)) % 16;
+        if padding > 8 {
+            return Err(shares_error("Invalid share length".to_string()));
+        }
+
+        let id_exp = join_words(indices[0], indices[1]);
+        let extendable = (id_exp >> 4) & 1 == 1;
+
+        if rs1024_polymod(customization(extendable), &indices) != 1 {
+            return Err(shares_error(format!(
+                "Invalid checksum in share starting with \"{} {}\"",
+                WORDLIST[usize::from(indices[0])],
+                WORDLIST[usize::from(indices[1])]
+            )));
+        }
+
+        let params = join_words(indices[2], indices[3]);
+        let group_threshold = ((params >> 12) & 0xf) as u8 + 1;
+        let group_count = ((params >> 8) & 0xf) as u8 + 1;
+
+        if group_count < group_threshold {
+            return Err(shares_error(
+                "Group threshold is larger than the group count".to_string(),
+            ));
+        }
+
+        let group_index = (params >> 16) as u8;
+        if group_index >= group_count {
+            return Err(shares_error(format!(
+                "Group index {group_index} is out of range for {group_count} groups"
+            )));
+        }
+
+        let value_words = &indices[4..indices.len() - CHECKSUM_LENGTH_WORDS];
+        let value = words_to_bytes(value_words, padding)?;
+
+        if value.len() < MIN_STRENGTH_BYTES || !value.len().is_multiple_of(2) {
+            return Err(shares_error(format!(
+                "Share value must be an even number of bytes, at least {MIN_STRENGTH_BYTES}"
+            )));
+        }
+
+        Ok(Self {
+            identifier: (id_exp >> 5) as u16,
+            extendable,
+            iteration_exponent: (id_exp & 0xf) as u8,
+            group_index,
+            group_threshold,
+            group_count,
+            member_index: ((params >> 4) & 0xf) as u8,
+            member_threshold: (params & 0xf) as u8 + 1,
+            value,
+        })
+    }
+
+    pub(crate) fn to_mnemonic(&self) -> String {
+        let id_exp = (u32::from(self.identifier) << 5)
+            | (u32::from(self.extendable) << 4)
+            | u32::from(self.iteration_exponent);
+        let params = (u32::from(self.group_index) << 16)
+            | (u32::from(self.group_threshold - 1) << 12)
+            | (u32::from(self.group_count - 1) << 8)
+            | (u32::from(self.member_index) << 4)
+            | u32::from(self.member_threshold - 1);
+
+        let mut indices = Vec::new();
+        indices.extend(split_words(id_exp));
+        indices.extend(split_words(params));
+        indices.extend(bytes_to_words(&self.value));
+
+        let padded = [&indices[..], &[0; CHECKSUM_LENGTH_WORDS]].concat();
+        let checksum = rs1024_polymod(customization(self.extendable), &padded) ^ 1;
+        indices.extend(
+            (0..CHECKSUM_LENGTH_WORDS)
+                .rev()
+                .map(|i| ((checksum >> (RADIX_BITS * i)) & 0x3ff) as u16),
+        );
+
+        indices
+            .iter()
+            .map(|i| WORDLIST[usize::from(*i)])
+            .collect::<Vec<&str>>()
+            .join(" ")
+    }
+}
+
+fn shares_error(msg: String) -> KeyVaultError {
+    KeyVaultError::Shares(msg)
+}
+
+fn check_passphrase(passphrase: &str) -> Result<()> {
+    if passphrase.bytes().all(|b| (32..=126).contains(&b)) {
+        Ok(())
+    } else {
+        Err(shares_error(
+            "Passphrase must only contain printable ASCII characters".to_string(),
+        ))
+    }
+}
+
+fn word_index(word: &str) -> Result<u16> {
+    WORDLIST
+        .binary_search(&word.to_lowercase().as_str())
+        .map(|i| i as u16)
+        .map_err(|_| shares_error(format!("Unknown word: {word}")))
+}
+
+fn join_words(high: u16, low: u16) -> u32 {
+    (u32::from(high) << RADIX_BITS) | u32::from(low)
+}
+
+fn split_words(value: u32) -> [u16; 2] {
+    [
+        ((value >> RADIX_BITS) & 0x3ff) as u16,
+        (value & 0x3ff) as u16,
+    ]
+}
+
+fn bytes_to_words(bytes: &[u8]) -> Vec<u16> {
+    // The value is left padded with zero bits up to a whole number of words
+    let word_count = (bytes.len() * 8).div_ceil(RADIX_BITS);
+    let mut bits = word_count * RADIX_BITS - bytes.len() * 8;
+    let mut acc: u32 = 0;
+    let mut words = Vec::with_capacity(word_count);
+
+    for byte in bytes {
+        acc = (acc << 8) | u32::from(*byte);
+        bits += 8;
+
+        if bits >= RADIX_BITS {
+            bits -= RADIX_BITS;
+            words.push((acc >> bits) as u16);
+            acc &= (1 << bits) - 1;
+        }
+    }
+
+    words
+}
+
+fn words_to_bytes(words: &[u16], padding: usize) -> Result<Zeroizing<Vec<u8>>> {
+    if words[0] >> (RADIX_BITS - padding) != 0 {
+        return Err(shares_error("Invalid share padding".to_string()));
+    }
+
+    let mut bytes = Zeroizing::new(Vec::with_capacity((words.len() * RADIX_BITS - padding) / 8));
+    let mut bits = 0;
+    let mut acc: u32 = 0;
+
+    for (i, word) in words.iter().enumerate() {
+        acc = (acc << RADIX_BITS) | u32::from(*word);
+        bits += if i == 0 {
+            RADIX_BITS - padding
+        } else {
+            RADIX_BITS
+        };
+
+        while bits >= 8 {
+            bits -= 8;
+            bytes.push((acc >> bits) as u8);
+            acc &= (1 << bits) - 1;
+        }
+    }
+
+    Ok(bytes)
+}
+
+fn customization(extendable: bool) -> &'static [u8] {
+    if extendable {
+        b"shamir_extendable"
+    } else {
+        b"shamir"
+    }
+}
+
+fn rs1024_polymod(customization: &[u8], values: &[u16]) -> u32 {
+    const GEN: [u32; 10] = [
+        0xe0e040, 0x1c1c080, 0x3838100, 0x7070200, 0xe0e0009, 0x1c0c2412, 0x38086c24, 0x3090fc48,
+        0x21b1f890, 0x3f3f120,
+    ];
+
+    let mut chk: u32 = 1;
+
+    for value in customization
+        .iter()
+        .map(|b| u32::from(*b))
+        .chain(values.iter().map(|v| u32::from(*v)))
+    {
+        let b = chk >> 20;
+        chk = ((chk & 0xfffff) << 10) ^ value;
+
+        for (i, g) in GEN.iter().enumerate() {
+            if (b >> i) & 1 == 1 {
+                chk ^= g;
+            }
+        }
+    }
+
+    chk
+}
+
+fn encrypt(
+    master_secret: &[u8],
+    passphrase: &str,
+    iteration_exponent: u8,
+    identifier: u16,
+    extendable: bool,
+) -> Zeroizing<Vec<u8>> {
+    let salt = feistel_salt(identifier, extendable);
+
+    feistel(
+        master_secret,
+        passphrase,
+        iteration_exponent,
+        &salt,
+        0..ROUND_COUNT,
+    )
+}
+
+fn decrypt(
+    encrypted: &[u8],
+    passphrase: &str,
+    iteration_exponent: u8,
+    identifier: u16,
+    extendable: bool,
+) -> Zeroizing<Vec<u8>> {
+    let salt = feistel_salt(identifier, extendable);
+
+    feistel(
+        encrypted,
+        passphrase,
+        iteration_exponent,
+        &salt,
+        (0..ROUND_COUNT).rev(),
+    )
+}
+
+fn feistel_salt(identifier: u16, extendable: bool) -> Vec<u8> {
+    if extendable {
+        Vec::new()
+    } else {
+        [b"shamir".as_slice(), &identifier.to_be_bytes()].concat()
+    }
+}
+
+fn feistel(
+    secret: &[u8],
+    passphrase: &str,
+    iteration_exponent: u8,
+    salt: &[u8],
+    rounds: impl Iterator<Item = u8>,
+) -> Zeroizing<Vec<u8>> {
+    let half = secret.len() / 2;
+    let mut left = Zeroizing::new(secret[..half].to_vec());
+    let mut right = Zeroizing::new(secret[half..].to_vec());
+    let iterations = (BASE_ITERATION_COUNT << iteration_exponent) / u32::from(ROUND_COUNT);
+
+    for round in rounds {
+        let password = Zeroizing::new([&[round], passphrase.as_bytes()].concat());
+        let mut f = Zeroizing::new(vec![0u8; right.len()]);
+        pbkdf2_hmac::<Sha256>(&password, &[salt, &right].concat(), iterations, &mut f);
+
+        let next = Zeroizing::new(left.iter().zip(f.iter()).map(|(l, f)| l ^ f).collect());
+        left = std::mem::replace(&mut right, next);
+    }
+
+    Zeroizing::new([right.as_slice(), left.as_slice()].concat())
+}
+
+fn share_secret(threshold: u8, count: u8, secret: &[u8]) -> Result<Vec<(u8, Zeroizing<Vec<u8>>)>> {
+    if threshold == 1 {
+        return Ok((0..count)
+            .map(|i| (i, Zeroizing::new(secret.to_vec())))
+            .collect());
+    }
+
+    let random_count = threshold - 2;
+
+    let mut shares: Vec<(u8, Zeroizing<Vec<u8>>)> = (0..random_count)
+        .map(|i| (i, random_bytes(secret.len())))
+        .collect();
+
+    let random_part = random_bytes(secret.len() - DIGEST_LENGTH_BYTES);
+    let digest_share =
+        Zeroizing::new([&create_digest(&random_part, secret), random_part.as_slice()].concat());
+
+    let mut base: Vec<(u8, &[u8])> = shares.iter().map(|(i, s)| (*i, s.as_slice())).collect();
+    base.push((DIGEST_INDEX, &digest_share));
+    base.push((SECRET_INDEX, secret));
+
+    let derived = (random_count..count)
+        .map(|i| Ok((i, interpolate(&base, i)?)))
+        .collect::<Result<Vec<(u8, Zeroizing<Vec<u8>>)>>>()?;
+
+    shares.extend(derived);
+
+    Ok(shares)
+}
+
+fn recover_secret(threshold: u8, shares: &[(u8, &[u8])]) -> Result<Zeroizing<Vec<u8>>> {
+    if threshold == 1 {
+        return Ok(Zeroizing::new(shares[0].1.to_vec()));
+    }
+
+    let secret = interpolate(shares, SECRET_INDEX)?;
+    let digest_share = interpolate(shares, DIGEST_INDEX)?;
+    let (digest, random_part) = digest_share.split_at(DIGEST_LENGTH_BYTES);
+
+    if digest != create_digest(random_part, &secret) {
+        return Err(shares_error("Share digest does not match".to_string()));
+    }
+
+    Ok(secret)
+}
+
+fn create_digest(random_part: &[u8], secret: &[u8]) -> [u8; DIGEST_LENGTH_BYTES] {
+    let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(random_part)
+        .expect("HMAC accepts keys of any length");
+    mac.update(secret);
+
+    let mut digest = [0u8; DIGEST_LENGTH_BYTES];
+    digest.copy_from_slice(&mac.finalize().into_bytes()[..DIGEST_LENGTH_BYTES]);
+
+    digest
+}
+
+fn random_bytes(len: usize) -> Zeroizing<Vec<u8>> {
+    let mut bytes = Zeroizing::new(vec![0u8; len]);
+    OsRng.fill_bytes(&mut bytes);
+
+    bytes
+}
+
+// Lagrange interpolation over GF(256) with the Rijndael polynomial
+fn interpolate(shares: &[(u8, &[u8])], x: u8) -> Result<Zeroizing<Vec<u8>>> {
+    let (exp, log) = &GF_TABLES;
+
+    for (i, (xi, yi)) in shares.iter().enumerate() {
+        if shares[..i].iter().any(|(xj, _)| xj == xi) {
+            return Err(shares_error("Share indices must be unique".to_string()));
+        }
+
+        if yi.len() != shares[0].1.len() {
+            return Err(shares_error(
+                "Shares must all be the same length".to_string(),
+            ));
+        }
+    }
+
+    if let Some((_, y)) = shares.iter().find(|(xi, _)| *xi == x) {
+        return Ok(Zeroizing::new(y.to_vec()));
+    }
+
+    let log_prod: usize = shares
+        .iter()
+        .map(|(xi, _)| usize::from(log[usize::from(xi ^ x)]))
+        .sum();
+
+    let mut result = Zeroizing::new(vec![0u8; shares[0].1.len()]);
+
+    for (xi, yi) in shares {
+        let log_others: usize = shares
+            .iter()
+            .map(|(xj, _)| usize::from(log[usize::from(xi ^ xj)]))
+            .sum();
+        let log_basis = (log_prod + 255 * shares.len() * 2
+            - usize::from(log[usize::from(xi ^ x)])
+            - log_others)
+            % 255;
+
+        for (r, y) in result.iter_mut().zip(yi.iter()) {
+            if *y != 0 {
+                *r ^= exp[(usize::from(log[usize::from(*y)]) + log_basis) % 255];
+            }
+        }
+    }
+
+    Ok(result)
+}
+
+const fn gf_tables() -> ([u8; 255], [u8; 256]) {
+    let mut exp = [0u8; 255];
+    let mut log = [0u8; 256];
+    let mut poly: u16 = 1;
+    let mut i = 0;
+
+    while i < 255 {
+        exp[i] = poly as u8;
+        log[poly as usize] = i as u8;
+
+        // Multiply by the generator 3
+        poly = (poly << 1) ^ poly;
+        if poly & 0x100 != 0 {
+            poly ^= 0x11b;
+        }
+
+        i += 1;
+    }
+
+    (exp, log)
+}
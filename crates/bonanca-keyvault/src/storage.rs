@@ -0,0 +1,110 @@
+use std::ffi::OsString;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use crate::error::Result;
+
+/// Number of `.bak.N` copies kept next to a vault
+pub const BACKUP_COUNT: usize = 3;
+
+/// Exclusive advisory lock on `<fname>.lock`, released when dropped.
+///
+/// The lock lives in a sidecar file because each write replaces the vault
+/// itself with a new inode.
+pub struct VaultLock {
+    _file: File,
+}
+
+impl VaultLock {
+    pub fn acquire(fname: &Path) -> Result<Self> {
+        let file = private_options().open(with_suffix(fname, ".lock"))?;
+        file.lock()?;
+
+        Ok(Self { _file: file })
+    }
+}
+
+/// Writes `contents` to a `0600` temp file next to `fname`, syncs it and
+/// renames it over `fname`, so a crash can never leave a truncated file.
+/// The previous contents are kept as `<fname>.bak.1` when `backup` is set.
+pub fn write_atomic(fname: &Path, contents: &[u8], backup: bool) -> Result<()> {
+    let tmp_path = with_suffix(fname, ".tmp");
+
+    // A leftover temp file would keep its old permissions
+    if tmp_path.exists() {
+        fs::remove_file(&tmp_path)?;
+    }
+
+    let mut file = private_options().truncate(true).open(&tmp_path)?;
+    file.write_all(contents)?;
+    file.sync_all()?;
+
+    if backup && fname.exists() {
+        rotate_backups(fname)?;
+    }
+
+    fs::rename(&tmp_path, fname)?;
+    sync_parent(fname)?;
+
+    Ok(())
+}
+
+pub fn backup_path(fname: &Path, n: usize) -> PathBuf {
+    with_suffix(fname, &format!(".bak.{n}"))
+}
+
+fn rotate_backups(fname: &Path) -> Result<()> {
+    for n in (1..BACKUP_COUNT).rev() {
+        let from = backup_path(fname, n);
+        if from.exists() {
+            fs::rename(&from, backup_path(fname, n + 1))?;
+        }
+    }
+
+    // Copy rather than rename, so `fname` exists at every point
+    let mut src = File::open(fname)?;
+    let mut dst = private_options()
+        .truncate(true)
+        .open(backup_path(fname, 1))?;
+    io::copy(&mut src, &mut dst)?;
+    dst.sync_all()?;
+
+    Ok(())
+}
+
+fn private_options() -> OpenOptions {
+    let mut options = OpenOptions::new();
+    options.write(true).create(true);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    options
+}
+
+#[cfg(unix)]
+fn sync_parent(fname: &Path) -> Result<()> {
+    let parent = match fname.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    File::open(parent)?.sync_all()?;
+
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn sync_parent(_fname: &Path) -> Result<()> {
+    Ok(())
+}
+
+fn with_suffix(fname: &Path, suffix: &str) -> PathBuf {
+    let mut name: OsString = fname.as_os_str().to_owned();
+    name.push(suffix);
+
+    PathBuf::from(name)
+}
@@ -2,86 +2,185 @@ use aes_gcm::{
     Aes256Gcm, Key,
     aead::{Aead, KeyInit, generic_array::GenericArray},
 };
-use anyhow::Result;
 use argon2::{
-    Argon2,
-    password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Algorithm, Argon2, Params, Version,
+    password_hash::{PasswordHash, PasswordVerifier},
 };
 use hex::ToHex;
+use hmac::{Hmac, Mac};
 use pbkdf2::pbkdf2_hmac;
 use sha2::Sha256;
+use zeroize::Zeroizing;
 
+use crate::error::{KeyVaultError, Result};
 use crate::keyvault::KdfParams;
 
-pub fn verify_password(mac: &str, password: &str) -> bool {
-    let hash = PasswordHash::new(mac).expect("Failed to parse MAC");
+pub fn verify_password(mac: &str, password: &str) -> Result<bool> {
+    let hash = PasswordHash::new(mac)
+        .map_err(|e| KeyVaultError::CorruptVault(format!("Failed to parse MAC: {e}")))?;
     let argon2 = Argon2::default();
 
-    argon2.verify_password(password.as_bytes(), &hash).is_ok()
+    Ok(argon2.verify_password(password.as_bytes(), &hash).is_ok())
 }
 
-pub fn hash_password(password: &str, salt: &SaltString) -> Result<String> {
-    let argon2 = Argon2::default();
+pub fn derive_key(
+    password: &str,
+    kdf: &str,
+    kdf_params: &KdfParams,
+) -> Result<Zeroizing<[u8; 32]>> {
+    if kdf_params.key_length() != 32 {
+        return Err(KeyVaultError::CorruptVault(format!(
+            "Key length must be 32, got {}",
+            kdf_params.key_length()
+        )));
+    }
+
+    let mut key = Zeroizing::new([0u8; 32]);
+
+    match (kdf, kdf_params) {
+        ("pbkdf2", KdfParams::Pbkdf2(params)) => {
+            pbkdf2_hmac::<Sha256>(
+                password.as_bytes(),
+                params.salt.as_bytes(),
+                params.n,
+                key.as_mut(),
+            );
+        }
+        ("argon2id", KdfParams::Argon2id(params)) => {
+            let (m_cost, t_cost, p_cost) = (params.m_cost, params.t_cost, params.p_cost);
+            let argon_params = Params::new(m_cost, t_cost, p_cost, Some(32)).map_err(|e| {
+                KeyVaultError::CorruptVault(format!("Invalid argon2id params: {e}"))
+            })?;
+
+            Argon2::new(Algorithm::Argon2id, Version::V0x13, argon_params)
+                .hash_password_into(password.as_bytes(), params.salt.as_bytes(), key.as_mut())
+                .map_err(|e| KeyVaultError::Encryption(format!("argon2id failed: {e}")))?;
+        }
+        ("scrypt", KdfParams::Scrypt(params)) => {
+            let scrypt_params = scrypt::Params::new(params.log_n, params.r, params.p, 32)
+                .map_err(|e| KeyVaultError::CorruptVault(format!("Invalid scrypt params: {e}")))?;
+
+            scrypt::scrypt(
+                password.as_bytes(),
+                params.salt.as_bytes(),
+                &scrypt_params,
+                key.as_mut(),
+            )
+            .map_err(|e| KeyVaultError::Encryption(format!("scrypt failed: {e}")))?;
+        }
+        ("pbkdf2" | "argon2id" | "scrypt", _) => {
+            return Err(KeyVaultError::CorruptVault(format!(
+                "kdf_params do not match kdf {kdf}"
+            )));
+        }
+        _ => return Err(KeyVaultError::UnsupportedKdf(kdf.to_string())),
+    }
+
+    Ok(key)
+}
 
-    let password_hash = argon2
-        .hash_password(password.as_bytes(), salt)
-        .expect("Failed to hash password")
-        .to_string();
+/// Splits the KDF output into a cipher key and a MAC key, so the MAC is
+/// never computed under the AES-GCM key
+pub fn split_key(key: &[u8; 32]) -> (Zeroizing<[u8; 32]>, Zeroizing<[u8; 32]>) {
+    (
+        expand_key(key, b"bonanca vault cipher"),
+        expand_key(key, b"bonanca vault mac"),
+    )
+}
+
+// HKDF-Expand (RFC 5869) for a single 32 byte block, the KDF output is
+// already uniform so it serves as the PRK
+pub(crate) fn expand_key(prk: &[u8; 32], info: &[u8]) -> Zeroizing<[u8; 32]> {
+    let mut hmac = <Hmac<Sha256> as Mac>::new_from_slice(prk).expect("HMAC takes any key length");
+    hmac.update(info);
+    hmac.update(&[1]);
 
-    Ok(password_hash)
+    Zeroizing::new(hmac.finalize().into_bytes().into())
 }
 
-pub fn encrypt_seed(
-    seed: [u8; 64],
-    password: &str,
-    nonce: &str,
-    kdf_params: &KdfParams,
-) -> Result<String> {
-    let mut key = [0u8; 32];
-    pbkdf2_hmac::<Sha256>(
-        password.as_bytes(),
-        kdf_params.salt.as_bytes(),
-        kdf_params.n,
-        &mut key,
-    );
-
-    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key));
-    let noncebytes = hex::decode(nonce)?;
-    let non = GenericArray::from_slice(&noncebytes);
-
-    let ciphertext = cipher
-        .encrypt(non, seed.as_ref())
-        .expect("Failed to encrypt master key");
+fn mac_of(mac_key: &[u8; 32], cipher_text: &str) -> Result<Hmac<Sha256>> {
+    let ciphertext = hex::decode(cipher_text).map_err(|_| KeyVaultError::CorruptCiphertext)?;
+
+    let mut hmac =
+        <Hmac<Sha256> as Mac>::new_from_slice(mac_key).expect("HMAC takes any key length");
+    hmac.update(&ciphertext);
+
+    Ok(hmac)
+}
+
+/// HMAC-SHA256 of the cipher text under the MAC key from `split_key`
+pub fn compute_mac(mac_key: &[u8; 32], cipher_text: &str) -> Result<String> {
+    Ok(mac_of(mac_key, cipher_text)?
+        .finalize()
+        .into_bytes()
+        .encode_hex())
+}
+
+/// Checks `mac` against the cipher text in constant time
+pub fn verify_mac(mac_key: &[u8; 32], cipher_text: &str, mac: &str) -> Result<bool> {
+    let hmac = mac_of(mac_key, cipher_text)?;
+    let Ok(mac) = hex::decode(mac) else {
+        return Ok(false);
+    };
+
+    Ok(hmac.verify_slice(&mac).is_ok())
+}
+
+fn decode_nonce(nonce: &str) -> Result<Vec<u8>> {
+    let noncebytes = hex::decode(nonce)
+        .map_err(|e| KeyVaultError::CorruptVault(format!("Invalid nonce: {e}")))?;
+
+    if noncebytes.len() != 12 {
+        return Err(KeyVaultError::CorruptVault(format!(
+            "Nonce must be 12 bytes, got {}",
+            noncebytes.len()
+        )));
+    }
+
+    Ok(noncebytes)
+}
+
+pub fn encrypt_secret(secret: &[u8], key: &[u8; 32], cipher: &str, nonce: &str) -> Result<String> {
+    let ciphertext = match cipher {
+        "aes256-gcm" => {
+            let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
+            let noncebytes = decode_nonce(nonce)?;
+            let non = GenericArray::from_slice(&noncebytes);
+
+            cipher.encrypt(non, secret).map_err(|_| {
+                KeyVaultError::Encryption("Failed to encrypt master key".to_string())
+            })?
+        }
+        _ => return Err(KeyVaultError::UnsupportedCipher(cipher.to_string())),
+    };
 
     let text = ciphertext.encode_hex();
 
     Ok(text)
 }
 
-pub fn decrypt_seed(
+pub fn decrypt_secret(
     cipher_text: &str,
-    password: &str,
+    key: &[u8; 32],
+    cipher: &str,
     nonce: &str,
-    kdf_params: &KdfParams,
-) -> Result<[u8; 64]> {
-    let mut key = [0u8; 32];
-    pbkdf2_hmac::<Sha256>(
-        password.as_bytes(),
-        kdf_params.salt.as_bytes(),
-        kdf_params.n,
-        &mut key,
-    );
-
-    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key));
-    let noncebytes = hex::decode(nonce)?;
-    let non = GenericArray::from_slice(&noncebytes);
-
-    let ciphertext = hex::decode(cipher_text)?;
-    let vec_seed = cipher
-        .decrypt(non, &*ciphertext)
-        .expect("Failed to decrypt master key");
-
-    let seed: [u8; 64] = vec_seed.as_slice().try_into()?;
-
-    Ok(seed)
+) -> Result<Zeroizing<Vec<u8>>> {
+    let ciphertext = hex::decode(cipher_text).map_err(|_| KeyVaultError::CorruptCiphertext)?;
+
+    let secret = match cipher {
+        "aes256-gcm" => {
+            let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
+            let noncebytes = decode_nonce(nonce)?;
+            let non = GenericArray::from_slice(&noncebytes);
+
+            Zeroizing::new(
+                cipher
+                    .decrypt(non, &*ciphertext)
+                    .map_err(|_| KeyVaultError::CorruptCiphertext)?,
+            )
+        }
+        _ => return Err(KeyVaultError::UnsupportedCipher(cipher.to_string())),
+    };
+
+    Ok(secret)
 }
@@ -63,6 +63,21 @@ impl PyAaveV3 {
         parse_txn_receipt(py, receipt)
     }
 
+    fn supply_with_permit<'py>(
+        &self,
+        py: Python<'py>,
+        wallet: &PyEvmWallet,
+        token: &str,
+        amount: f64,
+    ) -> PyResult<Py<PyDict>> {
+        let receipt = wallet
+            .rt
+            .block_on(self.inner.supply_with_permit(&wallet.inner, token, amount))
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()))?;
+
+        parse_txn_receipt(py, receipt)
+    }
+
     fn borrow<'py>(
         &self,
         py: Python<'py>,
@@ -93,6 +108,21 @@ impl PyAaveV3 {
         parse_txn_receipt(py, receipt)
     }
 
+    fn repay_with_permit<'py>(
+        &self,
+        py: Python<'py>,
+        wallet: &PyEvmWallet,
+        token: &str,
+        amount: f64,
+    ) -> PyResult<Py<PyDict>> {
+        let receipt = wallet
+            .rt
+            .block_on(self.inner.repay_with_permit(&wallet.inner, token, amount))
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()))?;
+
+        parse_txn_receipt(py, receipt)
+    }
+
     fn withdraw<'py>(
         &self,
         py: Python<'py>,
@@ -90,6 +90,22 @@ impl PyZeroX {
 
         parse_txn_receipt(py, receipt)
     }
+
+    fn permit2_swap<'py>(
+        &self,
+        py: Python<'py>,
+        wallet: &PyEvmWallet,
+        sell: &str,
+        buy: &str,
+        amount: f64,
+    ) -> PyResult<Py<PyDict>> {
+        let receipt = wallet
+            .rt
+            .block_on(self.inner.permit2_swap(&wallet.inner, sell, buy, amount))
+            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))?;
+
+        parse_txn_receipt(py, receipt)
+    }
 }
 
 #[pyclass(name = "ZeroXSwapQuote")]
@@ -161,6 +177,7 @@ impl PyZeroXSwapQuote {
                 value: String::new(),
             },
             zid: String::new(),
+            permit2: None,
         }
     }
 }
@@ -54,7 +54,7 @@ impl PySolTxnReceipt {
             hash: receipt.hash,
             slot: receipt.slot,
             block_time: receipt.block_time,
-            gas_used: receipt.gas_used,
+            gas_used: receipt.gas_used.to_f64(),
         }
     }
 }
@@ -1,6 +1,67 @@
-use bonanca_keyvault::keyvault::KeyVault;
+use bonanca_keyvault::{
+    accounts::Account,
+    chains::{Chain, DerivationScheme},
+    display::MnemonicDisplay,
+    error::KeyVaultError,
+    keyvault::{KdfParams, KeyVault},
+    password::PasswordProvider,
+    secret::PrivateKey,
+    session::UnlockedVault,
+};
+use pyo3::exceptions::PyRuntimeError;
 use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use std::collections::HashMap;
 use std::path::PathBuf;
+use std::time::Duration;
+
+pub fn password_provider(password: Option<String>) -> PasswordProvider {
+    match password {
+        Some(pass) => PasswordProvider::from(pass),
+        None => PasswordProvider::Prompt,
+    }
+}
+
+/// Shows the mnemonic through a Python callable returning whether the user
+/// confirmed the backup, or in the terminal when none is given
+fn mnemonic_display(display: Option<Py<PyAny>>) -> MnemonicDisplay {
+    match display {
+        Some(f) => MnemonicDisplay::callback(move |mnemonic| {
+            Python::attach(|py| Ok(f.call1(py, (mnemonic,))?.is_truthy(py)?))
+        }),
+        None => MnemonicDisplay::Terminal,
+    }
+}
+
+pub fn parse_scheme(scheme: Option<String>, chain: Chain) -> PyResult<DerivationScheme> {
+    let scheme = match scheme {
+        Some(name) => name
+            .parse()
+            .map_err(|e: KeyVaultError| PyErr::new::<PyRuntimeError, _>(e.to_string()))?,
+        None => DerivationScheme::default_for(chain),
+    };
+
+    if scheme.chain() != chain {
+        return Err(PyErr::new::<PyRuntimeError, _>(format!(
+            "{scheme} is not a {chain} derivation scheme"
+        )));
+    }
+
+    Ok(scheme)
+}
+
+fn parse_account<'py>(py: Python<'py>, account: &Account) -> PyResult<Py<PyDict>> {
+    let dict = PyDict::new(py);
+
+    dict.set_item("chain", account.chain.to_string())?;
+    dict.set_item("path", &account.path)?;
+    dict.set_item("address", &account.address)?;
+    dict.set_item("label", &account.label)?;
+    dict.set_item("created_at", account.created_at)?;
+    dict.set_item("tags", &account.tags)?;
+
+    Ok(dict.into())
+}
 
 #[pyclass(name = "KeyVault")]
 pub struct PyKeyVault {
@@ -10,34 +71,229 @@ pub struct PyKeyVault {
 #[pymethods]
 impl PyKeyVault {
     #[staticmethod]
-    fn new(lang: String) -> PyResult<Self> {
-        let inner = KeyVault::new(&lang);
+    #[pyo3(signature = (lang, password=None, passphrase=None, display=None))]
+    fn new(
+        lang: String,
+        password: Option<String>,
+        passphrase: Option<String>,
+        display: Option<Py<PyAny>>,
+    ) -> PyResult<Self> {
+        let inner = KeyVault::new(
+            &lang,
+            passphrase.as_deref(),
+            &password_provider(password),
+            &mnemonic_display(display),
+        )
+        .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))?;
+        Ok(Self { inner })
+    }
+
+    #[staticmethod]
+    #[pyo3(signature = (mnemonic, password=None, passphrase=None))]
+    fn from_mnemonic(
+        mnemonic: String,
+        password: Option<String>,
+        passphrase: Option<String>,
+    ) -> PyResult<Self> {
+        let inner = KeyVault::from_mnemonic(
+            &mnemonic,
+            passphrase.as_deref(),
+            &password_provider(password),
+        )
+        .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))?;
         Ok(Self { inner })
     }
 
     #[staticmethod]
-    fn from_mnemonic(mnemonic: String) -> PyResult<Self> {
-        let inner = KeyVault::from_mnemonic(&mnemonic);
+    #[pyo3(signature = (shares, password=None, passphrase=None))]
+    fn from_shares(
+        shares: Vec<String>,
+        password: Option<String>,
+        passphrase: Option<String>,
+    ) -> PyResult<Self> {
+        let inner =
+            KeyVault::from_shares(&shares, passphrase.as_deref(), &password_provider(password))
+                .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))?;
         Ok(Self { inner })
     }
 
     #[staticmethod]
     fn load(path: String) -> PyResult<Self> {
         let filepath = PathBuf::from(path);
-        let inner = KeyVault::load(&filepath);
+        let inner = KeyVault::load(&filepath)
+            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))?;
+        Ok(Self { inner })
+    }
+
+    #[staticmethod]
+    #[pyo3(signature = (path, old_password=None, new_password=None))]
+    fn change_password(
+        path: String,
+        old_password: Option<String>,
+        new_password: Option<String>,
+    ) -> PyResult<Self> {
+        let filepath = PathBuf::from(path);
+        let inner = KeyVault::change_password(
+            &filepath,
+            &password_provider(old_password),
+            &password_provider(new_password),
+        )
+        .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))?;
         Ok(Self { inner })
     }
 
     fn write(&self, path: String) -> PyResult<()> {
         let filepath = PathBuf::from(path);
-        self.inner.write(&filepath);
-        Ok(())
+        self.inner
+            .write(&filepath)
+            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))
+    }
+
+    #[pyo3(signature = (password=None))]
+    fn get_seed(&self, password: Option<String>) -> PyResult<Vec<u8>> {
+        self.inner
+            .get_seed(&password_provider(password))
+            .map(|seed| seed.expose().to_vec())
+            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))
+    }
+
+    #[pyo3(signature = (threshold, count, password=None, passphrase=None))]
+    fn split_shares(
+        &self,
+        threshold: u8,
+        count: u8,
+        password: Option<String>,
+        passphrase: Option<String>,
+    ) -> PyResult<Vec<String>> {
+        self.inner
+            .split_shares(
+                &password_provider(password),
+                threshold,
+                count,
+                passphrase.as_deref(),
+            )
+            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))
+    }
+
+    fn get_address(&self, path: String) -> PyResult<String> {
+        self.inner
+            .get_address(&path)
+            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))
+    }
+
+    fn chain_keys(&self) -> HashMap<String, String> {
+        self.inner
+            .chain_keys
+            .iter()
+            .map(|(path, account)| (path.clone(), account.address.clone()))
+            .collect()
     }
 
-    fn get_seed(&self) -> PyResult<Vec<u8>> {
+    fn find_account(&self, py: Python<'_>, label_or_address: &str) -> PyResult<Option<Py<PyDict>>> {
         self.inner
-            .get_seed()
-            .map(|seed| seed.to_vec())
-            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()))
+            .find_account(label_or_address)
+            .map(|account| parse_account(py, account))
+            .transpose()
+    }
+
+    #[pyo3(signature = (path, label=None))]
+    fn set_label(&mut self, path: &str, label: Option<String>) -> PyResult<()> {
+        self.inner
+            .set_label(path, label.as_deref())
+            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))
+    }
+
+    fn add_tag(&mut self, path: &str, tag: &str) -> PyResult<()> {
+        self.inner
+            .add_tag(path, tag)
+            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))
+    }
+
+    fn remove_tag(&mut self, path: &str, tag: &str) -> PyResult<()> {
+        self.inner
+            .remove_tag(path, tag)
+            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))
+    }
+
+    #[pyo3(signature = (chain, start, end, password=None))]
+    fn precompute_addresses(
+        &mut self,
+        chain: String,
+        start: u32,
+        end: u32,
+        password: Option<String>,
+    ) -> PyResult<()> {
+        let chain: Chain = chain
+            .parse()
+            .map_err(|e: KeyVaultError| PyErr::new::<PyRuntimeError, _>(e.to_string()))?;
+
+        self.inner
+            .precompute_addresses(&password_provider(password), chain, start..end)
+            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))
+    }
+
+    #[pyo3(signature = (label, chain, key, password=None))]
+    fn import_key(
+        &mut self,
+        label: String,
+        chain: String,
+        key: Vec<u8>,
+        password: Option<String>,
+    ) -> PyResult<()> {
+        let chain: Chain = chain
+            .parse()
+            .map_err(|e: KeyVaultError| PyErr::new::<PyRuntimeError, _>(e.to_string()))?;
+        let secret = PrivateKey::from_slice(&key)
+            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))?;
+
+        self.inner
+            .import_key(&label, chain, &secret, &password_provider(password))
+            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))
+    }
+
+    fn remove_imported_key(&mut self, label: String) -> bool {
+        self.inner.remove_imported_key(&label).is_some()
+    }
+
+    #[pyo3(signature = (password=None))]
+    fn upgrade(&mut self, password: Option<String>) -> PyResult<()> {
+        self.inner
+            .upgrade(&password_provider(password), KdfParams::default())
+            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))
+    }
+}
+
+#[pyclass(name = "UnlockedVault")]
+pub struct PyUnlockedVault {
+    pub inner: UnlockedVault,
+}
+
+#[pymethods]
+impl PyUnlockedVault {
+    #[staticmethod]
+    #[pyo3(signature = (path, password=None, timeout=None))]
+    fn open(path: String, password: Option<String>, timeout: Option<f64>) -> PyResult<Self> {
+        let filepath = PathBuf::from(path);
+        let password = password_provider(password);
+
+        let inner = match timeout {
+            Some(secs) => UnlockedVault::open_with_timeout(
+                &filepath,
+                &password,
+                Duration::from_secs_f64(secs),
+            ),
+            None => UnlockedVault::open(&filepath, &password),
+        }
+        .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))?;
+
+        Ok(Self { inner })
+    }
+
+    fn lock(&self) {
+        self.inner.lock()
+    }
+
+    fn is_locked(&self) -> bool {
+        self.inner.is_locked()
     }
 }
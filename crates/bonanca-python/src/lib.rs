@@ -8,7 +8,7 @@ use pyo3::prelude::*;
 #[pymodule]
 mod bonanca {
     #[pymodule_export]
-    use super::keyvault::PyKeyVault;
+    use super::keyvault::{PyKeyVault, PyUnlockedVault};
 
     #[pymodule_export]
     use super::pywallets;
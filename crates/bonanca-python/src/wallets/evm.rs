@@ -1,16 +1,41 @@
+use alloy::primitives::{Signature, U256};
 use alloy::rpc::types::TransactionReceipt;
-use bonanca_wallets::{HdWalletLoad, HdWalletView, wallets::evm::EvmWallet};
+use bonanca_keyvault::chains::{Chain, DerivationScheme};
+use bonanca_wallets::{
+    HdWalletLoad, HdWalletView, ImportedWalletLoad,
+    simulate::{SendOutcome, SimulationMode},
+    wallets::evm::EvmWallet,
+};
 use pyo3::prelude::*;
 use pyo3::{exceptions::PyRuntimeError, types::PyDict};
-use std::path::PathBuf;
+use std::{path::PathBuf, str::FromStr};
 use tokio::runtime::Runtime;
 
+use super::{parse_nft_metadata, parse_token_metadata, raw_to_u128};
+use crate::keyvault::{PyUnlockedVault, parse_scheme, password_provider};
+
+/// A dry run gives `simulated=True` with the gas, max cost in wei and call
+/// output in place of the receipt fields
 pub fn parse_txn_receipt<'py>(
     py: Python<'py>,
-    receipt: TransactionReceipt,
+    outcome: SendOutcome<TransactionReceipt>,
 ) -> PyResult<Py<PyDict>> {
     let dict = PyDict::new(py);
 
+    let receipt = match outcome {
+        SendOutcome::Sent(receipt) => receipt,
+        SendOutcome::Simulated(simulation) => {
+            dict.set_item("simulated", true)?;
+            dict.set_item("gas", simulation.gas)?;
+            dict.set_item("max_cost", simulation.max_cost().to_string())?;
+            dict.set_item("output", simulation.output.to_string())?;
+
+            return Ok(dict.into());
+        }
+    };
+
+    dict.set_item("simulated", false)?;
+
     dict.set_item("transaction_hash", receipt.transaction_hash.to_string())?;
     dict.set_item("gas_used", receipt.gas_used)?;
     dict.set_item("effective_gas_price", receipt.effective_gas_price)?;
@@ -43,71 +68,208 @@ pub struct PyEvmWallet {
 #[pymethods]
 impl PyEvmWallet {
     #[staticmethod]
-    fn view(keyvault: PathBuf, rpc: &str, child: u32) -> PyResult<Self> {
-        let inner = EvmWallet::view(&keyvault, rpc, child);
+    #[pyo3(signature = (keyvault, rpc, child, scheme=None))]
+    fn view(keyvault: PathBuf, rpc: &str, child: u32, scheme: Option<String>) -> PyResult<Self> {
+        let scheme = parse_scheme(scheme, Chain::Evm)?;
+        let inner = EvmWallet::view(&keyvault, rpc, (scheme, child))
+            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))?;
+        let rt = Runtime::new().unwrap();
+        Ok(Self { inner, rt })
+    }
+
+    #[staticmethod]
+    #[pyo3(signature = (keyvault, rpc, child, password=None, scheme=None))]
+    fn load(
+        keyvault: PathBuf,
+        rpc: &str,
+        child: u32,
+        password: Option<String>,
+        scheme: Option<String>,
+    ) -> PyResult<Self> {
+        let scheme = parse_scheme(scheme, Chain::Evm)?;
+        let inner = EvmWallet::load(
+            &keyvault,
+            rpc,
+            (scheme, child),
+            &password_provider(password),
+        )
+        .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))?;
         let rt = Runtime::new().unwrap();
         Ok(Self { inner, rt })
     }
 
     #[staticmethod]
-    fn load(keyvault: PathBuf, rpc: &str, child: u32) -> PyResult<Self> {
-        let inner = EvmWallet::load(&keyvault, rpc, child);
+    #[pyo3(signature = (keyvault, rpc, label, password=None))]
+    fn load_imported(
+        keyvault: PathBuf,
+        rpc: &str,
+        label: &str,
+        password: Option<String>,
+    ) -> PyResult<Self> {
+        let inner = EvmWallet::load_imported(&keyvault, rpc, label, &password_provider(password))
+            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))?;
+        let rt = Runtime::new().unwrap();
+        Ok(Self { inner, rt })
+    }
+
+    #[staticmethod]
+    #[pyo3(signature = (session, rpc, child, scheme=None))]
+    fn load_session(
+        session: PyRef<'_, PyUnlockedVault>,
+        rpc: &str,
+        child: u32,
+        scheme: Option<String>,
+    ) -> PyResult<Self> {
+        let scheme = parse_scheme(scheme, Chain::Evm)?;
+        let inner =
+            <EvmWallet as bonanca_wallets::SessionWalletLoad<(DerivationScheme, u32)>>::load(
+                &session.inner,
+                rpc,
+                (scheme, child),
+            )
+            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))?;
         let rt = Runtime::new().unwrap();
         Ok(Self { inner, rt })
     }
 
+    #[staticmethod]
+    #[pyo3(signature = (session, rpc, scheme=None, gap_limit=20))]
+    fn discover(
+        session: PyRef<'_, PyUnlockedVault>,
+        rpc: &str,
+        scheme: Option<String>,
+        gap_limit: u32,
+    ) -> PyResult<Vec<String>> {
+        let scheme = parse_scheme(scheme, Chain::Evm)?;
+        let rt = Runtime::new().unwrap();
+
+        rt.block_on(EvmWallet::discover(&session.inner, rpc, scheme, gap_limit))
+            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))
+    }
+
+    #[staticmethod]
+    #[pyo3(signature = (keystore, rpc, password=None))]
+    fn from_keystore(keystore: PathBuf, rpc: &str, password: Option<String>) -> PyResult<Self> {
+        let inner = EvmWallet::from_keystore(&keystore, rpc, &password_provider(password))
+            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))?;
+        let rt = Runtime::new().unwrap();
+        Ok(Self { inner, rt })
+    }
+
+    #[pyo3(signature = (keystore, password=None))]
+    fn export_keystore(&self, keystore: PathBuf, password: Option<String>) -> PyResult<()> {
+        self.inner
+            .export_keystore(&password_provider(password))
+            .and_then(|ks| Ok(ks.write(&keystore)?))
+            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))
+    }
+
     fn get_pubkey(&self) -> String {
         self.inner.get_pubkey().unwrap()
     }
 
     fn balance(&self) -> f64 {
-        self.rt.block_on(self.inner.balance()).unwrap()
+        self.rt.block_on(self.inner.balance()).unwrap().to_f64()
     }
 
     fn token_balance(&self, token: &str) -> f64 {
-        self.rt.block_on(self.inner.token_balance(token)).unwrap()
+        self.rt
+            .block_on(self.inner.token_balance(token))
+            .unwrap()
+            .to_f64()
+    }
+
+    fn token_balances(&self, tokens: Vec<String>) -> PyResult<Vec<f64>> {
+        let tokens: Vec<&str> = tokens.iter().map(String::as_str).collect();
+
+        self.rt
+            .block_on(self.inner.token_balances(&tokens))
+            .map(|bals| bals.iter().map(|bal| bal.to_f64()).collect())
+            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))
     }
 
-    fn approve_token_spending(&self, token: &str, spender: &str, amount: f64) -> PyResult<()> {
+    /// Hash of the approval, `None` if it was only simulated
+    fn approve_token_spending(
+        &self,
+        token: &str,
+        spender: &str,
+        amount: f64,
+    ) -> PyResult<Option<String>> {
         self.rt
             .block_on(self.inner.approve_token_spending(token, spender, amount))
+            .map(|outcome| outcome.sent().map(|hash| hash.to_string()))
+            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))
+    }
+
+    fn supports_permit(&self, token: &str) -> PyResult<bool> {
+        self.rt
+            .block_on(self.inner.supports_permit(token))
+            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))
+    }
+
+    /// Hash of the approval, `None` if it was only simulated
+    fn approve_permit2(&self, token: &str) -> PyResult<Option<String>> {
+        self.rt
+            .block_on(self.inner.approve_permit2(token))
+            .map(|outcome| outcome.sent().map(|hash| hash.to_string()))
             .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))
     }
 
     fn get_token_allowance(&self, token: &str, spender: &str) -> PyResult<f64> {
         self.rt
             .block_on(self.inner.get_token_allowance(token, spender))
+            .map(|allowance| allowance.to_f64())
             .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))
     }
 
-    fn format_native(&self, amount: f64) -> PyResult<u64> {
-        self.inner
+    fn token_metadata<'py>(&self, py: Python<'py>, token: &str) -> PyResult<Py<PyDict>> {
+        let metadata = self
+            .rt
+            .block_on(self.inner.token_metadata(token))
+            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))?;
+
+        parse_token_metadata(py, metadata)
+    }
+
+    fn format_native(&self, amount: f64) -> PyResult<u128> {
+        let amount = self
+            .inner
             .format_native(amount)
-            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))
+            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))?;
+
+        raw_to_u128(amount)
     }
 
-    fn parse_native(&self, amount: u64) -> PyResult<f64> {
+    fn parse_native(&self, amount: u128) -> PyResult<f64> {
         self.inner
-            .parse_native(amount)
+            .parse_native(U256::from(amount))
+            .map(|amount| amount.to_f64())
             .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))
     }
 
-    fn format_token(&self, amount: f64, token: &str) -> PyResult<u64> {
-        self.rt
+    fn format_token(&self, amount: f64, token: &str) -> PyResult<u128> {
+        let amount = self
+            .rt
             .block_on(self.inner.format_token(amount, token))
-            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))
+            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))?;
+
+        raw_to_u128(amount)
     }
 
-    fn parse_token(&self, amount: u64, token: &str) -> PyResult<f64> {
+    fn parse_token(&self, amount: u128, token: &str) -> PyResult<f64> {
         self.rt
-            .block_on(self.inner.parse_token(amount, token))
+            .block_on(self.inner.parse_token(U256::from(amount), token))
+            .map(|amount| amount.to_f64())
             .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))
     }
 
-    fn close(&self, to: &str) -> PyResult<()> {
-        self.rt
+    fn close<'py>(&self, py: Python<'py>, to: &str) -> PyResult<Py<PyDict>> {
+        let receipt = self
+            .rt
             .block_on(self.inner.close(to))
-            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))
+            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))?;
+
+        parse_txn_receipt(py, receipt)
     }
 
     fn transfer<'py>(&self, py: Python<'py>, to: &str, amount: f64) -> PyResult<Py<PyDict>> {
@@ -134,9 +296,160 @@ impl PyEvmWallet {
         parse_txn_receipt(py, receipt)
     }
 
-    fn transfer_all_tokens(&self, token: &str, to: &str) -> PyResult<()> {
-        self.rt
+    fn transfer_all_tokens<'py>(
+        &self,
+        py: Python<'py>,
+        token: &str,
+        to: &str,
+    ) -> PyResult<Option<Py<PyDict>>> {
+        let receipt = self
+            .rt
             .block_on(self.inner.transfer_all_tokens(token, to))
+            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))?;
+
+        receipt
+            .map(|receipt| parse_txn_receipt(py, receipt))
+            .transpose()
+    }
+
+    fn sign_message(&self, message: &[u8]) -> PyResult<String> {
+        self.rt
+            .block_on(self.inner.sign_message(message))
+            .map(|sig| sig.to_string())
+            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))
+    }
+
+    /// `typed_data` is `eth_signTypedData_v4` JSON
+    fn sign_typed_data(&self, typed_data: &str) -> PyResult<String> {
+        self.rt
+            .block_on(self.inner.sign_typed_data(typed_data))
+            .map(|sig| sig.to_string())
+            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))
+    }
+
+    #[staticmethod]
+    fn verify_message(address: &str, message: &[u8], signature: &str) -> PyResult<bool> {
+        let signature = Signature::from_str(signature)
+            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))?;
+
+        EvmWallet::verify_message(address, message, &signature)
             .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))
     }
+
+    #[staticmethod]
+    fn verify_typed_data(address: &str, typed_data: &str, signature: &str) -> PyResult<bool> {
+        let signature = Signature::from_str(signature)
+            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))?;
+
+        EvmWallet::verify_typed_data(address, typed_data, &signature)
+            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))
+    }
+
+    fn nft_owner(&self, collection: &str, token_id: u128) -> PyResult<String> {
+        self.rt
+            .block_on(self.inner.nft_owner(collection, U256::from(token_id)))
+            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))
+    }
+
+    fn erc1155_balance(&self, collection: &str, id: u128) -> PyResult<u128> {
+        let balance = self
+            .rt
+            .block_on(self.inner.erc1155_balance(collection, U256::from(id)))
+            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))?;
+
+        u128::try_from(balance).map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))
+    }
+
+    fn transfer_nft<'py>(
+        &self,
+        py: Python<'py>,
+        collection: &str,
+        token_id: u128,
+        to: &str,
+    ) -> PyResult<Py<PyDict>> {
+        let receipt = self
+            .rt
+            .block_on(
+                self.inner
+                    .transfer_nft(collection, U256::from(token_id), to),
+            )
+            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))?;
+
+        parse_txn_receipt(py, receipt)
+    }
+
+    fn transfer_erc1155<'py>(
+        &self,
+        py: Python<'py>,
+        collection: &str,
+        id: u128,
+        amount: u128,
+        to: &str,
+    ) -> PyResult<Py<PyDict>> {
+        let receipt = self
+            .rt
+            .block_on(self.inner.transfer_erc1155(
+                collection,
+                U256::from(id),
+                U256::from(amount),
+                to,
+            ))
+            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))?;
+
+        parse_txn_receipt(py, receipt)
+    }
+
+    fn set_nft_approval_for_all<'py>(
+        &self,
+        py: Python<'py>,
+        collection: &str,
+        operator: &str,
+        approved: bool,
+    ) -> PyResult<Py<PyDict>> {
+        let receipt = self
+            .rt
+            .block_on(
+                self.inner
+                    .set_nft_approval_for_all(collection, operator, approved),
+            )
+            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))?;
+
+        parse_txn_receipt(py, receipt)
+    }
+
+    fn nft_token_uri(&self, collection: &str, token_id: u128) -> PyResult<String> {
+        self.rt
+            .block_on(self.inner.nft_token_uri(collection, U256::from(token_id)))
+            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))
+    }
+
+    fn nft_metadata<'py>(
+        &self,
+        py: Python<'py>,
+        collection: &str,
+        token_id: u128,
+    ) -> PyResult<Py<PyDict>> {
+        let metadata = self
+            .rt
+            .block_on(self.inner.nft_metadata(collection, U256::from(token_id)))
+            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))?;
+
+        parse_nft_metadata(py, metadata)
+    }
+
+    /// One of "preflight", "dry_run" or "off"
+    fn set_simulation_mode(&mut self, mode: &str) -> PyResult<()> {
+        self.inner.simulation = match mode {
+            "preflight" => SimulationMode::Preflight,
+            "dry_run" => SimulationMode::DryRun,
+            "off" => SimulationMode::Off,
+            _ => {
+                return Err(PyErr::new::<PyRuntimeError, _>(format!(
+                    "Unknown simulation mode {mode}"
+                )));
+            }
+        };
+
+        Ok(())
+    }
 }
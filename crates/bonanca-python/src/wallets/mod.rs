@@ -1,2 +1,46 @@
 pub mod evm;
 pub mod solana;
+
+use bonanca_wallets::{amount::TokenAmount, nft::NftMetadata, tokens::TokenMetadata};
+use pyo3::{exceptions::PyValueError, prelude::*, types::PyDict};
+
+/// Raw amounts go to Python as ints, which a `u128` covers for any real
+/// token supply
+pub fn raw_to_u128(amount: TokenAmount) -> PyResult<u128> {
+    u128::try_from(amount.raw)
+        .map_err(|_| PyErr::new::<PyValueError, _>(format!("{amount} does not fit in a u128")))
+}
+
+pub fn parse_token_metadata<'py>(py: Python<'py>, metadata: TokenMetadata) -> PyResult<Py<PyDict>> {
+    let dict = PyDict::new(py);
+
+    dict.set_item("address", metadata.address)?;
+    dict.set_item("decimals", metadata.decimals)?;
+    dict.set_item("symbol", metadata.symbol)?;
+    dict.set_item("name", metadata.name)?;
+    dict.set_item("program", metadata.program)?;
+
+    Ok(dict.into())
+}
+
+pub fn parse_nft_metadata<'py>(py: Python<'py>, metadata: NftMetadata) -> PyResult<Py<PyDict>> {
+    let dict = PyDict::new(py);
+
+    dict.set_item("name", metadata.name)?;
+    dict.set_item("description", metadata.description)?;
+    dict.set_item("image", metadata.image)?;
+    dict.set_item("external_url", metadata.external_url)?;
+
+    // Attribute values may be strings or numbers, both are passed as strings
+    let attributes = PyDict::new(py);
+    for attribute in metadata.attributes {
+        let value = match attribute.value.as_str() {
+            Some(value) => value.to_string(),
+            None => attribute.value.to_string(),
+        };
+        attributes.set_item(attribute.trait_type.unwrap_or_default(), value)?;
+    }
+    dict.set_item("attributes", attributes)?;
+
+    Ok(dict.into())
+}
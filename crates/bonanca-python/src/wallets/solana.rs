@@ -1,9 +1,15 @@
-use bonanca_wallets::{HdWalletLoad, HdWalletView, wallets::solana::SolWallet};
-use pyo3::exceptions::PyRuntimeError;
+use alloy::primitives::U256;
+use bonanca_keyvault::chains::{Chain, DerivationScheme};
+use bonanca_wallets::{HdWalletLoad, HdWalletView, ImportedWalletLoad, wallets::solana::SolWallet};
 use pyo3::prelude::*;
-use std::path::PathBuf;
+use pyo3::{exceptions::PyRuntimeError, types::PyDict};
+use solana_sdk::signature::Signature;
+use std::{path::PathBuf, str::FromStr};
 use tokio::runtime::Runtime;
 
+use super::{parse_token_metadata, raw_to_u128};
+use crate::keyvault::{PyUnlockedVault, parse_scheme, password_provider};
+
 #[pyclass(name = "SolWallet")]
 pub struct PySolWallet {
     pub inner: SolWallet,
@@ -13,29 +19,144 @@ pub struct PySolWallet {
 #[pymethods]
 impl PySolWallet {
     #[staticmethod]
-    fn view(keyvault: PathBuf, rpc: &str, child: u32) -> PyResult<Self> {
-        let inner = SolWallet::view(&keyvault, rpc, child);
+    #[pyo3(signature = (keyvault, rpc, child, scheme=None))]
+    fn view(keyvault: PathBuf, rpc: &str, child: u32, scheme: Option<String>) -> PyResult<Self> {
+        let scheme = parse_scheme(scheme, Chain::Solana)?;
+        let inner = SolWallet::view(&keyvault, rpc, (scheme, child))
+            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))?;
+        let rt = Runtime::new().unwrap();
+        Ok(Self { inner, rt })
+    }
+
+    #[staticmethod]
+    #[pyo3(signature = (keyvault, rpc, child, password=None, scheme=None))]
+    fn load(
+        keyvault: PathBuf,
+        rpc: &str,
+        child: u32,
+        password: Option<String>,
+        scheme: Option<String>,
+    ) -> PyResult<Self> {
+        let scheme = parse_scheme(scheme, Chain::Solana)?;
+        let inner = SolWallet::load(
+            &keyvault,
+            rpc,
+            (scheme, child),
+            &password_provider(password),
+        )
+        .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))?;
+        let rt = Runtime::new().unwrap();
+        Ok(Self { inner, rt })
+    }
+
+    #[staticmethod]
+    #[pyo3(signature = (keyvault, rpc, label, password=None))]
+    fn load_imported(
+        keyvault: PathBuf,
+        rpc: &str,
+        label: &str,
+        password: Option<String>,
+    ) -> PyResult<Self> {
+        let inner = SolWallet::load_imported(&keyvault, rpc, label, &password_provider(password))
+            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))?;
         let rt = Runtime::new().unwrap();
         Ok(Self { inner, rt })
     }
 
     #[staticmethod]
-    fn load(keyvault: PathBuf, rpc: &str, child: u32) -> PyResult<Self> {
-        let inner = SolWallet::load(&keyvault, rpc, child);
+    #[pyo3(signature = (session, rpc, child, scheme=None))]
+    fn load_session(
+        session: PyRef<'_, PyUnlockedVault>,
+        rpc: &str,
+        child: u32,
+        scheme: Option<String>,
+    ) -> PyResult<Self> {
+        let scheme = parse_scheme(scheme, Chain::Solana)?;
+        let inner =
+            <SolWallet as bonanca_wallets::SessionWalletLoad<(DerivationScheme, u32)>>::load(
+                &session.inner,
+                rpc,
+                (scheme, child),
+            )
+            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))?;
         let rt = Runtime::new().unwrap();
         Ok(Self { inner, rt })
     }
 
+    #[staticmethod]
+    #[pyo3(signature = (session, rpc, scheme=None, gap_limit=20))]
+    fn discover(
+        session: PyRef<'_, PyUnlockedVault>,
+        rpc: &str,
+        scheme: Option<String>,
+        gap_limit: u32,
+    ) -> PyResult<Vec<String>> {
+        let scheme = parse_scheme(scheme, Chain::Solana)?;
+        let rt = Runtime::new().unwrap();
+
+        rt.block_on(SolWallet::discover(&session.inner, rpc, scheme, gap_limit))
+            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))
+    }
+
+    #[staticmethod]
+    fn from_keypair_file(keypair: PathBuf, rpc: &str) -> PyResult<Self> {
+        let inner = SolWallet::from_keypair_file(&keypair, rpc)
+            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))?;
+        let rt = Runtime::new().unwrap();
+        Ok(Self { inner, rt })
+    }
+
+    fn export_keypair(&self) -> PyResult<String> {
+        self.inner
+            .export_keypair()
+            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))
+    }
+
+    fn sign_message(&self, message: &[u8]) -> PyResult<String> {
+        self.inner
+            .sign_message(message)
+            .map(|sig| sig.to_string())
+            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))
+    }
+
+    fn sign_offchain_message(&self, message: &[u8]) -> PyResult<String> {
+        self.inner
+            .sign_offchain_message(message)
+            .map(|sig| sig.to_string())
+            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))
+    }
+
+    #[staticmethod]
+    fn verify_message(pubkey: &str, message: &[u8], signature: &str) -> PyResult<bool> {
+        let signature = Signature::from_str(signature)
+            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))?;
+
+        SolWallet::verify_message(pubkey, message, &signature)
+            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))
+    }
+
+    #[staticmethod]
+    fn verify_offchain_message(pubkey: &str, message: &[u8], signature: &str) -> PyResult<bool> {
+        let signature = Signature::from_str(signature)
+            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))?;
+
+        SolWallet::verify_offchain_message(pubkey, message, &signature)
+            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))
+    }
+
     fn get_pubkey(&self) -> String {
         self.inner.get_pubkey().unwrap()
     }
 
     fn balance(&self) -> f64 {
-        self.rt.block_on(self.inner.balance()).unwrap()
+        self.rt.block_on(self.inner.balance()).unwrap().to_f64()
     }
 
     fn token_balance(&self, token: &str) -> f64 {
-        self.rt.block_on(self.inner.token_balance(token)).unwrap()
+        self.rt
+            .block_on(self.inner.token_balance(token))
+            .unwrap()
+            .to_f64()
     }
 
     fn create_token_account(&self, mint: &str) -> PyResult<String> {
@@ -52,34 +173,53 @@ impl PySolWallet {
             .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))
     }
 
-    fn format_native(&self, amount: f64) -> PyResult<u64> {
-        self.inner
+    fn token_metadata<'py>(&self, py: Python<'py>, token: &str) -> PyResult<Py<PyDict>> {
+        let metadata = self
+            .rt
+            .block_on(self.inner.token_metadata(token))
+            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))?;
+
+        parse_token_metadata(py, metadata)
+    }
+
+    fn format_native(&self, amount: f64) -> PyResult<u128> {
+        let amount = self
+            .inner
             .format_native(amount)
-            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))
+            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))?;
+
+        raw_to_u128(amount)
     }
 
-    fn parse_native(&self, amount: u64) -> PyResult<f64> {
+    fn parse_native(&self, amount: u128) -> PyResult<f64> {
         self.inner
-            .parse_native(amount)
+            .parse_native(U256::from(amount))
+            .map(|amount| amount.to_f64())
             .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))
     }
 
-    fn format_token(&self, amount: f64, token: &str) -> PyResult<u64> {
-        self.rt
+    fn format_token(&self, amount: f64, token: &str) -> PyResult<u128> {
+        let amount = self
+            .rt
             .block_on(self.inner.format_token(amount, token))
-            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))
+            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))?;
+
+        raw_to_u128(amount)
     }
 
-    fn parse_token(&self, amount: u64, token: &str) -> PyResult<f64> {
+    fn parse_token(&self, amount: u128, token: &str) -> PyResult<f64> {
         self.rt
-            .block_on(self.inner.parse_token(amount, token))
+            .block_on(self.inner.parse_token(U256::from(amount), token))
+            .map(|amount| amount.to_f64())
             .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))
     }
 
     fn close(&self, to: &str) -> PyResult<()> {
-        self.rt
+        let _ = self
+            .rt
             .block_on(self.inner.close(to))
-            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))
+            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))?;
+        Ok(())
     }
 
     fn transfer(&self, to: &str, amount: f64) -> PyResult<()> {
@@ -99,8 +239,10 @@ impl PySolWallet {
     }
 
     fn transfer_all_tokens(&self, mint: &str, to: &str) -> PyResult<()> {
-        self.rt
+        let _ = self
+            .rt
             .block_on(self.inner.transfer_all_tokens(mint, to))
-            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))
+            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))?;
+        Ok(())
     }
 }
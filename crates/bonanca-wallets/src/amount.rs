@@ -0,0 +1,185 @@
+use std::fmt;
+
+use alloy_primitives::U256;
+
+use crate::error::{WalletError, WalletResult};
+
+/// Fixed-point precision prices are resolved to
+pub const PRICE_DECIMALS: u8 = 18;
+
+/// An exact token amount, the raw integer the chain works with plus the
+/// token's decimals
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TokenAmount {
+    pub raw: U256,
+    pub decimals: u8,
+}
+
+impl TokenAmount {
+    pub fn new(raw: U256, decimals: u8) -> Self {
+        Self { raw, decimals }
+    }
+
+    pub fn zero(decimals: u8) -> Self {
+        Self::new(U256::ZERO, decimals)
+    }
+
+    /// Parses a decimal string such as `"1.5"` without going through f64,
+    /// rejecting more fractional digits than the token has
+    pub fn parse(amount: &str, decimals: u8) -> WalletResult<Self> {
+        let amount = amount.trim();
+        let (whole, frac) = amount.split_once('.').unwrap_or((amount, ""));
+
+        let is_digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+        if (whole.is_empty() && frac.is_empty()) || !is_digits(whole) || !is_digits(frac) {
+            return Err(invalid(format!("{amount} is not a decimal number")));
+        }
+
+        if frac.len() > decimals as usize {
+            return Err(invalid(format!(
+                "{amount} has more than {decimals} decimal places"
+            )));
+        }
+
+        let digits = format!("{whole}{frac:0<width$}", width = decimals as usize);
+        let raw = U256::from_str_radix(&digits, 10)
+            .map_err(|_| invalid(format!("{amount} is too large")))?;
+
+        Ok(Self { raw, decimals })
+    }
+
+    /// Rounds `amount` to the token's decimals, for callers that work in
+    /// floats
+    pub fn from_f64(amount: f64, decimals: u8) -> WalletResult<Self> {
+        if !amount.is_finite() || amount < 0.0 {
+            return Err(invalid(format!("{amount} is not a valid token amount")));
+        }
+
+        Self::parse(
+            &format!("{amount:.prec$}", prec = decimals as usize),
+            decimals,
+        )
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        self.to_string()
+            .parse()
+            .expect("decimal strings always parse")
+    }
+
+    /// Raw amount as a `u64`, as used by Solana programs
+    pub fn to_u64(&self) -> WalletResult<u64> {
+        u64::try_from(self.raw).map_err(|_| invalid(format!("{self} does not fit in a u64")))
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.raw.is_zero()
+    }
+
+    /// Amount of a token with `decimals` bought by this amount at `price`,
+    /// the number of these tokens paid for one of the other. Rounds down.
+    pub fn at_price(&self, price: &TokenAmount, decimals: u8) -> WalletResult<Self> {
+        if price.is_zero() {
+            return Err(invalid("Price must not be zero".to_string()));
+        }
+
+        let scale = |decimals: u8| U256::from(10).pow(U256::from(decimals));
+        let overflow = || invalid(format!("{self} at a price of {price} is too large"));
+
+        let numerator = self
+            .raw
+            .checked_mul(scale(decimals))
+            .and_then(|n| n.checked_mul(scale(price.decimals)))
+            .ok_or_else(overflow)?;
+        let denominator = price
+            .raw
+            .checked_mul(scale(self.decimals))
+            .ok_or_else(overflow)?;
+
+        Ok(Self::new(numerator / denominator, decimals))
+    }
+
+    /// `None` on overflow or if the decimals differ
+    pub fn checked_add(&self, other: &Self) -> Option<Self> {
+        if self.decimals != other.decimals {
+            return None;
+        }
+
+        Some(Self::new(self.raw.checked_add(other.raw)?, self.decimals))
+    }
+
+    /// `None` on underflow or if the decimals differ
+    pub fn checked_sub(&self, other: &Self) -> Option<Self> {
+        if self.decimals != other.decimals {
+            return None;
+        }
+
+        Some(Self::new(self.raw.checked_sub(other.raw)?, self.decimals))
+    }
+}
+
+impl fmt::Display for TokenAmount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let decimals = self.decimals as usize;
+        let digits = format!("{:0>width$}", self.raw.to_string(), width = decimals + 1);
+        let (whole, frac) = digits.split_at(digits.len() - decimals);
+        let frac = frac.trim_end_matches('0');
+
+        if frac.is_empty() {
+            write!(f, "{whole}")
+        } else {
+            write!(f, "{whole}.{frac}")
+        }
+    }
+}
+
+/// An amount as given by the caller, turned into a `TokenAmount` once the
+/// token's decimals are known
+#[derive(Debug, Clone, PartialEq)]
+pub enum Amount {
+    Exact(TokenAmount),
+    Decimal(String),
+    Float(f64),
+}
+
+impl Amount {
+    pub fn resolve(self, decimals: u8) -> WalletResult<TokenAmount> {
+        match self {
+            Self::Exact(amount) if amount.decimals == decimals => Ok(amount),
+            Self::Exact(amount) => Err(invalid(format!(
+                "{amount} has {} decimals, the token has {decimals}",
+                amount.decimals
+            ))),
+            Self::Decimal(amount) => TokenAmount::parse(&amount, decimals),
+            Self::Float(amount) => TokenAmount::from_f64(amount, decimals),
+        }
+    }
+}
+
+impl From<TokenAmount> for Amount {
+    fn from(amount: TokenAmount) -> Self {
+        Self::Exact(amount)
+    }
+}
+
+impl From<&str> for Amount {
+    fn from(amount: &str) -> Self {
+        Self::Decimal(amount.to_string())
+    }
+}
+
+impl From<String> for Amount {
+    fn from(amount: String) -> Self {
+        Self::Decimal(amount)
+    }
+}
+
+impl From<f64> for Amount {
+    fn from(amount: f64) -> Self {
+        Self::Float(amount)
+    }
+}
+
+fn invalid(msg: String) -> WalletError {
+    WalletError::InvalidAmount(msg)
+}
@@ -0,0 +1,25 @@
+use anyhow::Result;
+
+/// Checks children 0, 1, 2, ... with `is_used` and stops after `gap_limit`
+/// unused children in a row. Returns the used children in order.
+pub async fn scan_children<F>(gap_limit: u32, mut is_used: F) -> Result<Vec<u32>>
+where
+    F: AsyncFnMut(u32) -> Result<bool>,
+{
+    let mut used = Vec::new();
+    let mut gap = 0;
+    let mut child = 0;
+
+    while gap < gap_limit {
+        if is_used(child).await? {
+            used.push(child);
+            gap = 0;
+        } else {
+            gap += 1;
+        }
+
+        child += 1;
+    }
+
+    Ok(used)
+}
@@ -0,0 +1,31 @@
+use bonanca_keyvault::{
+    chains::{Chain, DerivationScheme},
+    error::KeyVaultError,
+};
+use thiserror::Error;
+
+pub type WalletResult<T> = std::result::Result<T, WalletError>;
+
+#[derive(Debug, Error)]
+pub enum WalletError {
+    #[error("Invalid RPC URL {0}: {1}")]
+    InvalidRpcUrl(String, String),
+
+    #[error("Invalid {0} address {1}")]
+    InvalidAddress(Chain, String),
+
+    #[error("Invalid amount: {0}")]
+    InvalidAmount(String),
+
+    #[error("Invalid private key: {0}")]
+    InvalidKey(String),
+
+    #[error("{0} is not a {1} derivation scheme")]
+    WrongScheme(DerivationScheme, Chain),
+
+    #[error("Could not derive key: {0}")]
+    Derivation(String),
+
+    #[error(transparent)]
+    KeyVault(#[from] KeyVaultError),
+}
@@ -0,0 +1,275 @@
+use std::time::Duration;
+
+use alloy::{
+    network::TransactionBuilder,
+    providers::{DynProvider, Provider},
+    rpc::types::{BlockNumberOrTag, TransactionRequest},
+};
+use alloy_primitives::U256;
+use anyhow::{Context, Result, bail};
+
+// Smallest fee increase nodes accept for a replacement transaction
+const MIN_REPLACEMENT_PERCENT: u64 = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeeMode {
+    Eip1559,
+    /// A single gas price, for chains without EIP-1559
+    Legacy,
+}
+
+/// How an `EvmWallet` prices the transactions it sends
+#[derive(Debug, Clone)]
+pub struct FeePolicy {
+    pub mode: FeeMode,
+    /// Percentile of recent priority fees to pay, 0 to 100
+    pub priority_percentile: f64,
+    /// Number of recent blocks sampled for priority fees
+    pub history_blocks: u64,
+    /// Upper bound in wei for the max fee, or the gas price in legacy mode
+    pub max_fee_cap: Option<u128>,
+    /// Upper bound in wei for the priority fee
+    pub max_priority_fee_cap: Option<u128>,
+    /// Percent added to the previous fees when replacing a transaction,
+    /// most nodes reject replacements below 10
+    pub bump_percent: u64,
+}
+
+impl Default for FeePolicy {
+    fn default() -> Self {
+        Self {
+            mode: FeeMode::Eip1559,
+            priority_percentile: 50.0,
+            history_blocks: 10,
+            max_fee_cap: None,
+            max_priority_fee_cap: None,
+            bump_percent: 15,
+        }
+    }
+}
+
+impl FeePolicy {
+    pub fn legacy() -> Self {
+        Self {
+            mode: FeeMode::Legacy,
+            ..Self::default()
+        }
+    }
+
+    pub async fn estimate(&self, client: &DynProvider) -> Result<GasFees> {
+        let fees = match self.mode {
+            FeeMode::Eip1559 => {
+                let history = client
+                    .get_fee_history(
+                        self.history_blocks,
+                        BlockNumberOrTag::Latest,
+                        &[self.priority_percentile],
+                    )
+                    .await?;
+
+                let base_fee = history
+                    .next_block_base_fee()
+                    .context("Node did not return a base fee, try FeePolicy::legacy()")?;
+
+                let mut rewards: Vec<u128> = history
+                    .reward
+                    .unwrap_or_default()
+                    .iter()
+                    .filter_map(|block| block.first().copied())
+                    .collect();
+                rewards.sort_unstable();
+
+                let priority_fee = match rewards.get(rewards.len() / 2) {
+                    Some(fee) => *fee,
+                    None => client.get_max_priority_fee_per_gas().await?,
+                };
+
+                // Leaves room for the base fee to double before the
+                // transaction is priced out
+                GasFees::Eip1559 {
+                    max_fee: base_fee * 2 + priority_fee,
+                    priority_fee,
+                }
+            }
+            FeeMode::Legacy => GasFees::Legacy {
+                gas_price: client.get_gas_price().await?,
+            },
+        };
+
+        Ok(self.cap(fees))
+    }
+
+    /// Fees for a replacement of a transaction sent with `previous`: at
+    /// least `bump_percent` higher and never below the current estimate
+    pub async fn bump(&self, client: &DynProvider, previous: GasFees) -> Result<GasFees> {
+        let current = self.estimate(client).await?;
+
+        self.replacement(previous, current)
+    }
+
+    pub(crate) fn replacement(&self, previous: GasFees, current: GasFees) -> Result<GasFees> {
+        let bumped = previous.scale(100 + self.bump_percent);
+
+        let fees = self.cap(bumped.max(current));
+        if !fees.at_least(previous.scale(100 + MIN_REPLACEMENT_PERCENT)) {
+            bail!("Fee cap reached, cannot bump the fees any further");
+        }
+
+        Ok(fees)
+    }
+
+    pub(crate) fn cap(&self, fees: GasFees) -> GasFees {
+        let cap = |fee: u128, cap: Option<u128>| cap.map_or(fee, |cap| fee.min(cap));
+
+        match fees {
+            GasFees::Eip1559 {
+                max_fee,
+                priority_fee,
+            } => {
+                let max_fee = cap(max_fee, self.max_fee_cap);
+                let priority_fee = cap(priority_fee, self.max_priority_fee_cap).min(max_fee);
+
+                GasFees::Eip1559 {
+                    max_fee,
+                    priority_fee,
+                }
+            }
+            GasFees::Legacy { gas_price } => GasFees::Legacy {
+                gas_price: cap(gas_price, self.max_fee_cap),
+            },
+        }
+    }
+}
+
+/// Per-gas fees in wei
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GasFees {
+    Eip1559 { max_fee: u128, priority_fee: u128 },
+    Legacy { gas_price: u128 },
+}
+
+impl GasFees {
+    /// Fees already set on `txn`, if any
+    pub fn of(txn: &TransactionRequest) -> Option<Self> {
+        match (
+            txn.max_fee_per_gas,
+            txn.max_priority_fee_per_gas,
+            txn.gas_price,
+        ) {
+            (Some(max_fee), Some(priority_fee), _) => Some(Self::Eip1559 {
+                max_fee,
+                priority_fee,
+            }),
+            (_, _, Some(gas_price)) => Some(Self::Legacy { gas_price }),
+            _ => None,
+        }
+    }
+
+    pub fn apply(self, mut txn: TransactionRequest) -> TransactionRequest {
+        match self {
+            Self::Eip1559 {
+                max_fee,
+                priority_fee,
+            } => {
+                txn.gas_price = None;
+                txn.with_max_fee_per_gas(max_fee)
+                    .with_max_priority_fee_per_gas(priority_fee)
+            }
+            Self::Legacy { gas_price } => {
+                txn.max_fee_per_gas = None;
+                txn.max_priority_fee_per_gas = None;
+                txn.with_gas_price(gas_price)
+            }
+        }
+    }
+
+    /// Most `gas` can cost in wei, whatever the base fee does
+    pub fn max_cost(self, gas: u64) -> U256 {
+        let per_gas = match self {
+            Self::Eip1559 { max_fee, .. } => max_fee,
+            Self::Legacy { gas_price } => gas_price,
+        };
+
+        U256::from(gas) * U256::from(per_gas)
+    }
+
+    // Rounds up, so a bump always adds at least 1 wei
+    fn scale(self, percent: u64) -> Self {
+        let scale = |fee: u128| fee.saturating_mul(percent as u128).div_ceil(100);
+
+        match self {
+            Self::Eip1559 {
+                max_fee,
+                priority_fee,
+            } => Self::Eip1559 {
+                max_fee: scale(max_fee),
+                priority_fee: scale(priority_fee),
+            },
+            Self::Legacy { gas_price } => Self::Legacy {
+                gas_price: scale(gas_price),
+            },
+        }
+    }
+
+    /// Component-wise maximum, keeping `self`'s type if they differ
+    fn max(self, other: Self) -> Self {
+        match (self, other) {
+            (
+                Self::Eip1559 {
+                    max_fee,
+                    priority_fee,
+                },
+                Self::Eip1559 {
+                    max_fee: other_max,
+                    priority_fee: other_priority,
+                },
+            ) => Self::Eip1559 {
+                max_fee: max_fee.max(other_max),
+                priority_fee: priority_fee.max(other_priority),
+            },
+            (Self::Legacy { gas_price }, Self::Legacy { gas_price: other }) => Self::Legacy {
+                gas_price: gas_price.max(other),
+            },
+            _ => self,
+        }
+    }
+
+    fn at_least(self, other: Self) -> bool {
+        match (self, other) {
+            (
+                Self::Eip1559 {
+                    max_fee,
+                    priority_fee,
+                },
+                Self::Eip1559 {
+                    max_fee: other_max,
+                    priority_fee: other_priority,
+                },
+            ) => max_fee >= other_max && priority_fee >= other_priority,
+            (Self::Legacy { gas_price }, Self::Legacy { gas_price: other }) => gas_price >= other,
+            _ => true,
+        }
+    }
+}
+
+/// What `EvmWallet::wait_or_replace` does with a transaction that is not
+/// mined in time
+#[derive(Debug, Clone)]
+pub struct StuckTxnPolicy {
+    /// How long to wait on each version of the transaction
+    pub timeout: Duration,
+    /// Fee bumps before giving up on the transaction
+    pub max_bumps: u32,
+    /// Whether to finally replace it with a zero-value self-send
+    pub cancel: bool,
+}
+
+impl Default for StuckTxnPolicy {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(60),
+            max_bumps: 3,
+            cancel: true,
+        }
+    }
+}
@@ -1,30 +1,944 @@
+pub mod amount;
+pub mod discovery;
+pub mod error;
+pub mod fees;
+pub mod multicall;
+pub mod nft;
+pub mod nonce;
+pub mod permit;
+pub mod simulate;
+pub mod tokens;
 pub mod wallets;
 
 use alloy::rpc::types::TransactionRequest;
+use alloy_primitives::U256;
 use anyhow::Result;
+use bonanca_keyvault::{password::PasswordProvider, session::UnlockedVault};
 use solana_sdk::transaction::VersionedTransaction;
 
+use crate::{
+    amount::{Amount, TokenAmount},
+    error::WalletResult,
+    tokens::TokenMetadata,
+};
+
 pub enum TransactionData {
     Evm(TransactionRequest),
     Sol(VersionedTransaction),
 }
 
-pub trait WalletView<T> {
-    fn view(value: T, rpc: &str) -> Self;
+pub trait WalletView<T>: Sized {
+    fn view(value: T, rpc: &str) -> WalletResult<Self>;
+}
+
+pub trait WalletLoad<T>: Sized {
+    fn load(value: T, rpc: &str) -> WalletResult<Self>;
 }
 
-pub trait WalletLoad<T> {
-    fn load(value: T, rpc: &str) -> Self;
+pub trait HdWalletView<T, U>: Sized {
+    fn view(value: T, rpc: &str, child: U) -> WalletResult<Self>;
 }
 
-pub trait HdWalletView<T, U> {
-    fn view(value: T, rpc: &str, child: U) -> Self;
+pub trait HdWalletLoad<T, U>: Sized {
+    fn load(value: T, rpc: &str, child: U, password: &PasswordProvider) -> WalletResult<Self>;
 }
 
-pub trait HdWalletLoad<T, U> {
-    fn load(value: T, rpc: &str, child: U) -> Self;
+pub trait ImportedWalletLoad<T>: Sized {
+    fn load_imported(
+        value: T,
+        rpc: &str,
+        label: &str,
+        password: &PasswordProvider,
+    ) -> WalletResult<Self>;
+}
+
+pub trait SessionWalletLoad<U>: Sized {
+    fn load(session: &UnlockedVault, rpc: &str, child: U) -> WalletResult<Self>;
 }
 
 pub trait HdWallets<T, U> {
     fn get_child_keypair(&self, child: U) -> Result<T>;
 }
+
+/// Operations shared by every chain, so strategies can be written once and
+/// run against an `EvmWallet`, a `SolWallet` or an `AnyWallet`
+#[allow(async_fn_in_trait)]
+pub trait Wallet {
+    type Receipt;
+    type Transaction;
+
+    fn get_pubkey(&self) -> Result<String>;
+    fn format_native(&self, amount: impl Into<Amount>) -> Result<TokenAmount>;
+    fn parse_native(&self, amount: U256) -> Result<TokenAmount>;
+    async fn token_metadata(&self, token: &str) -> Result<TokenMetadata>;
+    async fn token_decimals(&self, token: &str) -> Result<u8>;
+    async fn format_token(&self, amount: impl Into<Amount>, token: &str) -> Result<TokenAmount>;
+    async fn parse_token(&self, amount: U256, token: &str) -> Result<TokenAmount>;
+    async fn balance(&self) -> Result<TokenAmount>;
+    async fn token_balance(&self, token: &str) -> Result<TokenAmount>;
+    async fn token_balances(&self, tokens: &[&str]) -> Result<Vec<TokenAmount>>;
+    async fn transfer(&self, to: &str, amount: impl Into<Amount>) -> Result<Self::Receipt>;
+    async fn transfer_token(
+        &self,
+        token: &str,
+        amount: impl Into<Amount>,
+        to: &str,
+    ) -> Result<Self::Receipt>;
+    /// `None` if there was no balance to transfer
+    async fn transfer_all_tokens(&self, token: &str, to: &str) -> Result<Option<Self::Receipt>>;
+    async fn close(&self, to: &str) -> Result<Self::Receipt>;
+    async fn sign_and_send(&self, txn: Self::Transaction) -> Result<Self::Receipt>;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use alloy::{
+        json_abi::JsonAbi,
+        network::TransactionBuilder,
+        providers::{Provider, ProviderBuilder},
+        rpc::types::TransactionRequest,
+        sol_types::{SolCall, SolStruct, SolValue, eip712_domain},
+        transports::mock::Asserter,
+    };
+    use alloy_primitives::{Address, Bytes, U64, U256, address, b256, hex, keccak256};
+    use anyhow::Result;
+    use bonanca_keyvault::{
+        chains::{Chain, DerivationScheme},
+        error::KeyVaultError,
+    };
+    use solana_client::rpc_response::OptionSerializer;
+
+    use super::amount::{Amount, PRICE_DECIMALS, TokenAmount};
+    use super::discovery::scan_children;
+    use super::error::WalletError;
+    use super::fees::{FeePolicy, GasFees};
+    use super::multicall::{IMulticall3, Multicall};
+    use super::nft::expand_uri_id;
+    use super::nonce::NonceManager;
+    use super::permit::{
+        IERC20Permit, Permit, PermitTransferFrom, SignedPermit, SignedPermit2Transfer,
+        TokenPermissions, permit2_domain,
+    };
+    use super::simulate::{RevertDecoder, SendOutcome, Simulation, SimulationMode};
+    use super::tokens::{TokenCache, TokenMetadata};
+    use super::wallets::{
+        any::{AnyReceipt, AnyWallet},
+        evm::EvmWallet,
+        solana::{SolTxnReceipt, SolWallet},
+    };
+    use super::{HdWalletView, Wallet, WalletLoad, WalletView};
+
+    // Never contacted, the wallets only sign and verify locally
+    const RPC: &str = "http://localhost:8545";
+
+    // Addresses of the "abandon ... about" mnemonic, no usable seed
+    const WATCH_ONLY_VAULT: &str = r#"
+        {
+            "vault": {
+                "cipher": "aes256-gcm",
+                "cipher_params": { "nonce": "287189f34a1433d2de201d08" },
+                "cipher_text": "00",
+                "kdf": "pbkdf2",
+                "kdf_params": { "key_length": 32, "n": 600000, "salt": "M6lWvNAGuZBSp9fBGAUEqw" },
+                "mac": "",
+                "salt": "M6lWvNAGuZBSp9fBGAUEqw"
+            },
+            "chain_keys": {
+                "m/44'/60'/0'/0/0": "0x9858EfFD232B4033E47d90003D41EC34EcaEda94",
+                "m/44'/501'/0'/0'": "HAgk14JpMQLgt6rVgv7cBQFJWFto5Dqxi472uT3DKpqk"
+            }
+        }
+    "#;
+
+    fn watch_only_vault(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(name);
+        std::fs::write(&path, WATCH_ONLY_VAULT).unwrap();
+
+        path
+    }
+
+    #[test]
+    fn test_token_amounts() {
+        // Above u64::MAX, which f64 could not hold exactly
+        let big = TokenAmount::parse("123456789012.123456789012345678", 18).unwrap();
+        assert_eq!(big.raw.to_string(), "123456789012123456789012345678");
+        assert!(big.raw > U256::from(u64::MAX));
+        assert_eq!(big.to_string(), "123456789012.123456789012345678");
+
+        assert!(TokenAmount::parse("1.1234567", 6).is_err());
+        assert!(TokenAmount::parse("1.5", 0).is_err());
+        for bad in ["", ".", "-1", "1.2.3", "1e18", "abc", " . "] {
+            assert!(TokenAmount::parse(bad, 18).is_err(), "{bad:?} parsed");
+        }
+
+        let padded = TokenAmount::parse("007.500", 6).unwrap();
+        assert_eq!(padded.raw, U256::from(7_500_000));
+        assert_eq!(padded.to_string(), "7.5");
+        assert_eq!(
+            TokenAmount::parse(".5", 6).unwrap().raw,
+            U256::from(500_000)
+        );
+        assert_eq!(
+            TokenAmount::parse("5.", 6).unwrap().raw,
+            U256::from(5_000_000)
+        );
+        assert_eq!(TokenAmount::parse("0.000", 6).unwrap().to_string(), "0");
+
+        for amount in ["0", "1", "0.000001", "1000000", "42.05", "0.1"] {
+            assert_eq!(TokenAmount::parse(amount, 6).unwrap().to_string(), amount);
+        }
+        assert_eq!(
+            TokenAmount::new(U256::from(1), 18).to_string(),
+            "0.000000000000000001"
+        );
+        assert_eq!(TokenAmount::new(U256::from(15), 0).to_string(), "15");
+
+        assert_eq!(
+            TokenAmount::from_f64(0.1, 6).unwrap().raw,
+            U256::from(100_000)
+        );
+        assert_eq!(TokenAmount::from_f64(2.6, 0).unwrap().raw, U256::from(3));
+        assert!(TokenAmount::from_f64(-1.0, 6).is_err());
+        assert!(TokenAmount::from_f64(f64::NAN, 6).is_err());
+
+        let exact = TokenAmount::parse("1.5", 6).unwrap();
+        assert_eq!(Amount::from(exact).resolve(6).unwrap(), exact);
+        assert!(Amount::from(exact).resolve(18).is_err());
+        assert_eq!(Amount::from("1.5").resolve(6).unwrap(), exact);
+        assert_eq!(Amount::from(1.5).resolve(6).unwrap(), exact);
+    }
+
+    #[test]
+    fn test_amount_at_price() {
+        // 100 USDC at 2000 USDC per ETH
+        let usdc = TokenAmount::parse("100", 6).unwrap();
+        let price = Amount::from(2000.0).resolve(PRICE_DECIMALS).unwrap();
+        let eth = usdc.at_price(&price, 18).unwrap();
+        assert_eq!(eth.to_string(), "0.05");
+
+        // Beyond f64 precision, rounded down
+        let sell = TokenAmount::parse("123456789012.123456789012345678", 18).unwrap();
+        let price = TokenAmount::parse("3", PRICE_DECIMALS).unwrap();
+        let buy = sell.at_price(&price, 18).unwrap();
+        assert_eq!(buy.to_string(), "41152263004.041152263004115226");
+
+        let cheap = TokenAmount::parse("0.000000000000000001", PRICE_DECIMALS).unwrap();
+        assert_eq!(
+            usdc.at_price(&cheap, 6).unwrap().to_string(),
+            "100000000000000000000"
+        );
+
+        assert!(
+            usdc.at_price(&TokenAmount::zero(PRICE_DECIMALS), 18)
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_scan_children() {
+        let mut checked = Vec::new();
+        let used = scan_children(3, async |child| -> Result<bool> {
+            checked.push(child);
+            Ok([0, 2, 5].contains(&child))
+        })
+        .await
+        .unwrap();
+        assert_eq!(used, vec![0, 2, 5]);
+        assert_eq!(checked, (0..9).collect::<Vec<_>>());
+
+        // The third empty child in a row ends the scan, so 4 is never checked
+        let mut last = 0;
+        let used = scan_children(3, async |child| -> Result<bool> {
+            last = child;
+            Ok(child == 0 || child == 4)
+        })
+        .await
+        .unwrap();
+        assert_eq!(used, vec![0]);
+        assert_eq!(last, 3);
+
+        let failed = scan_children(3, async |child| -> Result<bool> {
+            anyhow::ensure!(child < 1, "RPC down");
+            Ok(true)
+        })
+        .await;
+        assert!(failed.is_err());
+    }
+
+    #[test]
+    fn test_fee_bumps() {
+        const GWEI: u128 = 1_000_000_000;
+        let policy = FeePolicy::default();
+        let previous = GasFees::Eip1559 {
+            max_fee: 100 * GWEI,
+            priority_fee: 2 * GWEI,
+        };
+        let low = GasFees::Eip1559 {
+            max_fee: 50 * GWEI,
+            priority_fee: GWEI,
+        };
+
+        // 15% on both fees, well over the 10% nodes require
+        let bumped = policy.replacement(previous, low).unwrap();
+        assert_eq!(
+            bumped,
+            GasFees::Eip1559 {
+                max_fee: 115 * GWEI,
+                priority_fee: 2_300_000_000,
+            }
+        );
+
+        // A higher network estimate wins over the bump
+        let high = GasFees::Eip1559 {
+            max_fee: 200 * GWEI,
+            priority_fee: 3 * GWEI,
+        };
+        assert_eq!(policy.replacement(previous, high).unwrap(), high);
+
+        // Tiny fees still go up
+        let dust = GasFees::Legacy { gas_price: 1 };
+        let bumped = policy.replacement(dust, dust).unwrap();
+        assert_eq!(bumped, GasFees::Legacy { gas_price: 2 });
+
+        // Capped, but still a valid replacement
+        let capped = FeePolicy {
+            max_fee_cap: Some(111 * GWEI),
+            ..FeePolicy::default()
+        };
+        assert_eq!(
+            capped.replacement(previous, low).unwrap(),
+            GasFees::Eip1559 {
+                max_fee: 111 * GWEI,
+                priority_fee: 2_300_000_000,
+            }
+        );
+
+        // The cap leaves less than a 10% bump
+        let capped = FeePolicy {
+            max_fee_cap: Some(105 * GWEI),
+            ..FeePolicy::default()
+        };
+        assert!(capped.replacement(previous, low).is_err());
+
+        // Capping the max fee pulls the priority fee down with it
+        let capped = FeePolicy {
+            max_fee_cap: Some(44 * GWEI),
+            ..FeePolicy::default()
+        };
+        let all_tip = GasFees::Eip1559 {
+            max_fee: 40 * GWEI,
+            priority_fee: 40 * GWEI,
+        };
+        let bumped = capped.replacement(all_tip, all_tip).unwrap();
+        assert_eq!(
+            bumped,
+            GasFees::Eip1559 {
+                max_fee: 44 * GWEI,
+                priority_fee: 44 * GWEI,
+            }
+        );
+        assert_eq!(
+            capped.cap(GasFees::Eip1559 {
+                max_fee: 100 * GWEI,
+                priority_fee: 60 * GWEI,
+            }),
+            GasFees::Eip1559 {
+                max_fee: 44 * GWEI,
+                priority_fee: 44 * GWEI,
+            }
+        );
+
+        // Worst case is the max fee, or the gas price, for every unit of gas
+        assert_eq!(bumped.max_cost(21_000), U256::from(21_000 * 44 * GWEI));
+        assert_eq!(
+            GasFees::Legacy {
+                gas_price: 3 * GWEI
+            }
+            .max_cost(21_000),
+            U256::from(63_000 * GWEI)
+        );
+    }
+
+    #[test]
+    fn test_revert_decoder() {
+        let decoder = RevertDecoder::new();
+
+        // Error("Insufficient balance")
+        let error_string = hex!(
+            "08c379a0"
+            "0000000000000000000000000000000000000000000000000000000000000020"
+            "0000000000000000000000000000000000000000000000000000000000000014"
+            "496e73756666696369656e742062616c616e6365000000000000000000000000"
+        );
+        assert_eq!(
+            decoder.decode(&error_string).as_deref(),
+            Some("Insufficient balance")
+        );
+
+        // Panic(0x11), arithmetic overflow
+        let panic = hex!(
+            "4e487b71"
+            "0000000000000000000000000000000000000000000000000000000000000011"
+        );
+        assert_eq!(
+            decoder.decode(&panic).as_deref(),
+            Some("panic: arithmetic underflow or overflow (0x11)")
+        );
+
+        // ERC20InsufficientBalance(0x1111111111111111111111111111111111111111, 5, 10)
+        let custom = hex!(
+            "e450d38c"
+            "0000000000000000000000001111111111111111111111111111111111111111"
+            "0000000000000000000000000000000000000000000000000000000000000005"
+            "000000000000000000000000000000000000000000000000000000000000000a"
+        );
+        assert_eq!(decoder.decode(&custom), None);
+
+        let abi: JsonAbi = serde_json::from_str(
+            r#"[{
+                "type": "error",
+                "name": "ERC20InsufficientBalance",
+                "inputs": [
+                    { "name": "sender", "type": "address" },
+                    { "name": "balance", "type": "uint256" },
+                    { "name": "needed", "type": "uint256" }
+                ]
+            }]"#,
+        )
+        .unwrap();
+        decoder.register_abi(&abi);
+        assert_eq!(
+            decoder.decode(&custom).as_deref(),
+            Some("ERC20InsufficientBalance(0x1111111111111111111111111111111111111111, 5, 10)")
+        );
+
+        // Registered but malformed arguments fall back to the signature
+        assert_eq!(
+            decoder.decode(&custom[..36]).as_deref(),
+            Some("ERC20InsufficientBalance(address,uint256,uint256)")
+        );
+
+        // Unknown selector
+        assert_eq!(decoder.decode(&hex!("deadbeef")), None);
+        assert_eq!(decoder.decode(&[]), None);
+    }
+
+    #[test]
+    fn test_expand_uri_id() {
+        assert_eq!(
+            expand_uri_id("https://example.com/{id}.json", U256::from(0xabc)),
+            "https://example.com/0000000000000000000000000000000000000000000000000000000000000abc.json"
+        );
+        assert_eq!(
+            expand_uri_id("ipfs://QmHash/{id}", U256::MAX),
+            format!("ipfs://QmHash/{}", "f".repeat(64))
+        );
+
+        // URIs without the placeholder are left alone
+        assert_eq!(
+            expand_uri_id("https://example.com/1.json", U256::from(1)),
+            "https://example.com/1.json"
+        );
+    }
+
+    const MAIL_TYPED_DATA: &str = r#"{
+        "types": {
+            "EIP712Domain": [
+                { "name": "name", "type": "string" },
+                { "name": "version", "type": "string" },
+                { "name": "chainId", "type": "uint256" },
+                { "name": "verifyingContract", "type": "address" }
+            ],
+            "Person": [
+                { "name": "name", "type": "string" },
+                { "name": "wallet", "type": "address" }
+            ],
+            "Mail": [
+                { "name": "from", "type": "Person" },
+                { "name": "to", "type": "Person" },
+                { "name": "contents", "type": "string" }
+            ]
+        },
+        "primaryType": "Mail",
+        "domain": {
+            "name": "Ether Mail",
+            "version": "1",
+            "chainId": 1,
+            "verifyingContract": "0xCcCCccccCCCCcCCCCCCcCcCccCcCCCcCcccccccC"
+        },
+        "message": {
+            "from": { "name": "Cow", "wallet": "0xCD2a3d9F938E13CD947Ec05AbC7FE734Df8DD826" },
+            "to": { "name": "Bob", "wallet": "0xbBbBBBBbbBBBbbbBbbBbbbbBBbBbbbbBbBbbBBbB" },
+            "contents": "Hello, Bob!"
+        }
+    }"#;
+
+    #[tokio::test]
+    async fn test_evm_signatures() {
+        // web3.js `accounts.sign` example
+        let key = hex!("4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318");
+        let wallet = <EvmWallet as WalletLoad<[u8; 32]>>::load(key, RPC).unwrap();
+        let address = wallet.pubkey.to_string();
+        assert_eq!(address, "0x2c7536E3605D9C16a7a3D7b1898e529396a65c23");
+
+        let signature = wallet.sign_message(b"Some data").await.unwrap();
+        assert_eq!(
+            hex::encode_prefixed(signature.as_bytes()),
+            "0xb91467e570a6466aa9e9876cbcd013baba02900b8979d43fe208a4a4f339f5fd6007e74cd82e037b800186422fc2da167c747ef045e5d18a5f5d4300f8e1a0291c"
+        );
+        assert!(EvmWallet::verify_message(&address, b"Some data", &signature).unwrap());
+        assert!(!EvmWallet::verify_message(&address, b"Some data!", &signature).unwrap());
+
+        // EIP-712 "Mail" example, signed by keccak256("cow")
+        let wallet = <EvmWallet as WalletLoad<[u8; 32]>>::load(keccak256("cow").0, RPC).unwrap();
+        let address = wallet.pubkey.to_string();
+        assert_eq!(address, "0xCD2a3d9F938E13CD947Ec05AbC7FE734Df8DD826");
+
+        let signature = wallet.sign_typed_data(MAIL_TYPED_DATA).await.unwrap();
+        assert_eq!(
+            hex::encode_prefixed(signature.as_bytes()),
+            "0x4355c47d63924e8a72e509b65029052eb6c299d53a04e167c5775fd466751c9d07299936d304c153f6443dfa05f40ff007d72911b6f72307f996231605b915621c"
+        );
+        assert!(EvmWallet::verify_typed_data(&address, MAIL_TYPED_DATA, &signature).unwrap());
+
+        let tampered = MAIL_TYPED_DATA.replace("Hello, Bob!", "Hello, Eve!");
+        assert!(!EvmWallet::verify_typed_data(&address, &tampered, &signature).unwrap());
+        assert!(EvmWallet::verify_typed_data(&address, "{}", &signature).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_permit_signatures() {
+        // Key of the web3.js `accounts.sign` example
+        let key = hex!("4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318");
+        let wallet = <EvmWallet as WalletLoad<[u8; 32]>>::load(key, RPC).unwrap();
+        let usdc = address!("0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48");
+        let spender = address!("0x7a250d5630B4cF539739dF2C5dAcb4c659F2488D");
+
+        // EIP-2612 on mainnet USDC, the separator is the one it returns
+        let domain = eip712_domain! {
+            name: "USD Coin",
+            version: "2",
+            chain_id: 1,
+            verifying_contract: usdc,
+        };
+        assert_eq!(
+            domain.separator(),
+            b256!("0x06c37168a7db5138defc7866392bb87a741f9b3d104deb5094588ce041cae335")
+        );
+
+        let permit = Permit {
+            owner: wallet.pubkey,
+            spender,
+            value: U256::from(1_000_000),
+            nonce: U256::ZERO,
+            deadline: U256::from(1_700_000_000),
+        };
+        assert_eq!(
+            permit.eip712_type_hash(),
+            b256!("0x6e71edae12b1b97f4d1f60370fef10105fa2faae0126114a169c64845d6126c9")
+        );
+        assert_eq!(
+            permit.eip712_signing_hash(&domain),
+            b256!("0xce52ca670c53973ac5ff2f68655ee610879b83cc984d931b0c6148bd29f544db")
+        );
+
+        let signature = wallet.sign_typed(&permit, &domain).await.unwrap();
+        let signed = SignedPermit {
+            permit: permit.clone(),
+            signature,
+        };
+        assert_eq!(signed.v(), 27);
+        assert_eq!(
+            signed.r(),
+            b256!("0x6c41f322f9b6e06e4378543717ff65c10bcbae72ce212e68e0dc9e0db47aba8b")
+        );
+        assert_eq!(
+            signed.s(),
+            b256!("0x2e6a71634bdf2a06adf1f14fe62625b283d6e7b189645ced8e31a7a13f5ea918")
+        );
+
+        // Permit2 signature transfer, the separator is Permit2's on mainnet
+        let domain = permit2_domain(1);
+        assert_eq!(
+            domain.separator(),
+            b256!("0x866a5aba21966af95d6c7ab78eb2b2fc913915c28be3b9aa07cc04ff903e3f28")
+        );
+
+        let transfer = PermitTransferFrom {
+            permitted: TokenPermissions {
+                token: usdc,
+                amount: U256::from(1_000_000),
+            },
+            spender,
+            nonce: U256::from(42),
+            deadline: U256::from(1_700_000_000),
+        };
+        assert_eq!(
+            transfer.eip712_type_hash(),
+            b256!("0x939c21a48a8dbe3a9a2404a1d46691e4d39f6583d6ec6b35714604c986d80106")
+        );
+        assert_eq!(
+            transfer.eip712_signing_hash(&domain),
+            b256!("0x3842474bde12586f3f925aa04e9da4f8bd82c8eb48a4ecef1e034c9ad8fdc089")
+        );
+
+        let signature = wallet.sign_typed(&transfer, &domain).await.unwrap();
+        let signed = SignedPermit2Transfer {
+            permit: transfer,
+            signature,
+        };
+        assert_eq!(
+            hex::encode_prefixed(signed.signature_bytes()),
+            "0x8af43bac64566e73cc6884203e26d21de13ec0a690d353e687c8f49ae6e3c50949070558a2486d5ab774338a1833c0e6f1ee570130b62bf2aa28869cdbfb6a5c1c"
+        );
+
+        // An odd y parity is v = 28
+        let signed = SignedPermit { permit, signature };
+        assert_eq!(signed.v(), 28);
+    }
+
+    #[test]
+    fn test_solana_signatures() {
+        // RFC 8032 Ed25519 test 2
+        let key = hex!("4ccd089b28ff96da9db6c346ec114e0f5b8a319f35aba624da8cf6ed4fb8a6fb");
+        let wallet = <SolWallet as WalletLoad<[u8; 32]>>::load(key, RPC).unwrap();
+        let pubkey = wallet.pubkey.to_string();
+        assert_eq!(pubkey, "586Z7H2vpX9qNhN2T4e9Utugie3ogjbxzGaMtM3E6HR5");
+
+        let signature = wallet.sign_message(&[0x72]).unwrap();
+        assert_eq!(
+            hex::encode(signature),
+            "92a009a9f0d4cab8720e820b5f642540a2b27b5416503f8fb3762223ebdb69da085ac1e43e15996e458f3613d0f11d8c387b2eaeb4302aeeb00d291612bb0c00"
+        );
+        assert!(SolWallet::verify_message(&pubkey, &[0x72], &signature).unwrap());
+        assert!(!SolWallet::verify_message(&pubkey, &[0x73], &signature).unwrap());
+
+        let message = b"Sign in to Bonanca";
+        let signature = wallet.sign_offchain_message(message).unwrap();
+        assert!(SolWallet::verify_offchain_message(&pubkey, message, &signature).unwrap());
+        assert!(
+            !SolWallet::verify_offchain_message(&pubkey, b"Sign in to Bonanca!", &signature)
+                .unwrap()
+        );
+
+        // The off-chain format's prefix keeps the two kinds apart
+        assert!(!SolWallet::verify_message(&pubkey, message, &signature).unwrap());
+    }
+
+    #[test]
+    fn test_any_wallet() {
+        let path = watch_only_vault("bonanca_any_wallet_test.json");
+        let evm = AnyWallet::view(Chain::Evm, &path, RPC, 0).unwrap();
+        let sol = AnyWallet::view(Chain::Solana, &path, RPC, 0).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(evm.chain(), Chain::Evm);
+        assert_eq!(
+            evm.get_pubkey().unwrap(),
+            "0x9858EfFD232B4033E47d90003D41EC34EcaEda94"
+        );
+        assert!(evm.as_evm().unwrap().signer.is_none());
+        assert!(evm.as_sol().is_none());
+        assert_eq!(evm.tokens().chain(), Chain::Evm);
+
+        assert_eq!(sol.chain(), Chain::Solana);
+        assert_eq!(
+            sol.get_pubkey().unwrap(),
+            "HAgk14JpMQLgt6rVgv7cBQFJWFto5Dqxi472uT3DKpqk"
+        );
+        assert!(sol.as_sol().unwrap().key_pair.is_none());
+        assert!(sol.as_evm().is_none());
+        assert_eq!(
+            sol.format_native("1.5").unwrap().raw,
+            U256::from(1_500_000_000)
+        );
+
+        // Dry runs have no hash
+        let simulated = AnyReceipt::Evm(Box::new(SendOutcome::Simulated(Simulation {
+            output: Bytes::new(),
+            gas: 21_000,
+            fees: GasFees::Legacy { gas_price: 1 },
+        })));
+        assert_eq!(simulated.hash(), None);
+
+        let sol_receipt = AnyReceipt::Sol(Box::new(SolTxnReceipt {
+            hash: "SIGNATURE".to_string(),
+            slot: 1,
+            block_time: None,
+            gas_used: TokenAmount::zero(9),
+            pre_balances: Vec::new(),
+            post_balances: Vec::new(),
+            pre_token_balances: None,
+            post_token_balances: None,
+            loaded_addresses: OptionSerializer::None,
+        }));
+        assert_eq!(sol_receipt.hash().as_deref(), Some("SIGNATURE"));
+    }
+
+    #[test]
+    fn test_wallet_errors() {
+        let evm_address = "0x9858EfFD232B4033E47d90003D41EC34EcaEda94";
+        let sol_address = "HAgk14JpMQLgt6rVgv7cBQFJWFto5Dqxi472uT3DKpqk";
+
+        assert!(matches!(
+            <EvmWallet as WalletView<&str>>::view(evm_address, "not a url"),
+            Err(WalletError::InvalidRpcUrl(url, _)) if url == "not a url"
+        ));
+        assert!(matches!(
+            <SolWallet as WalletView<&str>>::view(sol_address, "not a url"),
+            Err(WalletError::InvalidRpcUrl(url, _)) if url == "not a url"
+        ));
+
+        assert!(matches!(
+            <EvmWallet as WalletView<&str>>::view(sol_address, RPC),
+            Err(WalletError::InvalidAddress(Chain::Evm, addr)) if addr == sol_address
+        ));
+        assert!(matches!(
+            <SolWallet as WalletView<&str>>::view(evm_address, RPC),
+            Err(WalletError::InvalidAddress(Chain::Solana, addr)) if addr == evm_address
+        ));
+
+        let path = watch_only_vault("bonanca_wallet_errors_test.json");
+
+        let evm = <EvmWallet as HdWalletView<&PathBuf, (DerivationScheme, u32)>>::view(
+            &path,
+            RPC,
+            (DerivationScheme::Phantom, 0),
+        );
+        let sol = <SolWallet as HdWalletView<&PathBuf, (DerivationScheme, u32)>>::view(
+            &path,
+            RPC,
+            (DerivationScheme::MetaMask, 0),
+        );
+        assert!(matches!(
+            evm,
+            Err(WalletError::WrongScheme(
+                DerivationScheme::Phantom,
+                Chain::Evm
+            ))
+        ));
+        assert!(matches!(
+            sol,
+            Err(WalletError::WrongScheme(
+                DerivationScheme::MetaMask,
+                Chain::Solana
+            ))
+        ));
+
+        // Only child 0 is in chain_keys
+        let evm = <EvmWallet as HdWalletView<&PathBuf, u32>>::view(&path, RPC, 1);
+        let sol = <SolWallet as HdWalletView<&PathBuf, u32>>::view(&path, RPC, 1);
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(matches!(
+            evm,
+            Err(WalletError::KeyVault(KeyVaultError::UnknownPath(p))) if p == "m/44'/60'/1'/0/0"
+        ));
+        assert!(matches!(
+            sol,
+            Err(WalletError::KeyVault(KeyVaultError::UnknownPath(p))) if p == "m/44'/501'/1'/0'"
+        ));
+    }
+
+    #[test]
+    fn test_token_cache() {
+        let usdc = TokenMetadata {
+            address: "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48".to_string(),
+            decimals: 6,
+            symbol: Some("USDC".to_string()),
+            name: Some("USD Coin".to_string()),
+            program: None,
+        };
+
+        let cache = TokenCache::new(Chain::Evm);
+        assert!(cache.is_empty());
+        cache.insert("1", usdc.clone());
+        assert_eq!(cache.len(), 1);
+
+        // Checksummed or not, EVM addresses are the same token
+        assert_eq!(cache.get("1", &usdc.address), Some(usdc.clone()));
+        assert_eq!(
+            cache.get("1", "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48"),
+            Some(usdc.clone())
+        );
+
+        // The same address on another network is another token
+        assert_eq!(cache.get("8453", &usdc.address), None);
+
+        // Clones share entries
+        let shared = cache.clone();
+        shared.insert(
+            "8453",
+            TokenMetadata {
+                decimals: 18,
+                ..usdc.clone()
+            },
+        );
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get("8453", &usdc.address).unwrap().decimals, 18);
+        assert_eq!(cache.get("1", &usdc.address).unwrap().decimals, 6);
+
+        assert_eq!(shared.remove("1", &usdc.address.to_lowercase()), Some(usdc));
+        assert_eq!(cache.len(), 1);
+        shared.clear();
+        assert!(cache.is_empty());
+
+        // Solana addresses are case sensitive base58
+        let wsol = TokenMetadata {
+            address: "So11111111111111111111111111111111111111112".to_string(),
+            decimals: 9,
+            symbol: Some("SOL".to_string()),
+            name: None,
+            program: Some("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA".to_string()),
+        };
+        let genesis = "5eykt4UsFv8P8NJdTREpY1vzqKqZKvdpKuc147dw2N9d";
+        let cache = TokenCache::new(Chain::Solana);
+        cache.insert(genesis, wsol.clone());
+        assert_eq!(cache.get(genesis, &wsol.address), Some(wsol.clone()));
+        assert_eq!(cache.get(genesis, &wsol.address.to_lowercase()), None);
+    }
+
+    #[tokio::test]
+    async fn test_nonce_manager() {
+        // Only answers what is pushed, so any unexpected RPC call fails
+        let asserter = Asserter::new();
+        let client = ProviderBuilder::new()
+            .connect_mocked_client(asserter.clone())
+            .erased();
+        let address = Address::ZERO;
+
+        let nonces = NonceManager::new();
+        assert_eq!(nonces.peek(), None);
+
+        // Synced once, then handed out locally
+        asserter.push_success(&U64::from(5));
+        assert_eq!(nonces.allocate(&client, address).await.unwrap(), 5);
+        assert_eq!(nonces.allocate(&client, address).await.unwrap(), 6);
+
+        // Clones share the sequence
+        let shared = nonces.clone();
+        assert_eq!(shared.allocate(&client, address).await.unwrap(), 7);
+        assert_eq!(nonces.peek(), Some(8));
+
+        nonces.reset();
+        assert_eq!(shared.peek(), None);
+        asserter.push_success(&U64::from(7));
+        assert_eq!(nonces.allocate(&client, address).await.unwrap(), 7);
+
+        asserter.push_success(&U64::from(12));
+        assert_eq!(nonces.resync(&client, address).await.unwrap(), 12);
+        assert_eq!(nonces.peek(), Some(12));
+
+        // A failed send may or may not have used its nonce
+        let mut wallet = <EvmWallet as WalletView<&str>>::view(&address.to_string(), RPC)
+            .unwrap()
+            .with_simulation_mode(SimulationMode::Off);
+        wallet.client = client;
+        wallet.nonces = nonces.clone();
+
+        let txn = TransactionRequest::default()
+            .with_to(address)
+            .with_gas_limit(21_000)
+            .with_gas_price(1);
+        asserter.push_failure_msg("nonce too low");
+        assert!(wallet.send(txn).await.is_err());
+        assert_eq!(nonces.peek(), None);
+    }
+
+    #[tokio::test]
+    async fn test_multicall() {
+        let usdc = address!("0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48");
+        let owner = address!("0x2c7536E3605D9C16a7a3D7b1898e529396a65c23");
+        let nonces = IERC20Permit::noncesCall { owner };
+
+        // aggregate3([(usdc, true, nonces(owner))])
+        let mut multicall = Multicall::new();
+        assert_eq!(multicall.add(usdc, &nonces), 0);
+        let batches: Vec<_> = multicall.batches().collect();
+        assert_eq!(batches.len(), 1);
+        assert_eq!(
+            hex::encode(batches[0].abi_encode()),
+            concat!(
+                "82ad56cb",
+                "0000000000000000000000000000000000000000000000000000000000000020",
+                "0000000000000000000000000000000000000000000000000000000000000001",
+                "0000000000000000000000000000000000000000000000000000000000000020",
+                "000000000000000000000000a0b86991c6218b36c1d19d4a2e9eb0ce3606eb48",
+                "0000000000000000000000000000000000000000000000000000000000000001",
+                "0000000000000000000000000000000000000000000000000000000000000060",
+                "0000000000000000000000000000000000000000000000000000000000000024",
+                "7ecebe000000000000000000000000002c7536e3605d9c16a7a3d7b1898e5293",
+                "96a65c2300000000000000000000000000000000000000000000000000000000",
+            )
+        );
+
+        // Split into batches of 500, keeping the call order
+        let mut multicall = Multicall::new();
+        for i in 0..1201 {
+            assert_eq!(multicall.add(usdc, &nonces), i);
+        }
+        let batches: Vec<_> = multicall.batches().collect();
+        let sizes: Vec<_> = batches.iter().map(|batch| batch.calls.len()).collect();
+        assert_eq!(sizes, [500, 500, 201]);
+        assert!(batches.iter().flat_map(|batch| &batch.calls).all(|call| {
+            call.target == usdc && call.allowFailure && call.callData == nonces.abi_encode()
+        }));
+        assert_eq!(multicall.clone().with_batch_size(0).batches().count(), 1201);
+
+        // One eth_call per batch, a reverted call only fails its own result
+        let asserter = Asserter::new();
+        let client = ProviderBuilder::new()
+            .connect_mocked_client(asserter.clone())
+            .erased();
+
+        let mut multicall = Multicall::new().with_batch_size(2);
+        for _ in 0..3 {
+            multicall.add(usdc, &nonces);
+        }
+
+        let returned = |results: Vec<(bool, Bytes)>| {
+            let results: Vec<_> = results
+                .into_iter()
+                .map(|(success, data)| IMulticall3::Call3Result {
+                    success,
+                    returnData: data,
+                })
+                .collect();
+
+            Bytes::from(IMulticall3::aggregate3Call::abi_encode_returns(&results))
+        };
+        asserter.push_success(&returned(vec![
+            (true, U256::from(7).abi_encode().into()),
+            (false, Bytes::new()),
+        ]));
+        asserter.push_success(&returned(vec![(true, U256::from(9).abi_encode().into())]));
+
+        let results = multicall.call(&client).await.unwrap();
+        assert!(asserter.read_q().is_empty());
+        assert_eq!(results.0.len(), 3);
+        assert_eq!(
+            results.decode::<IERC20Permit::noncesCall>(0).unwrap(),
+            U256::from(7)
+        );
+        assert!(
+            results
+                .decode::<IERC20Permit::noncesCall>(1)
+                .unwrap_err()
+                .to_string()
+                .contains("reverted")
+        );
+        assert_eq!(
+            results.decode::<IERC20Permit::noncesCall>(2).unwrap(),
+            U256::from(9)
+        );
+        assert!(
+            results
+                .decode::<IERC20Permit::noncesCall>(3)
+                .unwrap_err()
+                .to_string()
+                .contains("no call 3")
+        );
+    }
+}
@@ -0,0 +1,121 @@
+use alloy::{providers::DynProvider, sol, sol_types::SolCall};
+use alloy_primitives::{Address, Bytes, address};
+use anyhow::{Context, Result, bail};
+
+/// Multicall3, deployed at the same address on most EVM chains
+pub const MULTICALL3: Address = address!("0xcA11bde05977b3631167028862bE2a173976CA11");
+
+// Small enough to stay under the eth_call gas cap of common RPC providers
+const DEFAULT_BATCH_SIZE: usize = 500;
+
+sol! {
+    #[allow(missing_docs)]
+    #[sol(rpc)]
+    interface IMulticall3 {
+        struct Call3 {
+            address target;
+            bool allowFailure;
+            bytes callData;
+        }
+
+        struct Call3Result {
+            bool success;
+            bytes returnData;
+        }
+
+        function aggregate3(Call3[] calldata calls)
+            external
+            payable
+            returns (Call3Result[] memory returnData);
+    }
+}
+
+/// Read-only contract calls sent as one `aggregate3` request per batch.
+/// A call that reverts only fails its own result.
+#[derive(Clone)]
+pub struct Multicall {
+    calls: Vec<IMulticall3::Call3>,
+    batch_size: usize,
+}
+
+impl Default for Multicall {
+    fn default() -> Self {
+        Self {
+            calls: Vec::new(),
+            batch_size: DEFAULT_BATCH_SIZE,
+        }
+    }
+}
+
+impl Multicall {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Queues `call` on `target` and returns its index in the results
+    pub fn add<C: SolCall>(&mut self, target: Address, call: &C) -> usize {
+        self.calls.push(IMulticall3::Call3 {
+            target,
+            allowFailure: true,
+            callData: call.abi_encode().into(),
+        });
+
+        self.calls.len() - 1
+    }
+
+    pub fn len(&self) -> usize {
+        self.calls.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.calls.is_empty()
+    }
+
+    pub async fn call(&self, client: &DynProvider) -> Result<MulticallResults> {
+        let multicall = IMulticall3::new(MULTICALL3, client);
+        let mut results = Vec::with_capacity(self.calls.len());
+
+        for batch in self.batches() {
+            let returned = multicall
+                .aggregate3(batch.calls)
+                .call()
+                .await
+                .context("Multicall3 request failed, it may not be deployed on this chain")?;
+
+            results.extend(
+                returned
+                    .into_iter()
+                    .map(|result| result.success.then_some(result.returnData)),
+            );
+        }
+
+        Ok(MulticallResults(results))
+    }
+
+    /// One `aggregate3` call per `batch_size` queued calls, in order
+    pub(crate) fn batches(&self) -> impl Iterator<Item = IMulticall3::aggregate3Call> + '_ {
+        self.calls
+            .chunks(self.batch_size)
+            .map(|batch| IMulticall3::aggregate3Call {
+                calls: batch.to_vec(),
+            })
+    }
+}
+
+/// Raw return data of each queued call, `None` where the call reverted
+pub struct MulticallResults(pub Vec<Option<Bytes>>);
+
+impl MulticallResults {
+    pub fn decode<C: SolCall>(&self, index: usize) -> Result<C::Return> {
+        match self.0.get(index) {
+            Some(Some(data)) => Ok(C::abi_decode_returns(data)?),
+            Some(None) => bail!("Call {index} in the multicall reverted"),
+            None => bail!("Multicall has no call {index}"),
+        }
+    }
+}
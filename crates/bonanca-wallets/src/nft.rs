@@ -0,0 +1,19 @@
+use alloy_primitives::{FixedBytes, U256, fixed_bytes, hex};
+
+pub use bonanca_api_lib::nft::{NftAttribute, NftMetadata, NftMetadataApi};
+
+/// ERC-165 interface ids
+pub const ERC721_INTERFACE_ID: FixedBytes<4> = fixed_bytes!("0x80ac58cd");
+pub const ERC1155_INTERFACE_ID: FixedBytes<4> = fixed_bytes!("0xd9b67a26");
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NftStandard {
+    Erc721,
+    Erc1155,
+}
+
+/// ERC-1155 URIs may contain `{id}`, which clients replace with the token
+/// id as 64 lowercase hex digits
+pub fn expand_uri_id(uri: &str, id: U256) -> String {
+    uri.replace("{id}", &hex::encode(id.to_be_bytes::<32>()))
+}
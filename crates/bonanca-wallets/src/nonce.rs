@@ -0,0 +1,129 @@
+use std::{
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    time::Duration,
+};
+
+use alloy::{
+    network::Ethereum,
+    providers::{DynProvider, PendingTransactionBuilder, Provider},
+    rpc::types::{TransactionReceipt, TransactionRequest},
+};
+use alloy_primitives::{Address, TxHash};
+use anyhow::Result;
+
+/// Hands out EVM nonces locally so transactions can be sent back-to-back
+/// without waiting for each receipt. The first allocation, and the first one
+/// after a `reset`, reads the pending nonce from chain.
+#[derive(Debug, Clone, Default)]
+pub struct NonceManager {
+    next: Arc<Mutex<Option<u64>>>,
+}
+
+impl NonceManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub async fn allocate(&self, client: &DynProvider, address: Address) -> Result<u64> {
+        loop {
+            if let Some(nonce) = self.take_next() {
+                return Ok(nonce);
+            }
+
+            let pending = client.get_transaction_count(address).pending().await?;
+
+            // Another task may have synced while we were waiting on the RPC
+            self.lock().get_or_insert(pending);
+        }
+    }
+
+    /// Next nonce that will be handed out, `None` until synced
+    pub fn peek(&self) -> Option<u64> {
+        *self.lock()
+    }
+
+    /// Forgets the local nonce so the next allocation resyncs from chain
+    pub fn reset(&self) {
+        *self.lock() = None;
+    }
+
+    pub async fn resync(&self, client: &DynProvider, address: Address) -> Result<u64> {
+        let pending = client.get_transaction_count(address).pending().await?;
+        *self.lock() = Some(pending);
+
+        Ok(pending)
+    }
+
+    fn take_next(&self) -> Option<u64> {
+        let mut next = self.lock();
+        let nonce = (*next)?;
+        *next = Some(nonce + 1);
+
+        Some(nonce)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Option<u64>> {
+        self.next.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// A submitted transaction that has not been waited on yet. If it fails to
+/// land, the wallet's nonces are resynced from chain.
+pub struct PendingTxn {
+    pub hash: TxHash,
+    pub nonce: u64,
+    /// The request as sent, used to build replacements
+    pub(crate) request: TransactionRequest,
+    pub(crate) inner: PendingTransactionBuilder<Ethereum>,
+    nonces: NonceManager,
+}
+
+impl PendingTxn {
+    pub(crate) fn new(
+        inner: PendingTransactionBuilder<Ethereum>,
+        request: TransactionRequest,
+        nonce: u64,
+        nonces: NonceManager,
+    ) -> Self {
+        Self {
+            hash: *inner.tx_hash(),
+            nonce,
+            request,
+            inner,
+            nonces,
+        }
+    }
+
+    /// Gives up waiting after `timeout`, e.g. for a transaction that was
+    /// dropped from the mempool
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.inner = self.inner.with_timeout(Some(timeout));
+        self
+    }
+
+    pub fn with_confirmations(mut self, confirmations: u64) -> Self {
+        self.inner = self.inner.with_required_confirmations(confirmations);
+        self
+    }
+
+    pub async fn get_receipt(self) -> Result<TransactionReceipt> {
+        match self.inner.get_receipt().await {
+            Ok(receipt) => Ok(receipt),
+            Err(e) => {
+                self.nonces.reset();
+                Err(e.into())
+            }
+        }
+    }
+
+    /// Waits for the transaction without fetching its receipt
+    pub async fn watch(self) -> Result<TxHash> {
+        match self.inner.watch().await {
+            Ok(hash) => Ok(hash),
+            Err(e) => {
+                self.nonces.reset();
+                Err(e.into())
+            }
+        }
+    }
+}
@@ -0,0 +1,123 @@
+use alloy::{
+    sol,
+    sol_types::{Eip712Domain, eip712_domain},
+};
+use alloy_primitives::{Address, B256, Bytes, Signature, address};
+
+/// Uniswap's Permit2, deployed at the same address on every chain
+pub const PERMIT2: Address = address!("0x000000000022D473030F116dDEE9F6B43aC78BA3");
+
+sol! {
+    #[allow(missing_docs)]
+    #[sol(rpc)]
+    interface IERC20Permit {
+        function nonces(address owner) external view returns (uint256);
+        function DOMAIN_SEPARATOR() external view returns (bytes32);
+        function version() external view returns (string);
+    }
+
+    #[allow(missing_docs)]
+    #[sol(rpc)]
+    interface IPermit2 {
+        function allowance(address user, address token, address spender)
+            external
+            view
+            returns (uint160 amount, uint48 expiration, uint48 nonce);
+    }
+
+    // EIP-2612
+    #[allow(missing_docs)]
+    #[derive(Debug)]
+    struct Permit {
+        address owner;
+        address spender;
+        uint256 value;
+        uint256 nonce;
+        uint256 deadline;
+    }
+
+    // Permit2 allowance transfer
+    #[allow(missing_docs)]
+    #[derive(Debug)]
+    struct PermitDetails {
+        address token;
+        uint160 amount;
+        uint48 expiration;
+        uint48 nonce;
+    }
+
+    #[allow(missing_docs)]
+    #[derive(Debug)]
+    struct PermitSingle {
+        PermitDetails details;
+        address spender;
+        uint256 sigDeadline;
+    }
+
+    // Permit2 signature transfer
+    #[allow(missing_docs)]
+    #[derive(Debug)]
+    struct TokenPermissions {
+        address token;
+        uint256 amount;
+    }
+
+    #[allow(missing_docs)]
+    #[derive(Debug)]
+    struct PermitTransferFrom {
+        TokenPermissions permitted;
+        address spender;
+        uint256 nonce;
+        uint256 deadline;
+    }
+}
+
+pub fn permit2_domain(chain_id: u64) -> Eip712Domain {
+    eip712_domain! {
+        name: "Permit2",
+        chain_id: chain_id,
+        verifying_contract: PERMIT2,
+    }
+}
+
+/// An EIP-2612 permit, submitted by the spender in place of an `approve`
+#[derive(Debug, Clone)]
+pub struct SignedPermit {
+    pub permit: Permit,
+    pub signature: Signature,
+}
+
+impl SignedPermit {
+    pub fn v(&self) -> u8 {
+        27 + self.signature.v() as u8
+    }
+
+    pub fn r(&self) -> B256 {
+        self.signature.r().into()
+    }
+
+    pub fn s(&self) -> B256 {
+        self.signature.s().into()
+    }
+}
+
+/// A Permit2 allowance for `permit.spender`, valid until `details.expiration`
+#[derive(Debug, Clone)]
+pub struct SignedPermit2 {
+    pub permit: PermitSingle,
+    pub signature: Signature,
+}
+
+/// A one-time Permit2 transfer, consumed by the transfer it authorizes
+#[derive(Debug, Clone)]
+pub struct SignedPermit2Transfer {
+    pub permit: PermitTransferFrom,
+    pub signature: Signature,
+}
+
+impl SignedPermit2Transfer {
+    /// The 65 byte `r, s, v` signature Permit2 expects
+    pub fn signature_bytes(&self) -> Bytes {
+        Bytes::copy_from_slice(&self.signature.as_bytes())
+    }
+}
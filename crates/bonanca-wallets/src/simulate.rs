@@ -0,0 +1,190 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{Arc, PoisonError, RwLock},
+};
+
+use alloy::{
+    dyn_abi::{DynSolValue, JsonAbiExt},
+    json_abi::{Error as AbiError, JsonAbi},
+    rpc::types::TransactionReceipt,
+    sol_types::{Revert as RevertString, SolError, decode_revert_reason},
+};
+use alloy_primitives::{Bytes, Selector, TxHash, U256, hex};
+use anyhow::Result;
+
+use crate::{fees::GasFees, nonce::PendingTxn};
+
+/// What `EvmWallet::send` does before broadcasting a transaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SimulationMode {
+    /// Simulate first and refuse to send anything that would revert
+    #[default]
+    Preflight,
+    /// Simulate only, every send path returns `SendOutcome::Simulated`
+    /// instead of broadcasting
+    DryRun,
+    /// Broadcast without simulating
+    Off,
+}
+
+/// Outcome of a transaction run against the latest block
+#[derive(Debug, Clone)]
+pub struct Simulation {
+    /// Data returned by the call
+    pub output: Bytes,
+    pub gas: u64,
+    pub fees: GasFees,
+}
+
+impl Simulation {
+    /// Most the transaction can cost in wei, gas times the max fee
+    pub fn max_cost(&self) -> U256 {
+        self.fees.max_cost(self.gas)
+    }
+}
+
+/// What a send path did with a transaction: `Sent` it, or only
+/// `Simulated` it because the wallet is in dry-run mode
+#[derive(Debug, Clone)]
+pub enum SendOutcome<T = PendingTxn> {
+    Sent(T),
+    Simulated(Simulation),
+}
+
+impl<T> SendOutcome<T> {
+    pub fn sent(self) -> Option<T> {
+        match self {
+            Self::Sent(sent) => Some(sent),
+            Self::Simulated(_) => None,
+        }
+    }
+
+    pub fn simulation(&self) -> Option<&Simulation> {
+        match self {
+            Self::Sent(_) => None,
+            Self::Simulated(simulation) => Some(simulation),
+        }
+    }
+}
+
+impl SendOutcome {
+    /// Waits for a sent transaction to be mined
+    pub async fn get_receipt(self) -> Result<SendOutcome<TransactionReceipt>> {
+        Ok(match self {
+            Self::Sent(pending) => SendOutcome::Sent(pending.get_receipt().await?),
+            Self::Simulated(simulation) => SendOutcome::Simulated(simulation),
+        })
+    }
+
+    /// Waits for a sent transaction without fetching its receipt
+    pub async fn watch(self) -> Result<SendOutcome<TxHash>> {
+        Ok(match self {
+            Self::Sent(pending) => SendOutcome::Sent(pending.watch().await?),
+            Self::Simulated(simulation) => SendOutcome::Simulated(simulation),
+        })
+    }
+}
+
+impl SendOutcome<TransactionReceipt> {
+    /// Hash of the mined transaction, `None` if it was only simulated
+    pub fn hash(&self) -> Option<TxHash> {
+        match self {
+            Self::Sent(receipt) => Some(receipt.transaction_hash),
+            Self::Simulated(_) => None,
+        }
+    }
+}
+
+/// A simulated transaction that reverted
+#[derive(Debug, Clone)]
+pub struct Revert {
+    /// Raw revert data, empty for a bare `revert()`
+    pub data: Bytes,
+    pub reason: Option<String>,
+}
+
+impl fmt::Display for Revert {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.reason, self.data.is_empty()) {
+            (Some(reason), _) => write!(f, "Transaction would revert: {reason}"),
+            (None, true) => write!(f, "Transaction would revert without a reason"),
+            (None, false) => write!(f, "Transaction would revert: {}", self.data),
+        }
+    }
+}
+
+impl std::error::Error for Revert {}
+
+/// Custom errors known to a wallet, keyed by selector. Clones share the
+/// same entries.
+#[derive(Debug, Clone, Default)]
+pub struct RevertDecoder {
+    errors: Arc<RwLock<HashMap<Selector, AbiError>>>,
+}
+
+impl RevertDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register(&self, error: AbiError) {
+        let mut errors = self.errors.write().unwrap_or_else(PoisonError::into_inner);
+
+        errors.insert(error.selector(), error);
+    }
+
+    /// Registers every custom error in `abi`
+    pub fn register_abi(&self, abi: &JsonAbi) {
+        for error in abi.errors() {
+            self.register(error.clone());
+        }
+    }
+
+    /// Decodes `Error(string)`, `Panic(uint256)` and any registered custom
+    /// error, e.g. `NotEnoughLiquidity()` or `ERC20InsufficientBalance(0x.., 0, 1)`
+    pub fn decode(&self, data: &[u8]) -> Option<String> {
+        if let Ok(revert) = RevertString::abi_decode(data) {
+            return Some(revert.reason);
+        }
+
+        if let Some(reason) = self.decode_custom(data) {
+            return Some(reason);
+        }
+
+        // Empty data is a bare `revert()`, which alloy reads as ""
+        decode_revert_reason(data).filter(|reason| !reason.is_empty())
+    }
+
+    fn decode_custom(&self, data: &[u8]) -> Option<String> {
+        let selector = Selector::try_from(data.get(..4)?).ok()?;
+        let errors = self.errors.read().unwrap_or_else(PoisonError::into_inner);
+        let error = errors.get(&selector)?;
+
+        let args = match error.abi_decode_input(&data[4..]) {
+            Ok(values) => values.iter().map(format_value).collect::<Vec<_>>(),
+            Err(_) => return Some(error.signature()),
+        };
+
+        Some(format!("{}({})", error.name, args.join(", ")))
+    }
+}
+
+fn format_value(value: &DynSolValue) -> String {
+    match value {
+        DynSolValue::Bool(b) => b.to_string(),
+        DynSolValue::Int(i, _) => i.to_string(),
+        DynSolValue::Uint(u, _) => u.to_string(),
+        DynSolValue::Address(a) => a.to_string(),
+        DynSolValue::FixedBytes(word, size) => hex::encode_prefixed(&word[..*size]),
+        DynSolValue::Bytes(bytes) => hex::encode_prefixed(bytes),
+        DynSolValue::String(s) => format!("{s:?}"),
+        DynSolValue::Array(values)
+        | DynSolValue::FixedArray(values)
+        | DynSolValue::Tuple(values) => {
+            let values: Vec<String> = values.iter().map(format_value).collect();
+            format!("[{}]", values.join(", "))
+        }
+        other => format!("{other:?}"),
+    }
+}
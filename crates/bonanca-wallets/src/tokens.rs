@@ -0,0 +1,87 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, PoisonError, RwLock},
+};
+
+use bonanca_keyvault::chains::Chain;
+
+/// What the wallets need to know about a token, fetched once per address
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenMetadata {
+    pub address: String,
+    pub decimals: u8,
+    pub symbol: Option<String>,
+    pub name: Option<String>,
+    /// Owning token program (SPL Token or Token-2022), Solana only
+    pub program: Option<String>,
+}
+
+/// Token metadata for one chain, keyed by network and address. Clones share
+/// the same entries, so wallets can use a single cache across networks. The
+/// network is the chain id on EVM and the genesis hash on Solana.
+#[derive(Debug, Clone)]
+pub struct TokenCache {
+    chain: Chain,
+    tokens: Arc<RwLock<HashMap<(String, String), TokenMetadata>>>,
+}
+
+impl TokenCache {
+    pub fn new(chain: Chain) -> Self {
+        Self {
+            chain,
+            tokens: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    pub fn chain(&self) -> Chain {
+        self.chain
+    }
+
+    pub fn get(&self, network: &str, address: &str) -> Option<TokenMetadata> {
+        let tokens = self.tokens.read().unwrap_or_else(PoisonError::into_inner);
+
+        tokens.get(&self.key(network, address)).cloned()
+    }
+
+    /// Adds or replaces a token, e.g. to seed the cache from a token list
+    pub fn insert(&self, network: &str, token: TokenMetadata) {
+        let mut tokens = self.tokens.write().unwrap_or_else(PoisonError::into_inner);
+
+        tokens.insert(self.key(network, &token.address), token);
+    }
+
+    pub fn remove(&self, network: &str, address: &str) -> Option<TokenMetadata> {
+        let mut tokens = self.tokens.write().unwrap_or_else(PoisonError::into_inner);
+
+        tokens.remove(&self.key(network, address))
+    }
+
+    pub fn clear(&self) {
+        self.tokens
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.tokens
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // EVM addresses are hex and may or may not be checksummed, Solana
+    // addresses are case sensitive base58
+    fn key(&self, network: &str, address: &str) -> (String, String) {
+        let address = match self.chain {
+            Chain::Evm => address.to_lowercase(),
+            Chain::Solana => address.to_string(),
+        };
+
+        (network.to_string(), address)
+    }
+}
@@ -0,0 +1,275 @@
+use std::path::Path;
+
+use alloy::rpc::types::TransactionReceipt;
+use alloy_primitives::U256;
+use anyhow::{Result, bail};
+use bonanca_keyvault::{chains::Chain, password::PasswordProvider, session::UnlockedVault};
+
+use super::{
+    evm::EvmWallet,
+    solana::{SolTxnReceipt, SolWallet},
+};
+use crate::{
+    HdWalletLoad, HdWalletView, SessionWalletLoad, TransactionData, Wallet,
+    amount::{Amount, TokenAmount},
+    error::WalletResult,
+    simulate::SendOutcome,
+    tokens::{TokenCache, TokenMetadata},
+};
+
+/// A wallet on any supported chain, picked at runtime
+pub enum AnyWallet {
+    Evm(Box<EvmWallet>),
+    Sol(Box<SolWallet>),
+}
+
+pub enum AnyReceipt {
+    Evm(Box<SendOutcome<TransactionReceipt>>),
+    Sol(Box<SolTxnReceipt>),
+}
+
+impl AnyReceipt {
+    /// `None` for an EVM transaction that was only simulated
+    pub fn hash(&self) -> Option<String> {
+        match self {
+            Self::Evm(outcome) => outcome.hash().map(|hash| hash.to_string()),
+            Self::Sol(receipt) => Some(receipt.hash.clone()),
+        }
+    }
+}
+
+impl From<EvmWallet> for AnyWallet {
+    fn from(wallet: EvmWallet) -> Self {
+        Self::Evm(Box::new(wallet))
+    }
+}
+
+impl From<SolWallet> for AnyWallet {
+    fn from(wallet: SolWallet) -> Self {
+        Self::Sol(Box::new(wallet))
+    }
+}
+
+impl AnyWallet {
+    pub fn view<T: AsRef<Path>>(
+        chain: Chain,
+        keyvault: T,
+        rpc: &str,
+        child: u32,
+    ) -> WalletResult<Self> {
+        let wallet = match chain {
+            Chain::Evm => <EvmWallet as HdWalletView<T, u32>>::view(keyvault, rpc, child)?.into(),
+            Chain::Solana => {
+                <SolWallet as HdWalletView<T, u32>>::view(keyvault, rpc, child)?.into()
+            }
+        };
+
+        Ok(wallet)
+    }
+
+    pub fn load<T: AsRef<Path>>(
+        chain: Chain,
+        keyvault: T,
+        rpc: &str,
+        child: u32,
+        password: &PasswordProvider,
+    ) -> WalletResult<Self> {
+        let wallet = match chain {
+            Chain::Evm => {
+                <EvmWallet as HdWalletLoad<T, u32>>::load(keyvault, rpc, child, password)?.into()
+            }
+            Chain::Solana => {
+                <SolWallet as HdWalletLoad<T, u32>>::load(keyvault, rpc, child, password)?.into()
+            }
+        };
+
+        Ok(wallet)
+    }
+
+    pub fn load_session(
+        chain: Chain,
+        session: &UnlockedVault,
+        rpc: &str,
+        child: u32,
+    ) -> WalletResult<Self> {
+        let wallet = match chain {
+            Chain::Evm => <EvmWallet as SessionWalletLoad<u32>>::load(session, rpc, child)?.into(),
+            Chain::Solana => {
+                <SolWallet as SessionWalletLoad<u32>>::load(session, rpc, child)?.into()
+            }
+        };
+
+        Ok(wallet)
+    }
+
+    pub fn chain(&self) -> Chain {
+        match self {
+            Self::Evm(_) => Chain::Evm,
+            Self::Sol(_) => Chain::Solana,
+        }
+    }
+
+    pub fn tokens(&self) -> &TokenCache {
+        match self {
+            Self::Evm(wallet) => &wallet.tokens,
+            Self::Sol(wallet) => &wallet.tokens,
+        }
+    }
+
+    pub fn as_evm(&self) -> Option<&EvmWallet> {
+        match self {
+            Self::Evm(wallet) => Some(wallet.as_ref()),
+            Self::Sol(_) => None,
+        }
+    }
+
+    pub fn as_sol(&self) -> Option<&SolWallet> {
+        match self {
+            Self::Sol(wallet) => Some(wallet.as_ref()),
+            Self::Evm(_) => None,
+        }
+    }
+}
+
+impl Wallet for AnyWallet {
+    type Receipt = AnyReceipt;
+    type Transaction = TransactionData;
+
+    fn get_pubkey(&self) -> Result<String> {
+        match self {
+            Self::Evm(wallet) => wallet.get_pubkey(),
+            Self::Sol(wallet) => wallet.get_pubkey(),
+        }
+    }
+
+    fn format_native(&self, amount: impl Into<Amount>) -> Result<TokenAmount> {
+        match self {
+            Self::Evm(wallet) => wallet.format_native(amount),
+            Self::Sol(wallet) => wallet.format_native(amount),
+        }
+    }
+
+    fn parse_native(&self, amount: U256) -> Result<TokenAmount> {
+        match self {
+            Self::Evm(wallet) => wallet.parse_native(amount),
+            Self::Sol(wallet) => wallet.parse_native(amount),
+        }
+    }
+
+    async fn token_metadata(&self, token: &str) -> Result<TokenMetadata> {
+        match self {
+            Self::Evm(wallet) => wallet.token_metadata(token).await,
+            Self::Sol(wallet) => wallet.token_metadata(token).await,
+        }
+    }
+
+    async fn token_decimals(&self, token: &str) -> Result<u8> {
+        match self {
+            Self::Evm(wallet) => wallet.token_decimals(token).await,
+            Self::Sol(wallet) => wallet.token_decimals(token).await,
+        }
+    }
+
+    async fn format_token(&self, amount: impl Into<Amount>, token: &str) -> Result<TokenAmount> {
+        match self {
+            Self::Evm(wallet) => wallet.format_token(amount, token).await,
+            Self::Sol(wallet) => wallet.format_token(amount, token).await,
+        }
+    }
+
+    async fn parse_token(&self, amount: U256, token: &str) -> Result<TokenAmount> {
+        match self {
+            Self::Evm(wallet) => wallet.parse_token(amount, token).await,
+            Self::Sol(wallet) => wallet.parse_token(amount, token).await,
+        }
+    }
+
+    async fn balance(&self) -> Result<TokenAmount> {
+        match self {
+            Self::Evm(wallet) => wallet.balance().await,
+            Self::Sol(wallet) => wallet.balance().await,
+        }
+    }
+
+    async fn token_balance(&self, token: &str) -> Result<TokenAmount> {
+        match self {
+            Self::Evm(wallet) => wallet.token_balance(token).await,
+            Self::Sol(wallet) => wallet.token_balance(token).await,
+        }
+    }
+
+    async fn token_balances(&self, tokens: &[&str]) -> Result<Vec<TokenAmount>> {
+        match self {
+            Self::Evm(wallet) => wallet.token_balances(tokens).await,
+            Self::Sol(wallet) => wallet.token_balances(tokens).await,
+        }
+    }
+
+    async fn transfer(&self, to: &str, amount: impl Into<Amount>) -> Result<AnyReceipt> {
+        let receipt = match self {
+            Self::Evm(wallet) => AnyReceipt::Evm(Box::new(wallet.transfer(to, amount).await?)),
+            Self::Sol(wallet) => AnyReceipt::Sol(Box::new(wallet.transfer(to, amount).await?)),
+        };
+
+        Ok(receipt)
+    }
+
+    async fn transfer_token(
+        &self,
+        token: &str,
+        amount: impl Into<Amount>,
+        to: &str,
+    ) -> Result<AnyReceipt> {
+        let receipt = match self {
+            Self::Evm(wallet) => {
+                AnyReceipt::Evm(Box::new(wallet.transfer_token(token, amount, to).await?))
+            }
+            Self::Sol(wallet) => {
+                AnyReceipt::Sol(Box::new(wallet.transfer_token(token, amount, to).await?))
+            }
+        };
+
+        Ok(receipt)
+    }
+
+    async fn transfer_all_tokens(&self, token: &str, to: &str) -> Result<Option<AnyReceipt>> {
+        let receipt = match self {
+            Self::Evm(wallet) => wallet
+                .transfer_all_tokens(token, to)
+                .await?
+                .map(|receipt| AnyReceipt::Evm(Box::new(receipt))),
+            Self::Sol(wallet) => wallet
+                .transfer_all_tokens(token, to)
+                .await?
+                .map(|receipt| AnyReceipt::Sol(Box::new(receipt))),
+        };
+
+        Ok(receipt)
+    }
+
+    async fn close(&self, to: &str) -> Result<AnyReceipt> {
+        let receipt = match self {
+            Self::Evm(wallet) => AnyReceipt::Evm(Box::new(wallet.close(to).await?)),
+            Self::Sol(wallet) => AnyReceipt::Sol(Box::new(wallet.close(to).await?)),
+        };
+
+        Ok(receipt)
+    }
+
+    async fn sign_and_send(&self, txn: TransactionData) -> Result<AnyReceipt> {
+        let receipt = match (self, txn) {
+            (Self::Evm(wallet), TransactionData::Evm(txn)) => {
+                AnyReceipt::Evm(Box::new(wallet.sign_and_send(txn).await?))
+            }
+            (Self::Sol(wallet), TransactionData::Sol(txn)) => {
+                AnyReceipt::Sol(Box::new(wallet.sign_and_send(txn).await?))
+            }
+            _ => bail!(
+                "Transaction is for a different chain than the {} wallet",
+                self.chain()
+            ),
+        };
+
+        Ok(receipt)
+    }
+}
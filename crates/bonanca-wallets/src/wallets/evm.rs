@@ -1,6 +1,7 @@
-use std::{path::Path, str::FromStr};
+use std::{path::Path, str::FromStr, sync::OnceLock};
 
 use alloy::{
+    dyn_abi::TypedData,
     network::TransactionBuilder,
     providers::{DynProvider, Provider, ProviderBuilder},
     rpc::types::{TransactionReceipt, TransactionRequest},
@@ -10,23 +11,53 @@ use alloy::{
         local::{LocalSigner, PrivateKeySigner},
     },
     sol,
-    transports::http::reqwest::Url,
+    sol_types::{Eip712Domain, SolStruct},
+    transports::{TransportError, http::reqwest::Url},
 };
 use alloy_primitives::{
-    Address, FixedBytes, Signature, Uint,
-    utils::{format_ether, format_units, parse_ether, parse_units},
+    Address, Bytes, FixedBytes, Signature, TxHash, U256,
+    aliases::{U48, U160},
+};
+use anyhow::{Context, Result, bail};
+use bonanca_keyvault::{
+    chains::{Chain, DerivationScheme},
+    hd_keys::HDkeys,
+    keystore::EthKeystore,
+    keyvault::KeyVault,
+    password::PasswordProvider,
+    secret::PrivateKey,
+    session::UnlockedVault,
+};
+use zeroize::Zeroizing;
+
+use crate::{
+    HdWalletLoad, HdWalletView, HdWallets, ImportedWalletLoad, SessionWalletLoad, Wallet,
+    WalletLoad, WalletView,
+    amount::{Amount, TokenAmount},
+    discovery::scan_children,
+    error::{WalletError, WalletResult},
+    fees::{FeePolicy, GasFees, StuckTxnPolicy},
+    multicall::Multicall,
+    nft::{
+        ERC721_INTERFACE_ID, ERC1155_INTERFACE_ID, NftMetadata, NftMetadataApi, NftStandard,
+        expand_uri_id,
+    },
+    nonce::{NonceManager, PendingTxn},
+    permit::{
+        IERC20Permit, IPermit2, PERMIT2, Permit, PermitDetails, PermitSingle, PermitTransferFrom,
+        SignedPermit, SignedPermit2, SignedPermit2Transfer, permit2_domain,
+    },
+    simulate::{Revert, RevertDecoder, SendOutcome, Simulation, SimulationMode},
+    tokens::{TokenCache, TokenMetadata},
 };
-use anyhow::Result;
-use bonanca_keyvault::{hd_keys::HDkeys, keyvault::KeyVault};
 
-use crate::{HdWalletLoad, HdWalletView, HdWallets, WalletLoad, WalletView};
+const NATIVE_DECIMALS: u8 = 18;
 
 impl HdWallets<LocalSigner<SigningKey>, u32> for HDkeys {
     fn get_child_keypair(&self, child: u32) -> Result<LocalSigner<SigningKey>> {
-        let path = format!("m/44'/60'/{child}'/0/0");
+        let path = Chain::Evm.child_path(child);
         let secret = self.derive_secp256k1_child_prvkey(path)?;
-        let key_bytes = FixedBytes::new(secret);
-        let signer = PrivateKeySigner::from_bytes(&key_bytes)?;
+        let signer = PrivateKeySigner::from_slice(secret.expose())?;
         Ok(signer)
     }
 }
@@ -34,8 +65,7 @@ impl HdWallets<LocalSigner<SigningKey>, u32> for HDkeys {
 impl HdWallets<LocalSigner<SigningKey>, &str> for HDkeys {
     fn get_child_keypair(&self, path: &str) -> Result<LocalSigner<SigningKey>> {
         let secret = self.derive_secp256k1_child_prvkey(path.to_string())?;
-        let key_bytes = FixedBytes::new(secret);
-        let signer = PrivateKeySigner::from_bytes(&key_bytes)?;
+        let signer = PrivateKeySigner::from_slice(secret.expose())?;
         Ok(signer)
     }
 }
@@ -48,321 +78,1304 @@ sol! {
     "src/wallets/ABI/ERC20.json"
 }
 
+sol! {
+    #[allow(missing_docs)]
+    #[sol(rpc)]
+    ERC721,
+    "src/wallets/ABI/ERC721.json"
+}
+
+sol! {
+    #[allow(missing_docs)]
+    #[sol(rpc)]
+    ERC1155,
+    "src/wallets/ABI/ERC1155.json"
+}
+
 pub struct EvmWallet {
     pub signer: Option<LocalSigner<SigningKey>>,
     pub client: DynProvider,
     pub pubkey: Address,
+    pub tokens: TokenCache,
+    pub nonces: NonceManager,
+    pub fees: FeePolicy,
+    pub simulation: SimulationMode,
+    /// Custom errors decoded from reverted simulations
+    pub errors: RevertDecoder,
+    network: OnceLock<String>,
 }
 
-impl WalletView<&str> for EvmWallet {
-    fn view(pubkey: &str, rpc: &str) -> Self {
-        let rpc_url = Url::parse(rpc).unwrap();
-        let client: DynProvider = ProviderBuilder::new().connect_http(rpc_url).erased();
-
+impl EvmWallet {
+    fn new(signer: Option<LocalSigner<SigningKey>>, client: DynProvider, pubkey: Address) -> Self {
         Self {
-            signer: None,
+            signer,
             client,
-            pubkey: Address::from_str(pubkey).unwrap(),
+            pubkey,
+            tokens: TokenCache::new(Chain::Evm),
+            nonces: NonceManager::new(),
+            fees: FeePolicy::default(),
+            simulation: SimulationMode::default(),
+            errors: RevertDecoder::new(),
+            network: OnceLock::new(),
         }
     }
 }
 
+impl WalletView<&str> for EvmWallet {
+    fn view(pubkey: &str, rpc: &str) -> WalletResult<Self> {
+        Ok(Self::new(None, connect(rpc, None)?, parse_address(pubkey)?))
+    }
+}
+
 impl WalletLoad<[u8; 32]> for EvmWallet {
-    fn load(pkey: [u8; 32], rpc: &str) -> Self {
-        let key_bytes = FixedBytes::new(pkey);
-        let signer = PrivateKeySigner::from_bytes(&key_bytes).unwrap();
-        let rpc_url = Url::parse(rpc).unwrap();
+    fn load(mut pkey: [u8; 32], rpc: &str) -> WalletResult<Self> {
+        <Self as WalletLoad<&PrivateKey>>::load(&PrivateKey::new(&mut pkey), rpc)
+    }
+}
+
+impl WalletLoad<&PrivateKey> for EvmWallet {
+    fn load(pkey: &PrivateKey, rpc: &str) -> WalletResult<Self> {
+        let signer = PrivateKeySigner::from_slice(pkey.expose())
+            .map_err(|e| WalletError::InvalidKey(e.to_string()))?;
         let pubkey = signer.address();
-        let client: DynProvider = ProviderBuilder::new()
-            .wallet(signer.clone())
-            .connect_http(rpc_url)
-            .erased();
+        let client = connect(rpc, Some(&signer))?;
 
-        Self {
-            signer: Some(signer),
-            client,
-            pubkey,
-        }
+        Ok(Self::new(Some(signer), client, pubkey))
     }
 }
 
 impl<T: AsRef<Path>> HdWalletView<T, u32> for EvmWallet {
-    fn view(keyvault: T, rpc: &str, child: u32) -> Self {
-        let key_vault = KeyVault::load(keyvault.as_ref());
-        let path = format!("m/44'/60'/{child}'/0/0");
-        let pubkey = key_vault.chain_keys.get(&path).unwrap();
-        let rpc_url = Url::parse(rpc).unwrap();
-        let addy = Address::from_str(pubkey).unwrap();
-        let client: DynProvider = ProviderBuilder::new().connect_http(rpc_url).erased();
-
-        Self {
-            signer: None,
-            client,
-            pubkey: addy,
-        }
+    fn view(keyvault: T, rpc: &str, child: u32) -> WalletResult<Self> {
+        let key_vault = KeyVault::load(keyvault.as_ref())?;
+        let path = Chain::Evm.child_path(child);
+        let pubkey = key_vault.get_address(&path)?;
+
+        Ok(Self::new(
+            None,
+            connect(rpc, None)?,
+            parse_address(&pubkey)?,
+        ))
     }
 }
 
 impl<T: AsRef<Path>> HdWalletView<T, &str> for EvmWallet {
-    fn view(keyvault: T, rpc: &str, path: &str) -> Self {
-        let key_vault = KeyVault::load(keyvault.as_ref());
-        let pubkey = key_vault.chain_keys.get(path).unwrap();
-        let rpc_url = Url::parse(rpc).unwrap();
-        let addy = Address::from_str(pubkey).unwrap();
-        let client: DynProvider = ProviderBuilder::new().connect_http(rpc_url).erased();
-
-        Self {
-            signer: None,
-            client,
-            pubkey: addy,
-        }
+    fn view(keyvault: T, rpc: &str, label_or_path: &str) -> WalletResult<Self> {
+        let key_vault = KeyVault::load(keyvault.as_ref())?;
+        let path = key_vault.resolve_path(Chain::Evm, label_or_path)?;
+        let pubkey = key_vault.get_address(&path)?;
+
+        Ok(Self::new(
+            None,
+            connect(rpc, None)?,
+            parse_address(&pubkey)?,
+        ))
     }
 }
 
 impl<T: AsRef<Path>> HdWalletLoad<T, u32> for EvmWallet {
-    fn load(keyvault: T, rpc: &str, child: u32) -> Self {
-        let mut key_vault = KeyVault::load(keyvault.as_ref());
-        let path = format!("m/44'/60'/{child}'/0/0");
-        let hd_keys = key_vault.decrypt_vault().unwrap();
-        let signer: LocalSigner<SigningKey> = hd_keys.get_child_keypair(child).unwrap();
-        let rpc_url = Url::parse(rpc).unwrap();
+    fn load(keyvault: T, rpc: &str, child: u32, password: &PasswordProvider) -> WalletResult<Self> {
+        let path = Chain::Evm.child_path(child);
+
+        <Self as HdWalletLoad<T, &str>>::load(keyvault, rpc, &path, password)
+    }
+}
+
+impl<T: AsRef<Path>> HdWalletLoad<T, &str> for EvmWallet {
+    fn load(
+        keyvault: T,
+        rpc: &str,
+        label_or_path: &str,
+        password: &PasswordProvider,
+    ) -> WalletResult<Self> {
+        let key_vault = KeyVault::load(keyvault.as_ref())?;
+        let path = key_vault.resolve_path(Chain::Evm, label_or_path)?;
+        let hd_keys = key_vault.decrypt_vault(password)?;
+        let signer: LocalSigner<SigningKey> = hd_keys
+            .get_child_keypair(path.as_str())
+            .map_err(|e| WalletError::Derivation(e.to_string()))?;
         let pubkey = signer.address();
-        let client: DynProvider = ProviderBuilder::new()
-            .wallet(signer.clone())
-            .connect_http(rpc_url)
-            .erased();
+        let client = connect(rpc, Some(&signer))?;
 
         // Add pubkey to keyvault if not already in it
-        match key_vault.chain_keys.get(&path) {
-            Some(_) => {}
-            None => {
-                key_vault.add_pubkey(&path, &pubkey.to_string());
-                key_vault.write(keyvault.as_ref());
-            }
+        if !key_vault.chain_keys.contains_key(&path) {
+            KeyVault::update(keyvault.as_ref(), |key_vault| {
+                key_vault.add_account(Chain::Evm, &path, &pubkey.to_string());
+                Ok(())
+            })?;
         }
 
-        Self {
-            signer: Some(signer),
-            client,
-            pubkey,
-        }
+        Ok(Self::new(Some(signer), client, pubkey))
     }
 }
 
-impl<T: AsRef<Path>> HdWalletLoad<T, &str> for EvmWallet {
-    fn load(keyvault: T, rpc: &str, path: &str) -> Self {
-        let mut key_vault = KeyVault::load(keyvault.as_ref());
-        let hd_keys = key_vault.decrypt_vault().unwrap();
-        let signer: LocalSigner<SigningKey> = hd_keys.get_child_keypair(path).unwrap();
-        let rpc_url = Url::parse(rpc).unwrap();
+impl<T: AsRef<Path>> ImportedWalletLoad<T> for EvmWallet {
+    fn load_imported(
+        keyvault: T,
+        rpc: &str,
+        label: &str,
+        password: &PasswordProvider,
+    ) -> WalletResult<Self> {
+        let key_vault = KeyVault::load(keyvault.as_ref())?;
+        let secret = key_vault.get_imported_key(label, Chain::Evm, password)?;
+
+        <Self as WalletLoad<&PrivateKey>>::load(&secret, rpc)
+    }
+}
+
+impl SessionWalletLoad<u32> for EvmWallet {
+    fn load(session: &UnlockedVault, rpc: &str, child: u32) -> WalletResult<Self> {
+        let path = Chain::Evm.child_path(child);
+
+        <Self as SessionWalletLoad<&str>>::load(session, rpc, &path)
+    }
+}
+
+impl SessionWalletLoad<&str> for EvmWallet {
+    fn load(session: &UnlockedVault, rpc: &str, label_or_path: &str) -> WalletResult<Self> {
+        let path = session.resolve_path(Chain::Evm, label_or_path)?;
+        let signer: LocalSigner<SigningKey> = session
+            .with_keys(|hd_keys| hd_keys.get_child_keypair(path.as_str()))?
+            .map_err(|e| WalletError::Derivation(e.to_string()))?;
         let pubkey = signer.address();
-        let client: DynProvider = ProviderBuilder::new()
+        let client = connect(rpc, Some(&signer))?;
+
+        session.add_pubkey(&path, &pubkey.to_string())?;
+
+        Ok(Self::new(Some(signer), client, pubkey))
+    }
+}
+
+impl<T: AsRef<Path>> HdWalletView<T, (DerivationScheme, u32)> for EvmWallet {
+    fn view(
+        keyvault: T,
+        rpc: &str,
+        (scheme, child): (DerivationScheme, u32),
+    ) -> WalletResult<Self> {
+        check_scheme(scheme)?;
+
+        <Self as HdWalletView<T, &str>>::view(keyvault, rpc, &scheme.path(child))
+    }
+}
+
+impl<T: AsRef<Path>> HdWalletLoad<T, (DerivationScheme, u32)> for EvmWallet {
+    fn load(
+        keyvault: T,
+        rpc: &str,
+        (scheme, child): (DerivationScheme, u32),
+        password: &PasswordProvider,
+    ) -> WalletResult<Self> {
+        check_scheme(scheme)?;
+
+        <Self as HdWalletLoad<T, &str>>::load(keyvault, rpc, &scheme.path(child), password)
+    }
+}
+
+impl SessionWalletLoad<(DerivationScheme, u32)> for EvmWallet {
+    fn load(
+        session: &UnlockedVault,
+        rpc: &str,
+        (scheme, child): (DerivationScheme, u32),
+    ) -> WalletResult<Self> {
+        check_scheme(scheme)?;
+
+        <Self as SessionWalletLoad<&str>>::load(session, rpc, &scheme.path(child))
+    }
+}
+
+fn connect(rpc: &str, signer: Option<&LocalSigner<SigningKey>>) -> WalletResult<DynProvider> {
+    let rpc_url =
+        Url::parse(rpc).map_err(|e| WalletError::InvalidRpcUrl(rpc.to_string(), e.to_string()))?;
+
+    let client = match signer {
+        Some(signer) => ProviderBuilder::new()
             .wallet(signer.clone())
             .connect_http(rpc_url)
-            .erased();
+            .erased(),
+        None => ProviderBuilder::new().connect_http(rpc_url).erased(),
+    };
 
-        // Add pubkey to keyvault if not already in it
-        match key_vault.chain_keys.get(path) {
-            Some(_) => {}
-            None => {
-                key_vault.add_pubkey(&path, &pubkey.to_string());
-                key_vault.write(keyvault.as_ref());
-            }
-        }
+    Ok(client)
+}
 
-        Self {
-            signer: Some(signer),
-            client,
-            pubkey,
-        }
+fn parse_address(address: &str) -> WalletResult<Address> {
+    Address::from_str(address)
+        .map_err(|_| WalletError::InvalidAddress(Chain::Evm, address.to_string()))
+}
+
+fn typed_data_hash(typed_data: &str) -> Result<FixedBytes<32>> {
+    let typed_data: TypedData =
+        serde_json::from_str(typed_data).context("Invalid EIP-712 typed data")?;
+
+    Ok(typed_data.eip712_signing_hash()?)
+}
+
+fn check_scheme(scheme: DerivationScheme) -> WalletResult<()> {
+    if scheme.chain() != Chain::Evm {
+        return Err(WalletError::WrongScheme(scheme, Chain::Evm));
     }
+
+    Ok(())
 }
 
 impl EvmWallet {
     pub async fn sign_hash(&self, hash: &FixedBytes<32>) -> Result<Signature> {
-        let sig = self.signer.as_ref().unwrap().sign_hash(&hash).await?;
+        let signer = self.signer.as_ref().context("Wallet is view only")?;
+        let sig = signer.sign_hash(hash).await?;
+
+        Ok(sig)
+    }
+
+    /// Signs `message` with the EIP-191 prefix, as `personal_sign` does
+    pub async fn sign_message(&self, message: &[u8]) -> Result<Signature> {
+        let signer = self.signer.as_ref().context("Wallet is view only")?;
+        let sig = signer.sign_message(message).await?;
 
         Ok(sig)
     }
 
+    /// Signs an EIP-712 struct under `domain`
+    pub async fn sign_typed<T: SolStruct>(
+        &self,
+        value: &T,
+        domain: &Eip712Domain,
+    ) -> Result<Signature> {
+        self.sign_hash(&value.eip712_signing_hash(domain)).await
+    }
+
+    /// Signs EIP-712 typed data given as `eth_signTypedData_v4` JSON, with
+    /// `types`, `primaryType`, `domain` and `message`
+    pub async fn sign_typed_data(&self, typed_data: &str) -> Result<Signature> {
+        self.sign_hash(&typed_data_hash(typed_data)?).await
+    }
+
+    /// Checks that `address` signed `message` with `personal_sign`
+    pub fn verify_message(address: &str, message: &[u8], signature: &Signature) -> Result<bool> {
+        let signer = signature.recover_address_from_msg(message)?;
+
+        Ok(signer == Address::from_str(address)?)
+    }
+
+    /// Checks that `address` signed the `eth_signTypedData_v4` JSON
+    pub fn verify_typed_data(
+        address: &str,
+        typed_data: &str,
+        signature: &Signature,
+    ) -> Result<bool> {
+        let signer = signature.recover_address_from_prehash(&typed_data_hash(typed_data)?)?;
+
+        Ok(signer == Address::from_str(address)?)
+    }
+
     pub async fn approve_token_spending(
         &self,
         token: &str,
         spender: &str,
-        amount: f64,
-    ) -> Result<()> {
+        amount: impl Into<Amount>,
+    ) -> Result<SendOutcome<TxHash>> {
+        self.send_approval(token, spender, amount)
+            .await?
+            .watch()
+            .await
+    }
+
+    /// Submits an approval without waiting for it to be mined
+    pub async fn send_approval(
+        &self,
+        token: &str,
+        spender: &str,
+        amount: impl Into<Amount>,
+    ) -> Result<SendOutcome> {
         let token_addy = Address::from_str(token)?;
         let spender_addy = Address::from_str(spender)?;
+        let value = self.format_token(amount, token).await?;
 
         let erc20 = ERC20::new(token_addy, &self.client);
+        let txn = erc20
+            .approve(spender_addy, value.raw)
+            .into_transaction_request();
 
-        let decimals = erc20.decimals().call().await?;
-        let value: Uint<256, 4> = parse_units(&amount.to_string(), decimals)?.into();
+        self.send(txn).await
+    }
+
+    pub async fn get_token_allowance(&self, token: &str, spender: &str) -> Result<TokenAmount> {
+        let token_addy = Address::from_str(token)?;
+        let spender_addy = Address::from_str(spender)?;
+
+        let erc20 = ERC20::new(token_addy, &self.client);
+
+        let value = erc20.allowance(self.pubkey, spender_addy).call().await?;
+        let deci = self.token_decimals(token).await?;
+
+        Ok(TokenAmount::new(value, deci))
+    }
+
+    /// Allowances for several `(token, spender)` pairs in one batched call
+    pub async fn allowances(&self, pairs: &[(&str, &str)]) -> Result<Vec<TokenAmount>> {
+        let tokens: Vec<&str> = pairs.iter().map(|(token, _)| *token).collect();
+        let metadata = self.tokens_metadata(&tokens).await?;
+
+        let mut multicall = Multicall::new();
+        for (token, spender) in pairs {
+            let call = ERC20::allowanceCall {
+                _owner: self.pubkey,
+                _spender: Address::from_str(spender)?,
+            };
+            multicall.add(Address::from_str(token)?, &call);
+        }
+
+        let results = multicall.call(&self.client).await?;
+
+        metadata
+            .iter()
+            .enumerate()
+            .map(|(i, token)| {
+                let allowance = results.decode::<ERC20::allowanceCall>(i).with_context(|| {
+                    format!("Could not read the allowance of {}", token.address)
+                })?;
+                Ok(TokenAmount::new(allowance, token.decimals))
+            })
+            .collect()
+    }
+
+    /// Whether `token` implements EIP-2612 permits
+    pub async fn supports_permit(&self, token: &str) -> Result<bool> {
+        let token_addy = Address::from_str(token)?;
+        let erc20 = IERC20Permit::new(token_addy, &self.client);
+
+        let separator = erc20.DOMAIN_SEPARATOR().call().await;
+        let nonce = erc20.nonces(self.pubkey).call().await;
+
+        Ok(separator.is_ok() && nonce.is_ok())
+    }
+
+    /// Signs an EIP-2612 permit letting `spender` pull `amount` of `token`
+    /// until `deadline`, a unix timestamp. Nothing is sent, the spender
+    /// submits the permit along with its own call.
+    pub async fn sign_permit(
+        &self,
+        token: &str,
+        spender: &str,
+        amount: impl Into<Amount>,
+        deadline: u64,
+    ) -> Result<SignedPermit> {
+        let token_addy = Address::from_str(token)?;
+        let spender_addy = Address::from_str(spender)?;
+        let value = self.format_token(amount, token).await?;
+
+        let erc20 = IERC20Permit::new(token_addy, &self.client);
+        let nonce = erc20
+            .nonces(self.pubkey)
+            .call()
+            .await
+            .with_context(|| format!("{token} does not support EIP-2612 permits"))?;
+
+        let permit = Permit {
+            owner: self.pubkey,
+            spender: spender_addy,
+            value: value.raw,
+            nonce,
+            deadline: U256::from(deadline),
+        };
+
+        let domain = self.permit_domain(token_addy).await?;
+        let signature = self.sign_typed(&permit, &domain).await?;
+
+        Ok(SignedPermit { permit, signature })
+    }
 
-        let _ = erc20
-            .approve(spender_addy, value)
-            .send()
+    // Tokens only publish the hash of their domain, so rebuild it and check
+    async fn permit_domain(&self, token: Address) -> Result<Eip712Domain> {
+        let erc20 = IERC20Permit::new(token, &self.client);
+        let separator = erc20
+            .DOMAIN_SEPARATOR()
+            .call()
+            .await
+            .with_context(|| format!("{token} does not support EIP-2612 permits"))?;
+
+        let name = self
+            .token_metadata(&token.to_string())
             .await?
-            .watch()
-            .await?;
+            .name
+            .with_context(|| format!("{token} has no name to build its permit domain"))?;
+        let chain_id = U256::from(self.client.get_chain_id().await?);
+
+        // Most tokens use version "1", some like USDC expose their own
+        let mut versions = vec!["1".to_string()];
+        if let Ok(version) = erc20.version().call().await {
+            versions.insert(0, version);
+        }
+
+        for version in versions {
+            let domain = Eip712Domain::new(
+                Some(name.clone().into()),
+                Some(version.into()),
+                Some(chain_id),
+                Some(token),
+                None,
+            );
+
+            if domain.separator() == separator {
+                return Ok(domain);
+            }
+        }
 
-        Ok(())
+        bail!("{token} uses a non-standard permit domain")
     }
 
-    pub async fn get_token_allowance(&self, token: &str, spender: &str) -> Result<f64> {
+    /// Approves Permit2 to move all of `token`, needed once per token
+    /// before any Permit2 signature can be used
+    pub async fn approve_permit2(&self, token: &str) -> Result<SendOutcome<TxHash>> {
+        let deci = self.token_decimals(token).await?;
+        let max = TokenAmount::new(U256::MAX, deci);
+
+        self.approve_token_spending(token, &PERMIT2.to_string(), max)
+            .await
+    }
+
+    /// Signs a Permit2 allowance letting `spender` pull up to `amount` of
+    /// `token` until `expiration`. The signature itself must be submitted
+    /// before `deadline`. Both are unix timestamps.
+    pub async fn sign_permit2(
+        &self,
+        token: &str,
+        spender: &str,
+        amount: impl Into<Amount>,
+        expiration: u64,
+        deadline: u64,
+    ) -> Result<SignedPermit2> {
         let token_addy = Address::from_str(token)?;
         let spender_addy = Address::from_str(spender)?;
+        let value = self.format_token(amount, token).await?;
 
-        let erc20 = ERC20::new(token_addy, &self.client);
+        let permit2 = IPermit2::new(PERMIT2, &self.client);
+        let allowance = permit2
+            .allowance(self.pubkey, token_addy, spender_addy)
+            .call()
+            .await?;
 
-        let value = erc20.allowance(self.pubkey, spender_addy).call().await?;
+        let permit = PermitSingle {
+            details: PermitDetails {
+                token: token_addy,
+                amount: U160::saturating_from(value.raw),
+                expiration: U48::from(expiration),
+                nonce: allowance.nonce,
+            },
+            spender: spender_addy,
+            sigDeadline: U256::from(deadline),
+        };
+
+        let domain = permit2_domain(self.client.get_chain_id().await?);
+        let signature = self.sign_typed(&permit, &domain).await?;
+
+        Ok(SignedPermit2 { permit, signature })
+    }
+
+    /// Signs a one-time Permit2 transfer. Nonces are unordered, so the
+    /// protocol consuming the permit usually picks one.
+    pub async fn sign_permit2_transfer(
+        &self,
+        permit: PermitTransferFrom,
+    ) -> Result<SignedPermit2Transfer> {
+        let domain = permit2_domain(self.client.get_chain_id().await?);
+        let signature = self.sign_typed(&permit, &domain).await?;
+
+        Ok(SignedPermit2Transfer { permit, signature })
+    }
+
+    /// Loads a wallet from an Ethereum V3 keystore (geth, foundry)
+    pub fn from_keystore(fname: &Path, rpc: &str, password: &PasswordProvider) -> Result<Self> {
+        let keystore = EthKeystore::load(fname)?;
+        let pass = Zeroizing::new(password.get_password("Keystore Password: ")?);
+        let secret = keystore.decrypt(&pass);
+
+        Ok(<Self as WalletLoad<&PrivateKey>>::load(&secret?, rpc)?)
+    }
 
-        let deci = erc20.decimals().call().await?;
-        let allow = format_units(value, deci)?;
+    pub fn export_keystore(&self, password: &PasswordProvider) -> Result<EthKeystore> {
+        let signer = self.signer.as_ref().context("Wallet is view only")?;
+        let secret = PrivateKey::new(&mut signer.credential().to_bytes().into());
+        let pass = Zeroizing::new(password.get_password("Set Keystore Password: ")?);
+        let keystore = EthKeystore::encrypt(&secret, &pass);
 
-        Ok(allow.parse()?)
+        Ok(keystore?)
+    }
+
+    /// Walks the children of `scheme` until `gap_limit` unused accounts in
+    /// a row, recording every used account in the vault. Returns the paths
+    /// of the used accounts.
+    pub async fn discover(
+        session: &UnlockedVault,
+        rpc: &str,
+        scheme: DerivationScheme,
+        gap_limit: u32,
+    ) -> Result<Vec<String>> {
+        check_scheme(scheme)?;
+
+        let first = session.derive_address(Chain::Evm, &scheme.path(0))?;
+        let mut wallet = <Self as WalletView<&str>>::view(&first, rpc)?;
+
+        let children = scan_children(gap_limit, async |child| -> Result<bool> {
+            let address = session.derive_address(Chain::Evm, &scheme.path(child))?;
+            wallet.pubkey = Address::from_str(&address)?;
+            wallet.is_used().await
+        })
+        .await?;
+
+        children
+            .into_iter()
+            .map(|child| -> Result<String> {
+                let path = scheme.path(child);
+                let address = session.derive_address(Chain::Evm, &path)?;
+                session.add_pubkey(&path, &address)?;
+                Ok(path)
+            })
+            .collect()
+    }
+
+    /// An account counts as used once it has sent a transaction or holds
+    /// a balance
+    pub async fn is_used(&self) -> Result<bool> {
+        if self.client.get_transaction_count(self.pubkey).await? > 0 {
+            return Ok(true);
+        }
+
+        Ok(!self.client.get_balance(self.pubkey).await?.is_zero())
     }
 
     pub fn get_pubkey(&self) -> Result<String> {
         Ok(self.pubkey.to_string())
     }
 
-    pub fn format_native(&self, amount: f64) -> Result<u64> {
-        let amt = (amount * 1e18) as u64;
+    /// Shares `cache` with this wallet, e.g. one cache for every EVM wallet
+    pub fn with_token_cache(mut self, cache: TokenCache) -> Self {
+        self.tokens = cache;
+        self
+    }
 
-        Ok(amt)
+    pub fn with_fee_policy(mut self, fees: FeePolicy) -> Self {
+        self.fees = fees;
+        self
     }
 
-    pub fn parse_native(&self, amount: u64) -> Result<f64> {
-        Ok((amount as f64) / 1.0e18)
+    pub fn with_simulation_mode(mut self, simulation: SimulationMode) -> Self {
+        self.simulation = simulation;
+        self
     }
 
-    pub async fn format_token(&self, amount: f64, token: &str) -> Result<u64> {
-        let token_addy = Address::from_str(token)?;
-        let erc20 = ERC20::new(token_addy, &self.client);
-        let deci = erc20.decimals().call().await?;
+    /// Chain id of the RPC, fetched once, which keys the token cache
+    async fn network(&self) -> Result<&str> {
+        if let Some(network) = self.network.get() {
+            return Ok(network);
+        }
 
-        let amt = (amount * 10.0_f64.powi(deci.into())) as u64;
+        let network = self.client.get_chain_id().await?.to_string();
 
-        Ok(amt)
+        Ok(self.network.get_or_init(|| network))
     }
 
-    pub async fn parse_token(&self, amount: u64, token: &str) -> Result<f64> {
+    pub async fn token_metadata(&self, token: &str) -> Result<TokenMetadata> {
+        let network = self.network().await?;
+        if let Some(metadata) = self.tokens.get(network, token) {
+            return Ok(metadata);
+        }
+
         let token_addy = Address::from_str(token)?;
         let erc20 = ERC20::new(token_addy, &self.client);
-        let deci = erc20.decimals().call().await?;
 
-        Ok((amount as f64) / 10.0_f64.powi(deci.into()))
+        let decimals = erc20.decimals().call().await?;
+        // Both are optional in ERC-20 and some older tokens return bytes32
+        let symbol = erc20.symbol().call().await.ok();
+        let name = erc20.name().call().await.ok();
+
+        let metadata = TokenMetadata {
+            address: token_addy.to_string(),
+            decimals,
+            symbol,
+            name,
+            program: None,
+        };
+        self.tokens.insert(network, metadata.clone());
+
+        Ok(metadata)
+    }
+
+    /// Metadata for several tokens, fetching everything not yet cached in
+    /// one batched call
+    pub async fn tokens_metadata(&self, tokens: &[&str]) -> Result<Vec<TokenMetadata>> {
+        let network = self.network().await?;
+        let mut multicall = Multicall::new();
+        let mut pending = Vec::new();
+
+        for token in tokens {
+            if self.tokens.get(network, token).is_some() {
+                continue;
+            }
+
+            let token_addy = Address::from_str(token)?;
+            let decimals = multicall.add(token_addy, &ERC20::decimalsCall {});
+            let symbol = multicall.add(token_addy, &ERC20::symbolCall {});
+            let name = multicall.add(token_addy, &ERC20::nameCall {});
+
+            pending.push((token_addy, decimals, symbol, name));
+        }
+
+        if !multicall.is_empty() {
+            let results = multicall.call(&self.client).await?;
+
+            for (token_addy, decimals, symbol, name) in pending {
+                let decimals = results
+                    .decode::<ERC20::decimalsCall>(decimals)
+                    .with_context(|| format!("{token_addy} has no decimals, is it an ERC-20?"))?;
+
+                self.tokens.insert(
+                    network,
+                    TokenMetadata {
+                        address: token_addy.to_string(),
+                        decimals,
+                        symbol: results.decode::<ERC20::symbolCall>(symbol).ok(),
+                        name: results.decode::<ERC20::nameCall>(name).ok(),
+                        program: None,
+                    },
+                );
+            }
+        }
+
+        let mut metadata = Vec::with_capacity(tokens.len());
+        for token in tokens {
+            metadata.push(self.token_metadata(token).await?);
+        }
+
+        Ok(metadata)
+    }
+
+    pub async fn token_decimals(&self, token: &str) -> Result<u8> {
+        Ok(self.token_metadata(token).await?.decimals)
+    }
+
+    pub fn format_native(&self, amount: impl Into<Amount>) -> Result<TokenAmount> {
+        Ok(amount.into().resolve(NATIVE_DECIMALS)?)
+    }
+
+    pub fn parse_native(&self, amount: U256) -> Result<TokenAmount> {
+        Ok(TokenAmount::new(amount, NATIVE_DECIMALS))
+    }
+
+    pub async fn format_token(
+        &self,
+        amount: impl Into<Amount>,
+        token: &str,
+    ) -> Result<TokenAmount> {
+        let amount = amount.into();
+        let deci = self.token_decimals(token).await?;
+
+        Ok(amount.resolve(deci)?)
+    }
+
+    pub async fn parse_token(&self, amount: U256, token: &str) -> Result<TokenAmount> {
+        let raw = amount;
+        let deci = self.token_decimals(token).await?;
+
+        Ok(TokenAmount::new(raw, deci))
     }
 
-    pub async fn close(&self, to: &str) -> Result<()> {
+    pub async fn close(&self, to: &str) -> Result<SendOutcome<TransactionReceipt>> {
         let to_addy = Address::from_str(to)?;
         let bal = self.balance().await?;
 
-        let wei = parse_ether(&(bal * 0.9).to_string())?;
-
-        let fees = self.client.estimate_eip1559_fees().await?;
+        let fees = self.fees.estimate(&self.client).await?;
 
+        // Estimate with most of the balance, so the node doesn't reject the
+        // estimate for lack of funds
         let tx = TransactionRequest::default()
             .with_from(self.pubkey)
             .with_to(to_addy)
-            .with_value(wei);
+            .with_value(bal.raw / U256::from(10) * U256::from(9));
 
-        let gas = self.client.estimate_gas(tx).await?;
+        let gas = self.client.estimate_gas(tx.clone()).await?;
 
-        let total_fees_wei = (gas as u128) * fees.max_fee_per_gas;
-        let total_fees: f64 = format_ether(total_fees_wei).parse()?;
+        // Send with the fees and gas paid for here, so the rest is spendable
+        let amount = bal
+            .raw
+            .checked_sub(fees.max_cost(gas))
+            .context("Balance does not cover the transfer fees")?;
+        let tx = fees.apply(tx.with_value(amount).with_gas_limit(gas));
 
-        // 2 percent higher fee buffer
-        let _ = self.transfer(to, bal - (total_fees * 1.02)).await?;
-
-        Ok(())
+        self.send(tx).await?.get_receipt().await
     }
 
-    pub async fn balance(&self) -> Result<f64> {
+    pub async fn balance(&self) -> Result<TokenAmount> {
         let bal = self.client.get_balance(self.pubkey).await?;
 
-        let fbal = format_ether(bal);
+        Ok(TokenAmount::new(bal, NATIVE_DECIMALS))
+    }
 
-        Ok(fbal.parse()?)
+    pub async fn transfer(
+        &self,
+        to: &str,
+        amount: impl Into<Amount>,
+    ) -> Result<SendOutcome<TransactionReceipt>> {
+        self.send_transfer(to, amount).await?.get_receipt().await
     }
 
-    pub async fn transfer(&self, to: &str, amount: f64) -> Result<TransactionReceipt> {
+    /// Submits a native transfer without waiting for it to be mined
+    pub async fn send_transfer(&self, to: &str, amount: impl Into<Amount>) -> Result<SendOutcome> {
         let to_addy = Address::from_str(to)?;
-        let wei = parse_ether(&amount.to_string())?;
+        let wei = self.format_native(amount)?;
 
         let tx = TransactionRequest::default()
-            .with_from(self.pubkey)
             .with_to(to_addy)
-            .with_value(wei);
+            .with_value(wei.raw);
 
-        let sig = self
-            .client
-            .send_transaction(tx)
-            .await?
-            .get_receipt()
-            .await?;
-
-        Ok(sig)
+        self.send(tx).await
     }
 
-    pub async fn token_balance(&self, token: &str) -> Result<f64> {
+    pub async fn token_balance(&self, token: &str) -> Result<TokenAmount> {
         let token_addy = Address::from_str(token)?;
 
         // Instantiate the contract instance
         let erc20 = ERC20::new(token_addy, &self.client);
 
-        // Fetch the token balance and decimals
+        // Fetch the token balance, decimals come from the token cache
         let balance = erc20.balanceOf(self.pubkey).call().await?;
-        let deci = erc20.decimals().call().await?;
+        let deci = self.token_decimals(token).await?;
+
+        Ok(TokenAmount::new(balance, deci))
+    }
+
+    /// Balances of several tokens in at most two RPC calls, one for any
+    /// decimals not yet cached and one for the balances
+    pub async fn token_balances(&self, tokens: &[&str]) -> Result<Vec<TokenAmount>> {
+        let metadata = self.tokens_metadata(tokens).await?;
+
+        let call = ERC20::balanceOfCall {
+            _owner: self.pubkey,
+        };
 
-        let bal = format_units(balance, deci)?;
+        let mut multicall = Multicall::new();
+        for token in &metadata {
+            multicall.add(Address::from_str(&token.address)?, &call);
+        }
 
-        Ok(bal.parse()?)
+        let results = multicall.call(&self.client).await?;
+
+        metadata
+            .iter()
+            .enumerate()
+            .map(|(i, token)| {
+                let balance = results
+                    .decode::<ERC20::balanceOfCall>(i)
+                    .with_context(|| format!("Could not read the balance of {}", token.address))?;
+                Ok(TokenAmount::new(balance, token.decimals))
+            })
+            .collect()
     }
 
     pub async fn transfer_token(
         &self,
         token: &str,
-        amount: f64,
+        amount: impl Into<Amount>,
         to: &str,
-    ) -> Result<TransactionReceipt> {
+    ) -> Result<SendOutcome<TransactionReceipt>> {
+        self.send_token_transfer(token, amount, to)
+            .await?
+            .get_receipt()
+            .await
+    }
+
+    /// Submits a token transfer without waiting for it to be mined
+    pub async fn send_token_transfer(
+        &self,
+        token: &str,
+        amount: impl Into<Amount>,
+        to: &str,
+    ) -> Result<SendOutcome> {
         let to_addy = Address::from_str(to)?;
         let token_addy = Address::from_str(token)?;
+        let amnt = self.format_token(amount, token).await?;
 
         let erc20 = ERC20::new(token_addy, &self.client);
-        let deci = erc20.decimals().call().await?;
+        let txn = erc20.transfer(to_addy, amnt.raw).into_transaction_request();
 
-        let amnt: Uint<256, 4> = parse_units(&amount.to_string(), deci)?.into();
+        self.send(txn).await
+    }
 
-        let sig = erc20
-            .transfer(to_addy, amnt)
-            .send()
-            .await?
-            .get_receipt()
-            .await?;
+    /// `None` if there was no balance to transfer
+    pub async fn transfer_all_tokens(
+        &self,
+        token: &str,
+        to: &str,
+    ) -> Result<Option<SendOutcome<TransactionReceipt>>> {
+        let amount = self.token_balance(token).await?;
+        if amount.is_zero() {
+            return Ok(None);
+        }
 
-        Ok(sig)
+        Ok(Some(self.transfer_token(token, amount, to).await?))
     }
 
-    pub async fn transfer_all_tokens(&self, token: &str, to: &str) -> Result<()> {
-        let amount = self.token_balance(token).await?;
+    /// Whether `collection` is an ERC-721 or ERC-1155 contract, per ERC-165
+    pub async fn nft_standard(&self, collection: &str) -> Result<NftStandard> {
+        let collection_addy = Address::from_str(collection)?;
+        let erc721 = ERC721::new(collection_addy, &self.client);
+
+        for (interface, standard) in [
+            (ERC721_INTERFACE_ID, NftStandard::Erc721),
+            (ERC1155_INTERFACE_ID, NftStandard::Erc1155),
+        ] {
+            if let Ok(true) = erc721.supportsInterface(interface).call().await {
+                return Ok(standard);
+            }
+        }
+
+        bail!("{collection} is neither an ERC-721 nor an ERC-1155 contract")
+    }
+
+    /// Number of tokens held in an ERC-721 collection
+    pub async fn nft_balance(&self, collection: &str) -> Result<U256> {
+        let erc721 = ERC721::new(Address::from_str(collection)?, &self.client);
+
+        Ok(erc721.balanceOf(self.pubkey).call().await?)
+    }
+
+    pub async fn nft_owner(&self, collection: &str, token_id: U256) -> Result<String> {
+        let erc721 = ERC721::new(Address::from_str(collection)?, &self.client);
+
+        Ok(erc721.ownerOf(token_id).call().await?.to_string())
+    }
+
+    pub async fn owns_nft(&self, collection: &str, token_id: U256) -> Result<bool> {
+        let erc721 = ERC721::new(Address::from_str(collection)?, &self.client);
+
+        Ok(erc721.ownerOf(token_id).call().await? == self.pubkey)
+    }
+
+    pub async fn erc1155_balance(&self, collection: &str, id: U256) -> Result<U256> {
+        let erc1155 = ERC1155::new(Address::from_str(collection)?, &self.client);
+
+        Ok(erc1155.balanceOf(self.pubkey, id).call().await?)
+    }
+
+    pub async fn erc1155_balances(&self, collection: &str, ids: &[U256]) -> Result<Vec<U256>> {
+        let erc1155 = ERC1155::new(Address::from_str(collection)?, &self.client);
+        let accounts = vec![self.pubkey; ids.len()];
+
+        Ok(erc1155
+            .balanceOfBatch(accounts, ids.to_vec())
+            .call()
+            .await?)
+    }
+
+    /// Sends an ERC-721 token with `safeTransferFrom`, which reverts if `to`
+    /// is a contract that cannot receive it
+    pub async fn transfer_nft(
+        &self,
+        collection: &str,
+        token_id: U256,
+        to: &str,
+    ) -> Result<SendOutcome<TransactionReceipt>> {
+        let erc721 = ERC721::new(Address::from_str(collection)?, &self.client);
+        let txn = erc721
+            .safeTransferFrom(self.pubkey, Address::from_str(to)?, token_id)
+            .into_transaction_request();
+
+        self.sign_and_send(txn).await
+    }
+
+    pub async fn transfer_erc1155(
+        &self,
+        collection: &str,
+        id: U256,
+        amount: U256,
+        to: &str,
+    ) -> Result<SendOutcome<TransactionReceipt>> {
+        let erc1155 = ERC1155::new(Address::from_str(collection)?, &self.client);
+        let txn = erc1155
+            .safeTransferFrom(
+                self.pubkey,
+                Address::from_str(to)?,
+                id,
+                amount,
+                Bytes::new(),
+            )
+            .into_transaction_request();
+
+        self.sign_and_send(txn).await
+    }
+
+    pub async fn batch_transfer_erc1155(
+        &self,
+        collection: &str,
+        ids: &[U256],
+        amounts: &[U256],
+        to: &str,
+    ) -> Result<SendOutcome<TransactionReceipt>> {
+        if ids.len() != amounts.len() {
+            bail!("Got {} token ids but {} amounts", ids.len(), amounts.len());
+        }
+
+        let erc1155 = ERC1155::new(Address::from_str(collection)?, &self.client);
+        let txn = erc1155
+            .safeBatchTransferFrom(
+                self.pubkey,
+                Address::from_str(to)?,
+                ids.to_vec(),
+                amounts.to_vec(),
+                Bytes::new(),
+            )
+            .into_transaction_request();
+
+        self.sign_and_send(txn).await
+    }
+
+    /// Lets `operator` move every token in `collection`, ERC-721 and
+    /// ERC-1155 share the same function
+    pub async fn set_nft_approval_for_all(
+        &self,
+        collection: &str,
+        operator: &str,
+        approved: bool,
+    ) -> Result<SendOutcome<TransactionReceipt>> {
+        let erc721 = ERC721::new(Address::from_str(collection)?, &self.client);
+        let txn = erc721
+            .setApprovalForAll(Address::from_str(operator)?, approved)
+            .into_transaction_request();
+
+        self.sign_and_send(txn).await
+    }
+
+    pub async fn is_nft_approved_for_all(&self, collection: &str, operator: &str) -> Result<bool> {
+        let erc721 = ERC721::new(Address::from_str(collection)?, &self.client);
+
+        Ok(erc721
+            .isApprovedForAll(self.pubkey, Address::from_str(operator)?)
+            .call()
+            .await?)
+    }
+
+    /// Metadata URI of a token, `tokenURI` for ERC-721 and `uri` for ERC-1155
+    pub async fn nft_token_uri(&self, collection: &str, token_id: U256) -> Result<String> {
+        let collection_addy = Address::from_str(collection)?;
 
-        if amount != 0.0 {
-            let _ = self.transfer_token(token, amount, to).await?;
+        let uri = match self.nft_standard(collection).await? {
+            NftStandard::Erc721 => {
+                let erc721 = ERC721::new(collection_addy, &self.client);
+                erc721.tokenURI(token_id).call().await?
+            }
+            NftStandard::Erc1155 => {
+                let erc1155 = ERC1155::new(collection_addy, &self.client);
+                expand_uri_id(&erc1155.uri(token_id).call().await?, token_id)
+            }
+        };
+
+        Ok(uri)
+    }
+
+    /// Fetches a token's metadata JSON, resolving IPFS, Arweave and data URIs
+    pub async fn nft_metadata(&self, collection: &str, token_id: U256) -> Result<NftMetadata> {
+        let uri = self.nft_token_uri(collection, token_id).await?;
+
+        NftMetadataApi::default().get_metadata(&uri).await
+    }
+
+    pub async fn sign_and_send(
+        &self,
+        txn: TransactionRequest,
+    ) -> Result<SendOutcome<TransactionReceipt>> {
+        self.send(txn).await?.get_receipt().await
+    }
+
+    /// Signs and submits `txn` with the next local nonce, unless it already
+    /// has one, and returns without waiting for it to be mined
+    pub async fn send(&self, mut txn: TransactionRequest) -> Result<SendOutcome> {
+        if GasFees::of(&txn).is_none() {
+            txn = self.fees.estimate(&self.client).await?.apply(txn);
+        }
+
+        if let Some(simulation) = self.preflight(&mut txn).await? {
+            return Ok(SendOutcome::Simulated(simulation));
+        }
+
+        let nonce = match txn.nonce {
+            Some(nonce) => nonce,
+            None => self.nonces.allocate(&self.client, self.pubkey).await?,
+        };
+
+        txn = txn.with_from(self.pubkey).with_nonce(nonce);
+
+        match self.client.send_transaction(txn.clone()).await {
+            Ok(pending) => Ok(SendOutcome::Sent(PendingTxn::new(
+                pending,
+                txn,
+                nonce,
+                self.nonces.clone(),
+            ))),
+            Err(e) => {
+                // The nonce may or may not have been used, so ask the chain
+                self.nonces.reset();
+                Err(e.into())
+            }
         }
+    }
 
-        Ok(())
+    /// Simulates `txn` as the wallet's `SimulationMode` asks, filling in its
+    /// gas limit. Returns the simulation if `txn` must not be broadcast.
+    async fn preflight(&self, txn: &mut TransactionRequest) -> Result<Option<Simulation>> {
+        match self.simulation {
+            SimulationMode::Preflight => {
+                let simulation = self.simulate(txn).await?;
+                if txn.gas.is_none() {
+                    txn.gas = Some(simulation.gas);
+                }
+
+                Ok(None)
+            }
+            SimulationMode::DryRun => Ok(Some(self.simulate(txn).await?)),
+            SimulationMode::Off => Ok(None),
+        }
     }
 
-    pub async fn sign_and_send(&self, txn: TransactionRequest) -> Result<TransactionReceipt> {
-        let sig = self
+    /// Runs `txn` against the latest block with `eth_call` and estimates its
+    /// gas, failing with a `Revert` error if it would revert
+    pub async fn simulate(&self, txn: &TransactionRequest) -> Result<Simulation> {
+        let fees = match GasFees::of(txn) {
+            Some(fees) => fees,
+            None => self.fees.estimate(&self.client).await?,
+        };
+
+        // With fees set, nodes require the balance to cover the call's whole
+        // gas allowance, so simulate without them
+        let mut txn = txn.clone().with_from(self.pubkey);
+        txn.max_fee_per_gas = None;
+        txn.max_priority_fee_per_gas = None;
+        txn.gas_price = None;
+
+        let output = self
             .client
-            .send_transaction(txn)
-            .await?
-            .get_receipt()
-            .await?;
+            .call(txn.clone())
+            .await
+            .map_err(|e| self.revert_error(e))?;
+
+        let gas = match txn.gas {
+            Some(gas) => gas,
+            None => self
+                .client
+                .estimate_gas(txn)
+                .await
+                .map_err(|e| self.revert_error(e))?,
+        };
+
+        Ok(Simulation { output, gas, fees })
+    }
 
-        Ok(sig)
+    fn revert_error(&self, e: TransportError) -> anyhow::Error {
+        match e.as_error_resp().and_then(|resp| resp.as_revert_data()) {
+            Some(data) => Revert {
+                reason: self.errors.decode(&data),
+                data,
+            }
+            .into(),
+            None => e.into(),
+        }
+    }
+
+    /// Re-sends `pending` with the same nonce and bumped fees
+    pub async fn speed_up(&self, pending: &PendingTxn) -> Result<SendOutcome> {
+        self.replace(&pending.request, pending.request.clone())
+            .await
+    }
+
+    /// Replaces `pending` with a zero-value send to this wallet
+    pub async fn cancel(&self, pending: &PendingTxn) -> Result<SendOutcome> {
+        self.replace(&pending.request, self.cancel_request()).await
+    }
+
+    /// Waits for `pending`, bumping its fees each time it is not mined
+    /// within the timeout and finally cancelling it if `stuck.cancel` is set.
+    /// In dry-run mode the first replacement is only simulated and returned.
+    pub async fn wait_or_replace(
+        &self,
+        pending: PendingTxn,
+        stuck: &StuckTxnPolicy,
+    ) -> Result<SendOutcome<TransactionReceipt>> {
+        let nonce = pending.nonce;
+        let mut hashes = Vec::new();
+        let mut cancel_hash = None;
+        let mut bumps = 0;
+        let mut pending = pending;
+
+        loop {
+            hashes.push(pending.hash);
+            let PendingTxn { request, inner, .. } = pending;
+
+            let receipt = match inner.with_timeout(Some(stuck.timeout)).get_receipt().await {
+                Ok(receipt) => Some(receipt),
+                // An earlier version may have been mined while we waited
+                Err(_) => self.find_receipt(&hashes).await?,
+            };
+
+            if let Some(receipt) = receipt {
+                if Some(receipt.transaction_hash) == cancel_hash {
+                    bail!("Transaction with nonce {nonce} was cancelled");
+                }
+
+                return Ok(SendOutcome::Sent(receipt));
+            }
+
+            let cancelling = bumps == stuck.max_bumps;
+            let replacement = if !cancelling {
+                bumps += 1;
+                self.replace(&request, request.clone()).await
+            } else if stuck.cancel && cancel_hash.is_none() {
+                self.replace(&request, self.cancel_request()).await
+            } else {
+                self.nonces.reset();
+                bail!("Transaction with nonce {nonce} is still pending after {bumps} fee bumps");
+            };
+
+            pending = match replacement {
+                Ok(SendOutcome::Sent(pending)) => pending,
+                Ok(SendOutcome::Simulated(simulation)) => {
+                    return Ok(SendOutcome::Simulated(simulation));
+                }
+                // Fails with "nonce too low" if a version was just mined
+                Err(e) => match self.find_receipt(&hashes).await? {
+                    Some(receipt) => return Ok(SendOutcome::Sent(receipt)),
+                    None => return Err(e),
+                },
+            };
+
+            if cancelling {
+                cancel_hash = Some(pending.hash);
+            }
+        }
+    }
+
+    async fn replace(
+        &self,
+        previous: &TransactionRequest,
+        txn: TransactionRequest,
+    ) -> Result<SendOutcome> {
+        let nonce = previous.nonce.context("Pending transaction has no nonce")?;
+        let previous_fees = GasFees::of(previous).context("Pending transaction has no fees")?;
+        let fees = self.fees.bump(&self.client, previous_fees).await?;
+
+        let mut txn = fees.apply(txn).with_from(self.pubkey).with_nonce(nonce);
+
+        // Replacements go through the same checks as any other send
+        if let Some(simulation) = self.preflight(&mut txn).await? {
+            return Ok(SendOutcome::Simulated(simulation));
+        }
+
+        // The nonce is still taken by the original, so no resync on failure
+        let pending = self.client.send_transaction(txn.clone()).await?;
+
+        Ok(SendOutcome::Sent(PendingTxn::new(
+            pending,
+            txn,
+            nonce,
+            self.nonces.clone(),
+        )))
+    }
+
+    fn cancel_request(&self) -> TransactionRequest {
+        TransactionRequest::default()
+            .with_to(self.pubkey)
+            .with_value(U256::ZERO)
+    }
+
+    async fn find_receipt(&self, hashes: &[TxHash]) -> Result<Option<TransactionReceipt>> {
+        for hash in hashes {
+            if let Some(receipt) = self.client.get_transaction_receipt(*hash).await? {
+                return Ok(Some(receipt));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Replaces the local nonce with the chain's pending nonce
+    pub async fn resync_nonce(&self) -> Result<u64> {
+        self.nonces.resync(&self.client, self.pubkey).await
+    }
+}
+
+impl Wallet for EvmWallet {
+    type Receipt = SendOutcome<TransactionReceipt>;
+    type Transaction = TransactionRequest;
+
+    fn get_pubkey(&self) -> Result<String> {
+        EvmWallet::get_pubkey(self)
+    }
+
+    fn format_native(&self, amount: impl Into<Amount>) -> Result<TokenAmount> {
+        EvmWallet::format_native(self, amount)
+    }
+
+    fn parse_native(&self, amount: U256) -> Result<TokenAmount> {
+        EvmWallet::parse_native(self, amount)
+    }
+
+    async fn token_metadata(&self, token: &str) -> Result<TokenMetadata> {
+        EvmWallet::token_metadata(self, token).await
+    }
+
+    async fn token_decimals(&self, token: &str) -> Result<u8> {
+        EvmWallet::token_decimals(self, token).await
+    }
+
+    async fn format_token(&self, amount: impl Into<Amount>, token: &str) -> Result<TokenAmount> {
+        EvmWallet::format_token(self, amount, token).await
+    }
+
+    async fn parse_token(&self, amount: U256, token: &str) -> Result<TokenAmount> {
+        EvmWallet::parse_token(self, amount, token).await
+    }
+
+    async fn balance(&self) -> Result<TokenAmount> {
+        EvmWallet::balance(self).await
+    }
+
+    async fn token_balance(&self, token: &str) -> Result<TokenAmount> {
+        EvmWallet::token_balance(self, token).await
+    }
+
+    async fn token_balances(&self, tokens: &[&str]) -> Result<Vec<TokenAmount>> {
+        EvmWallet::token_balances(self, tokens).await
+    }
+
+    async fn transfer(
+        &self,
+        to: &str,
+        amount: impl Into<Amount>,
+    ) -> Result<SendOutcome<TransactionReceipt>> {
+        EvmWallet::transfer(self, to, amount).await
+    }
+
+    async fn transfer_token(
+        &self,
+        token: &str,
+        amount: impl Into<Amount>,
+        to: &str,
+    ) -> Result<SendOutcome<TransactionReceipt>> {
+        EvmWallet::transfer_token(self, token, amount, to).await
+    }
+
+    async fn transfer_all_tokens(
+        &self,
+        token: &str,
+        to: &str,
+    ) -> Result<Option<SendOutcome<TransactionReceipt>>> {
+        EvmWallet::transfer_all_tokens(self, token, to).await
+    }
+
+    async fn close(&self, to: &str) -> Result<SendOutcome<TransactionReceipt>> {
+        EvmWallet::close(self, to).await
+    }
+
+    async fn sign_and_send(
+        &self,
+        txn: TransactionRequest,
+    ) -> Result<SendOutcome<TransactionReceipt>> {
+        EvmWallet::sign_and_send(self, txn).await
     }
 }
@@ -1,2 +1,3 @@
+pub mod any;
 pub mod evm;
 pub mod solana;
@@ -1,5 +1,15 @@
+use alloy::transports::http::reqwest::Url;
+use alloy_primitives::U256;
 use anyhow::{Context, Result};
-use bonanca_keyvault::{hd_keys::HDkeys, keyvault::KeyVault};
+use bonanca_keyvault::{
+    chains::{Chain, DerivationScheme},
+    hd_keys::HDkeys,
+    keystore::{encode_solana_keypair, read_solana_keypair},
+    keyvault::KeyVault,
+    password::PasswordProvider,
+    secret::PrivateKey,
+    session::UnlockedVault,
+};
 use solana_client::{
     nonblocking::rpc_client::RpcClient,
     rpc_config::{CommitmentConfig, RpcTransactionConfig, UiTransactionEncoding},
@@ -10,24 +20,34 @@ use solana_client::{
 };
 use solana_sdk::{
     instruction::{AccountMeta, Instruction},
+    offchain_message::OffchainMessage,
     pubkey::Pubkey,
     signature::Signature,
     signer::{Signer, keypair::Keypair},
     transaction::{Transaction, VersionedTransaction},
 };
 use solana_system_interface::instruction::transfer;
-use std::{path::Path, str::FromStr};
-
-use crate::{HdWalletLoad, HdWalletView, HdWallets, WalletLoad, WalletView};
+use std::{path::Path, str::FromStr, sync::OnceLock};
+
+use crate::{
+    HdWalletLoad, HdWalletView, HdWallets, ImportedWalletLoad, SessionWalletLoad, Wallet,
+    WalletLoad, WalletView,
+    amount::{Amount, TokenAmount},
+    discovery::scan_children,
+    error::{WalletError, WalletResult},
+    tokens::{TokenCache, TokenMetadata},
+};
 
+const NATIVE_DECIMALS: u8 = 9;
 const SYSTEM_ID: Pubkey = Pubkey::from_str_const("11111111111111111111111111111111");
 const ATOKEN_ID: Pubkey = Pubkey::from_str_const("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL");
+const METADATA_ID: Pubkey = Pubkey::from_str_const("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
 
 impl HdWallets<Keypair, u32> for HDkeys {
     fn get_child_keypair(&self, child: u32) -> Result<Keypair> {
-        let path = format!("m/44'/501'/{child}'/0'");
+        let path = Chain::Solana.child_path(child);
         let secret = self.derive_ed25519_child_prvkey(path)?;
-        let keypair = Keypair::new_from_array(secret);
+        let keypair = Keypair::new_from_array(*secret.expose());
         Ok(keypair)
     }
 }
@@ -35,7 +55,7 @@ impl HdWallets<Keypair, u32> for HDkeys {
 impl HdWallets<Keypair, &str> for HDkeys {
     fn get_child_keypair(&self, path: &str) -> Result<Keypair> {
         let secret = self.derive_ed25519_child_prvkey(path.to_string())?;
-        let keypair = Keypair::new_from_array(secret);
+        let keypair = Keypair::new_from_array(*secret.expose());
         Ok(keypair)
     }
 }
@@ -44,117 +64,221 @@ pub struct SolWallet {
     pub key_pair: Option<Keypair>,
     pub client: RpcClient,
     pub pubkey: Pubkey,
+    pub tokens: TokenCache,
+    network: OnceLock<String>,
 }
 
-impl WalletView<&str> for SolWallet {
-    fn view(pubkey: &str, rpc: &str) -> Self {
+impl SolWallet {
+    fn new(key_pair: Option<Keypair>, client: RpcClient, pubkey: Pubkey) -> Self {
         Self {
-            key_pair: None,
-            client: RpcClient::new(rpc.to_string()),
-            pubkey: Pubkey::from_str(pubkey).expect("Could not parse pubkey"),
+            key_pair,
+            client,
+            pubkey,
+            tokens: TokenCache::new(Chain::Solana),
+            network: OnceLock::new(),
         }
     }
 }
 
+impl WalletView<&str> for SolWallet {
+    fn view(pubkey: &str, rpc: &str) -> WalletResult<Self> {
+        Ok(Self::new(None, connect(rpc)?, parse_pubkey(pubkey)?))
+    }
+}
+
 impl WalletLoad<[u8; 32]> for SolWallet {
-    fn load(pkey: [u8; 32], rpc: &str) -> Self {
-        let kp = Keypair::new_from_array(pkey);
-        let client = RpcClient::new(rpc.to_string());
+    fn load(mut pkey: [u8; 32], rpc: &str) -> WalletResult<Self> {
+        <Self as WalletLoad<&PrivateKey>>::load(&PrivateKey::new(&mut pkey), rpc)
+    }
+}
+
+impl WalletLoad<&PrivateKey> for SolWallet {
+    fn load(pkey: &PrivateKey, rpc: &str) -> WalletResult<Self> {
+        let kp = Keypair::new_from_array(*pkey.expose());
+        let client = connect(rpc)?;
         let pubkey = kp.pubkey();
 
-        Self {
-            key_pair: Some(kp),
-            client,
-            pubkey,
-        }
+        Ok(Self::new(Some(kp), client, pubkey))
     }
 }
 
 impl<T: AsRef<Path>> HdWalletView<T, u32> for SolWallet {
-    fn view(keyvault: T, rpc: &str, child: u32) -> Self {
-        let key_vault = KeyVault::load(keyvault.as_ref());
-        let path = format!("m/44'/501'/{child}'/0'");
-        let pubkey = key_vault
-            .chain_keys
-            .get(&path)
-            .expect("Child does not exist");
-        Self {
-            key_pair: None,
-            client: RpcClient::new(rpc.to_string()),
-            pubkey: Pubkey::from_str(&pubkey).expect("Could not parse pubkey"),
-        }
+    fn view(keyvault: T, rpc: &str, child: u32) -> WalletResult<Self> {
+        let key_vault = KeyVault::load(keyvault.as_ref())?;
+        let path = Chain::Solana.child_path(child);
+        let pubkey = key_vault.get_address(&path)?;
+
+        Ok(Self::new(None, connect(rpc)?, parse_pubkey(&pubkey)?))
     }
 }
 
 impl<T: AsRef<Path>> HdWalletView<T, &str> for SolWallet {
-    fn view(keyvault: T, rpc: &str, path: &str) -> Self {
-        let key_vault = KeyVault::load(keyvault.as_ref());
-        let pubkey = key_vault
-            .chain_keys
-            .get(path)
-            .expect("Child does not exist");
-        Self {
-            key_pair: None,
-            client: RpcClient::new(rpc.to_string()),
-            pubkey: Pubkey::from_str(&pubkey).expect("Could not parse pubkey"),
-        }
+    fn view(keyvault: T, rpc: &str, label_or_path: &str) -> WalletResult<Self> {
+        let key_vault = KeyVault::load(keyvault.as_ref())?;
+        let path = key_vault.resolve_path(Chain::Solana, label_or_path)?;
+        let pubkey = key_vault.get_address(&path)?;
+
+        Ok(Self::new(None, connect(rpc)?, parse_pubkey(&pubkey)?))
     }
 }
 
 impl<T: AsRef<Path>> HdWalletLoad<T, u32> for SolWallet {
-    fn load(keyvault: T, rpc: &str, child: u32) -> Self {
-        let mut key_vault = KeyVault::load(keyvault.as_ref());
-        let path = format!("m/44'/501'/{child}'/0'");
-        let hd_keys = key_vault.decrypt_vault().unwrap();
-        let kp: Keypair = hd_keys.get_child_keypair(child).unwrap();
-        let client = RpcClient::new(rpc.to_string());
+    fn load(keyvault: T, rpc: &str, child: u32, password: &PasswordProvider) -> WalletResult<Self> {
+        let path = Chain::Solana.child_path(child);
+
+        <Self as HdWalletLoad<T, &str>>::load(keyvault, rpc, &path, password)
+    }
+}
+
+impl<T: AsRef<Path>> HdWalletLoad<T, &str> for SolWallet {
+    fn load(
+        keyvault: T,
+        rpc: &str,
+        label_or_path: &str,
+        password: &PasswordProvider,
+    ) -> WalletResult<Self> {
+        let key_vault = KeyVault::load(keyvault.as_ref())?;
+        let path = key_vault.resolve_path(Chain::Solana, label_or_path)?;
+        let hd_keys = key_vault.decrypt_vault(password)?;
+        let kp: Keypair = hd_keys
+            .get_child_keypair(path.as_str())
+            .map_err(|e| WalletError::Derivation(e.to_string()))?;
+        let client = connect(rpc)?;
         let pubkey = kp.pubkey();
 
         // Add pubkey to keyvault if not already in it
-        match key_vault.chain_keys.get(&path) {
-            Some(_) => {}
-            None => {
-                key_vault.add_pubkey(&path, &pubkey.to_string());
-                key_vault.write(keyvault.as_ref());
-            }
+        if !key_vault.chain_keys.contains_key(&path) {
+            KeyVault::update(keyvault.as_ref(), |key_vault| {
+                key_vault.add_account(Chain::Solana, &path, &pubkey.to_string());
+                Ok(())
+            })?;
         }
 
-        Self {
-            key_pair: Some(kp),
-            client,
-            pubkey,
-        }
+        Ok(Self::new(Some(kp), client, pubkey))
     }
 }
 
-impl<T: AsRef<Path>> HdWalletLoad<T, &str> for SolWallet {
-    fn load(keyvault: T, rpc: &str, path: &str) -> Self {
-        let mut key_vault = KeyVault::load(keyvault.as_ref());
-        let hd_keys = key_vault.decrypt_vault().unwrap();
-        let kp: Keypair = hd_keys.get_child_keypair(path).unwrap();
-        let client = RpcClient::new(rpc.to_string());
+impl<T: AsRef<Path>> ImportedWalletLoad<T> for SolWallet {
+    fn load_imported(
+        keyvault: T,
+        rpc: &str,
+        label: &str,
+        password: &PasswordProvider,
+    ) -> WalletResult<Self> {
+        let key_vault = KeyVault::load(keyvault.as_ref())?;
+        let secret = key_vault.get_imported_key(label, Chain::Solana, password)?;
+
+        <Self as WalletLoad<&PrivateKey>>::load(&secret, rpc)
+    }
+}
+
+impl SessionWalletLoad<u32> for SolWallet {
+    fn load(session: &UnlockedVault, rpc: &str, child: u32) -> WalletResult<Self> {
+        let path = Chain::Solana.child_path(child);
+
+        <Self as SessionWalletLoad<&str>>::load(session, rpc, &path)
+    }
+}
+
+impl SessionWalletLoad<&str> for SolWallet {
+    fn load(session: &UnlockedVault, rpc: &str, label_or_path: &str) -> WalletResult<Self> {
+        let path = session.resolve_path(Chain::Solana, label_or_path)?;
+        let kp: Keypair = session
+            .with_keys(|hd_keys| hd_keys.get_child_keypair(path.as_str()))?
+            .map_err(|e| WalletError::Derivation(e.to_string()))?;
+        let client = connect(rpc)?;
         let pubkey = kp.pubkey();
 
-        // Add pubkey to keyvault if not already in it
-        match key_vault.chain_keys.get(path) {
-            Some(_) => {}
-            None => {
-                key_vault.add_pubkey(&path, &pubkey.to_string());
-                key_vault.write(keyvault.as_ref());
-            }
-        }
+        session.add_pubkey(&path, &pubkey.to_string())?;
 
-        Self {
-            key_pair: Some(kp),
-            client,
-            pubkey,
-        }
+        Ok(Self::new(Some(kp), client, pubkey))
+    }
+}
+
+impl<T: AsRef<Path>> HdWalletView<T, (DerivationScheme, u32)> for SolWallet {
+    fn view(
+        keyvault: T,
+        rpc: &str,
+        (scheme, child): (DerivationScheme, u32),
+    ) -> WalletResult<Self> {
+        check_scheme(scheme)?;
+
+        <Self as HdWalletView<T, &str>>::view(keyvault, rpc, &scheme.path(child))
+    }
+}
+
+impl<T: AsRef<Path>> HdWalletLoad<T, (DerivationScheme, u32)> for SolWallet {
+    fn load(
+        keyvault: T,
+        rpc: &str,
+        (scheme, child): (DerivationScheme, u32),
+        password: &PasswordProvider,
+    ) -> WalletResult<Self> {
+        check_scheme(scheme)?;
+
+        <Self as HdWalletLoad<T, &str>>::load(keyvault, rpc, &scheme.path(child), password)
+    }
+}
+
+impl SessionWalletLoad<(DerivationScheme, u32)> for SolWallet {
+    fn load(
+        session: &UnlockedVault,
+        rpc: &str,
+        (scheme, child): (DerivationScheme, u32),
+    ) -> WalletResult<Self> {
+        check_scheme(scheme)?;
+
+        <Self as SessionWalletLoad<&str>>::load(session, rpc, &scheme.path(child))
+    }
+}
+
+fn connect(rpc: &str) -> WalletResult<RpcClient> {
+    // RpcClient only fails on its first request, so check the URL up front
+    Url::parse(rpc).map_err(|e| WalletError::InvalidRpcUrl(rpc.to_string(), e.to_string()))?;
+
+    Ok(RpcClient::new(rpc.to_string()))
+}
+
+fn parse_pubkey(pubkey: &str) -> WalletResult<Pubkey> {
+    Pubkey::from_str(pubkey)
+        .map_err(|_| WalletError::InvalidAddress(Chain::Solana, pubkey.to_string()))
+}
+
+/// Name and symbol from a Metaplex metadata account
+fn parse_metaplex_metadata(data: &[u8]) -> Option<(String, String)> {
+    // Key (1 byte), update authority and mint (32 bytes each) come first
+    let mut rest = data.get(65..)?;
+
+    // Borsh strings, a u32 length then the bytes padded with NULs
+    let mut next_string = || {
+        let len = u32::from_le_bytes(rest.get(..4)?.try_into().ok()?) as usize;
+        let bytes = rest.get(4..4 + len)?;
+        rest = &rest[4 + len..];
+
+        Some(
+            String::from_utf8_lossy(bytes)
+                .trim_end_matches('\0')
+                .to_string(),
+        )
+    };
+
+    let name = next_string()?;
+    let symbol = next_string()?;
+
+    Some((name, symbol))
+}
+
+fn check_scheme(scheme: DerivationScheme) -> WalletResult<()> {
+    if scheme.chain() != Chain::Solana {
+        return Err(WalletError::WrongScheme(scheme, Chain::Solana));
     }
+
+    Ok(())
 }
 
 impl SolWallet {
     async fn build_sign_and_send(&self, instr: Instruction) -> Result<()> {
-        let kp = self.key_pair.as_ref().unwrap();
+        let kp = self.key_pair.as_ref().context("Wallet is view only")?;
 
         // Get blockhash and sign transaction
         let blockhash = self.client.get_latest_blockhash().await?;
@@ -175,7 +299,7 @@ impl SolWallet {
 
     pub async fn get_ata(&self, mint: &str) -> Result<Pubkey> {
         let token = Pubkey::from_str(mint)?;
-        let owner = self.client.get_account(&token).await?.owner;
+        let owner = self.token_program(mint).await?;
 
         let (token_account, _) = Pubkey::find_program_address(
             &[
@@ -191,7 +315,7 @@ impl SolWallet {
 
     pub async fn create_token_account(&self, mint: &str) -> Result<Pubkey> {
         let token = Pubkey::from_str(mint)?;
-        let owner = self.client.get_account(&token).await?.owner;
+        let owner = self.token_program(mint).await?;
 
         let (token_account, _) = Pubkey::find_program_address(
             &[
@@ -222,7 +346,7 @@ impl SolWallet {
 
     pub async fn close_token_account(&self, mint_str: &str) -> Result<()> {
         let mint = Pubkey::from_str(mint_str)?;
-        let owner = self.client.get_account(&mint).await?.owner;
+        let owner = self.token_program(mint_str).await?;
         let token_account = self.get_token_account(&mint).await?;
 
         // Build close instructions
@@ -250,69 +374,236 @@ impl SolWallet {
         let token = accounts.first().context("Could not find token account")?;
 
         // Get token account pubkey
-        let addy = Pubkey::from_str_const(&token.pubkey);
+        let addy = parse_pubkey(&token.pubkey)?;
 
         Ok(addy)
     }
 
+    /// Loads a wallet from a `solana-keygen` keypair file
+    pub fn from_keypair_file(fname: &Path, rpc: &str) -> Result<Self> {
+        let secret = read_solana_keypair(fname)?;
+
+        Ok(<Self as WalletLoad<&PrivateKey>>::load(&secret, rpc)?)
+    }
+
+    /// Solana CLI keypair JSON of the wallet's key
+    pub fn export_keypair(&self) -> Result<String> {
+        let kp = self.key_pair.as_ref().context("Wallet is view only")?;
+        let secret = PrivateKey::from_slice(kp.secret_bytes())?;
+
+        Ok(encode_solana_keypair(&secret))
+    }
+
+    /// Signs the raw bytes of `message`, as wallet `signMessage` and
+    /// Sign In With Solana do
+    pub fn sign_message(&self, message: &[u8]) -> Result<Signature> {
+        let kp = self.key_pair.as_ref().context("Wallet is view only")?;
+
+        Ok(kp.sign_message(message))
+    }
+
+    pub fn verify_message(pubkey: &str, message: &[u8], signature: &Signature) -> Result<bool> {
+        let pubkey = Pubkey::from_str(pubkey)?;
+
+        Ok(signature.verify(pubkey.as_ref(), message))
+    }
+
+    /// Signs `message` in the off-chain message format, whose signing
+    /// domain prefix keeps it from ever being a valid transaction
+    pub fn sign_offchain_message(&self, message: &[u8]) -> Result<Signature> {
+        let kp = self.key_pair.as_ref().context("Wallet is view only")?;
+        let message = OffchainMessage::new(0, message)?;
+
+        Ok(message.sign(kp)?)
+    }
+
+    pub fn verify_offchain_message(
+        pubkey: &str,
+        message: &[u8],
+        signature: &Signature,
+    ) -> Result<bool> {
+        let pubkey = Pubkey::from_str(pubkey)?;
+        let message = OffchainMessage::new(0, message)?;
+
+        Ok(message.verify(&pubkey, signature)?)
+    }
+
+    /// Walks the children of `scheme` until `gap_limit` unused accounts in
+    /// a row, recording every used account in the vault. Returns the paths
+    /// of the used accounts.
+    pub async fn discover(
+        session: &UnlockedVault,
+        rpc: &str,
+        scheme: DerivationScheme,
+        gap_limit: u32,
+    ) -> Result<Vec<String>> {
+        check_scheme(scheme)?;
+
+        let first = session.derive_address(Chain::Solana, &scheme.path(0))?;
+        let mut wallet = <Self as WalletView<&str>>::view(&first, rpc)?;
+
+        let children = scan_children(gap_limit, async |child| -> Result<bool> {
+            let address = session.derive_address(Chain::Solana, &scheme.path(child))?;
+            wallet.pubkey = Pubkey::from_str(&address)?;
+            wallet.is_used().await
+        })
+        .await?;
+
+        children
+            .into_iter()
+            .map(|child| -> Result<String> {
+                let path = scheme.path(child);
+                let address = session.derive_address(Chain::Solana, &path)?;
+                session.add_pubkey(&path, &address)?;
+                Ok(path)
+            })
+            .collect()
+    }
+
+    /// An account counts as used once it holds a balance or appears in a
+    /// transaction
+    pub async fn is_used(&self) -> Result<bool> {
+        if self.client.get_balance(&self.pubkey).await? > 0 {
+            return Ok(true);
+        }
+
+        let signatures = self.client.get_signatures_for_address(&self.pubkey).await?;
+
+        Ok(!signatures.is_empty())
+    }
+
     pub fn get_pubkey(&self) -> Result<String> {
         Ok(self.pubkey.to_string())
     }
 
-    pub fn format_native(&self, amount: f64) -> Result<u64> {
-        let amt = (amount * 1e9) as u64;
+    /// Shares `cache` with this wallet, e.g. one cache for every Solana wallet
+    pub fn with_token_cache(mut self, cache: TokenCache) -> Self {
+        self.tokens = cache;
+        self
+    }
+
+    /// Genesis hash of the cluster, fetched once, which keys the token cache
+    async fn network(&self) -> Result<&str> {
+        if let Some(network) = self.network.get() {
+            return Ok(network);
+        }
+
+        let network = self.client.get_genesis_hash().await?.to_string();
+
+        Ok(self.network.get_or_init(|| network))
+    }
+
+    pub async fn token_metadata(&self, mint: &str) -> Result<TokenMetadata> {
+        let network = self.network().await?;
+        if let Some(metadata) = self.tokens.get(network, mint) {
+            return Ok(metadata);
+        }
+
+        let mint_pubkey = Pubkey::from_str(mint)?;
+        let (metadata_addy, _) = Pubkey::find_program_address(
+            &[
+                b"metadata",
+                &METADATA_ID.to_bytes(),
+                &mint_pubkey.to_bytes(),
+            ],
+            &METADATA_ID,
+        );
+
+        // Fetch the mint and its Metaplex metadata in one round-trip
+        let accounts = self
+            .client
+            .get_multiple_accounts(&[mint_pubkey, metadata_addy])
+            .await?;
+
+        let mint_account = accounts[0]
+            .as_ref()
+            .with_context(|| format!("Mint {mint} does not exist"))?;
+
+        // Same layout for SPL Token and Token-2022, decimals come after the
+        // mint authority (36 bytes) and the supply (8 bytes)
+        let decimals = *mint_account
+            .data
+            .get(44)
+            .with_context(|| format!("{mint} is not a token mint"))?;
+
+        let (name, symbol) = accounts[1]
+            .as_ref()
+            .and_then(|account| parse_metaplex_metadata(&account.data))
+            .unzip();
+
+        let metadata = TokenMetadata {
+            address: mint.to_string(),
+            decimals,
+            symbol,
+            name,
+            program: Some(mint_account.owner.to_string()),
+        };
+        self.tokens.insert(network, metadata.clone());
 
-        Ok(amt)
+        Ok(metadata)
     }
 
-    pub fn parse_native(&self, amount: u64) -> Result<f64> {
-        Ok((amount as f64) / 1.0e9)
+    pub async fn token_decimals(&self, token: &str) -> Result<u8> {
+        Ok(self.token_metadata(token).await?.decimals)
     }
 
-    pub async fn format_token(&self, amount: f64, token: &str) -> Result<u64> {
-        let pubkey = Pubkey::from_str_const(token);
-        let token_addy = self.get_token_account(&pubkey).await?;
-        let acct = self.client.get_token_account(&token_addy).await?.unwrap();
+    async fn token_program(&self, mint: &str) -> Result<Pubkey> {
+        let metadata = self.token_metadata(mint).await?;
+        let program = metadata
+            .program
+            .with_context(|| format!("No token program known for {mint}"))?;
 
-        let deci = acct.token_amount.decimals;
+        Ok(Pubkey::from_str(&program)?)
+    }
 
-        let amt = (amount * 10.0_f64.powi(deci.into())) as u64;
+    pub fn format_native(&self, amount: impl Into<Amount>) -> Result<TokenAmount> {
+        Ok(amount.into().resolve(NATIVE_DECIMALS)?)
+    }
 
-        Ok(amt)
+    pub fn parse_native(&self, amount: U256) -> Result<TokenAmount> {
+        Ok(TokenAmount::new(amount, NATIVE_DECIMALS))
     }
 
-    pub async fn parse_token(&self, amount: u64, token: &str) -> Result<f64> {
-        let pubkey = Pubkey::from_str_const(token);
-        let token_addy = self.get_token_account(&pubkey).await?;
-        let acct = self.client.get_token_account(&token_addy).await?.unwrap();
+    pub async fn format_token(
+        &self,
+        amount: impl Into<Amount>,
+        token: &str,
+    ) -> Result<TokenAmount> {
+        let amount = amount.into();
+        let deci = self.token_decimals(token).await?;
 
-        let deci = acct.token_amount.decimals;
+        Ok(amount.resolve(deci)?)
+    }
 
-        Ok((amount as f64) / 10.0_f64.powi(deci.into()))
+    pub async fn parse_token(&self, amount: U256, token: &str) -> Result<TokenAmount> {
+        let raw = amount;
+        let deci = self.token_decimals(token).await?;
+
+        Ok(TokenAmount::new(raw, deci))
     }
 
-    pub async fn close(&self, to: &str) -> Result<()> {
+    pub async fn close(&self, to: &str) -> Result<SolTxnReceipt> {
         let bal = self.balance().await?;
 
         // Subtract fee
-        let amount = bal - 0.000005;
-
-        let _ = self.transfer(to, amount).await?;
+        let fee = TokenAmount::new(U256::from(5000u64), NATIVE_DECIMALS);
+        let amount = bal
+            .checked_sub(&fee)
+            .context("Balance does not cover the transfer fee")?;
 
-        Ok(())
+        self.transfer(to, amount).await
     }
 
-    pub async fn balance(&self) -> Result<f64> {
+    pub async fn balance(&self) -> Result<TokenAmount> {
         let balance = self.client.get_balance(&self.pubkey).await?;
-        let bal = (balance as f64) / 1e9;
 
-        Ok(bal)
+        Ok(TokenAmount::new(U256::from(balance), NATIVE_DECIMALS))
     }
 
-    pub async fn transfer(&self, to: &str, amount: f64) -> Result<SolTxnReceipt> {
-        let kp = self.key_pair.as_ref().unwrap();
-        let to_pubkey = Pubkey::from_str_const(to);
-        let lamp = self.format_native(amount)?;
+    pub async fn transfer(&self, to: &str, amount: impl Into<Amount>) -> Result<SolTxnReceipt> {
+        let kp = self.key_pair.as_ref().context("Wallet is view only")?;
+        let to_pubkey = parse_pubkey(to)?;
+        let lamp = self.format_native(amount)?.to_u64()?;
 
         let info = transfer(&self.pubkey, &to_pubkey, lamp);
         let mut trans = Transaction::new_with_payer(&[info], Some(&self.pubkey));
@@ -320,36 +611,42 @@ impl SolWallet {
         let blockhash = self.client.get_latest_blockhash().await?;
         trans.sign(&[kp], blockhash);
 
-        let sig = self
-            .client
-            .send_and_confirm_transaction(&trans)
-            .await
-            .unwrap();
+        let sig = self.client.send_and_confirm_transaction(&trans).await?;
 
         Ok(SolTxnReceipt::new(sig, &self.client).await)
     }
 
-    pub async fn token_balance(&self, mint: &str) -> Result<f64> {
-        let mint_pubkey = Pubkey::from_str_const(mint);
+    pub async fn token_balance(&self, mint: &str) -> Result<TokenAmount> {
+        let mint_pubkey = parse_pubkey(mint)?;
         let addy_result = self.get_token_account(&mint_pubkey).await;
 
-        let bal = match addy_result {
-            Ok(addy) => {
-                let token_data = self.client.get_token_account_balance(&addy).await?;
-                token_data.ui_amount.unwrap_or(0.0)
-            }
-            Err(_) => 0.0,
+        let token_data = match addy_result {
+            Ok(addy) => self.client.get_token_account_balance(&addy).await?,
+            // No token account yet, the balance is zero
+            Err(_) => return Ok(TokenAmount::zero(self.token_decimals(mint).await?)),
         };
 
-        Ok(bal)
+        Ok(TokenAmount::new(
+            U256::from_str(&token_data.amount)?,
+            token_data.decimals,
+        ))
+    }
+
+    pub async fn token_balances(&self, mints: &[&str]) -> Result<Vec<TokenAmount>> {
+        let mut balances = Vec::with_capacity(mints.len());
+        for mint in mints {
+            balances.push(self.token_balance(mint).await?);
+        }
+
+        Ok(balances)
     }
 
-    pub async fn burn_token(&self, mint: &str, amount: f64) -> Result<SolTxnReceipt> {
-        let kp = self.key_pair.as_ref().unwrap();
-        let mint_pubkey = Pubkey::from_str_const(mint);
-        let owner = self.client.get_account(&mint_pubkey).await?.owner;
+    pub async fn burn_token(&self, mint: &str, amount: impl Into<Amount>) -> Result<SolTxnReceipt> {
+        let kp = self.key_pair.as_ref().context("Wallet is view only")?;
+        let mint_pubkey = parse_pubkey(mint)?;
+        let owner = self.token_program(mint).await?;
         let source = self.get_token_account(&mint_pubkey).await?;
-        let lamp = self.format_token(amount, mint).await?;
+        let lamp = self.format_token(amount, mint).await?.to_u64()?;
 
         let mut data = vec![8];
         data.extend_from_slice(&lamp.to_le_bytes());
@@ -369,22 +666,23 @@ impl SolWallet {
         let blockhash = self.client.get_latest_blockhash().await?;
         trans.sign(&[kp], blockhash);
 
-        let sig = self
-            .client
-            .send_and_confirm_transaction(&trans)
-            .await
-            .unwrap();
+        let sig = self.client.send_and_confirm_transaction(&trans).await?;
 
         Ok(SolTxnReceipt::new(sig, &self.client).await)
     }
 
-    pub async fn transfer_token(&self, mint: &str, amount: f64, to: &str) -> Result<SolTxnReceipt> {
-        let kp = self.key_pair.as_ref().unwrap();
-        let to_pubkey = Pubkey::from_str_const(to);
-        let mint_pubkey = Pubkey::from_str_const(mint);
-        let owner = self.client.get_account(&mint_pubkey).await?.owner;
+    pub async fn transfer_token(
+        &self,
+        mint: &str,
+        amount: impl Into<Amount>,
+        to: &str,
+    ) -> Result<SolTxnReceipt> {
+        let kp = self.key_pair.as_ref().context("Wallet is view only")?;
+        let to_pubkey = parse_pubkey(to)?;
+        let mint_pubkey = parse_pubkey(mint)?;
+        let owner = self.token_program(mint).await?;
         let source = self.get_token_account(&mint_pubkey).await?;
-        let lamp = self.format_token(amount, mint).await?;
+        let lamp = self.format_token(amount, mint).await?.to_u64()?;
 
         let mut data = vec![3];
         data.extend_from_slice(&lamp.to_le_bytes());
@@ -394,8 +692,8 @@ impl SolWallet {
             .get_token_accounts_by_owner(&to_pubkey, Mint(mint_pubkey))
             .await?;
 
-        let token = accounts.first().unwrap();
-        let destination = Pubkey::from_str_const(&token.pubkey);
+        let token = accounts.first().context("Recipient has no token account")?;
+        let destination = parse_pubkey(&token.pubkey)?;
 
         let instruction = Instruction {
             program_id: owner,
@@ -412,29 +710,29 @@ impl SolWallet {
         let blockhash = self.client.get_latest_blockhash().await?;
         trans.sign(&[kp], blockhash);
 
-        let sig = self
-            .client
-            .send_and_confirm_transaction(&trans)
-            .await
-            .unwrap();
+        let sig = self.client.send_and_confirm_transaction(&trans).await?;
 
         Ok(SolTxnReceipt::new(sig, &self.client).await)
     }
 
-    pub async fn transfer_all_tokens(&self, mint: &str, to: &str) -> Result<()> {
+    /// Sends the whole balance and closes the token account, the receipt is
+    /// `None` if there was no balance to transfer
+    pub async fn transfer_all_tokens(&self, mint: &str, to: &str) -> Result<Option<SolTxnReceipt>> {
         let amount = self.token_balance(mint).await?;
 
-        if amount != 0.0 {
-            let _ = self.transfer_token(mint, amount, to).await?;
-        }
+        let receipt = if amount.is_zero() {
+            None
+        } else {
+            Some(self.transfer_token(mint, amount, to).await?)
+        };
 
         let _ = self.close_token_account(&mint).await?;
 
-        Ok(())
+        Ok(receipt)
     }
 
     pub async fn sign_and_send(&self, mut txn: VersionedTransaction) -> Result<SolTxnReceipt> {
-        let kp = self.key_pair.as_ref().unwrap();
+        let kp = self.key_pair.as_ref().context("Wallet is view only")?;
         let message = txn.message.serialize();
         let signature = kp.sign_message(&message);
 
@@ -455,13 +753,83 @@ impl SolWallet {
     }
 }
 
+impl Wallet for SolWallet {
+    type Receipt = SolTxnReceipt;
+    type Transaction = VersionedTransaction;
+
+    fn get_pubkey(&self) -> Result<String> {
+        SolWallet::get_pubkey(self)
+    }
+
+    fn format_native(&self, amount: impl Into<Amount>) -> Result<TokenAmount> {
+        SolWallet::format_native(self, amount)
+    }
+
+    fn parse_native(&self, amount: U256) -> Result<TokenAmount> {
+        SolWallet::parse_native(self, amount)
+    }
+
+    async fn token_metadata(&self, token: &str) -> Result<TokenMetadata> {
+        SolWallet::token_metadata(self, token).await
+    }
+
+    async fn token_decimals(&self, token: &str) -> Result<u8> {
+        SolWallet::token_decimals(self, token).await
+    }
+
+    async fn format_token(&self, amount: impl Into<Amount>, token: &str) -> Result<TokenAmount> {
+        SolWallet::format_token(self, amount, token).await
+    }
+
+    async fn parse_token(&self, amount: U256, token: &str) -> Result<TokenAmount> {
+        SolWallet::parse_token(self, amount, token).await
+    }
+
+    async fn balance(&self) -> Result<TokenAmount> {
+        SolWallet::balance(self).await
+    }
+
+    async fn token_balance(&self, token: &str) -> Result<TokenAmount> {
+        SolWallet::token_balance(self, token).await
+    }
+
+    async fn token_balances(&self, tokens: &[&str]) -> Result<Vec<TokenAmount>> {
+        SolWallet::token_balances(self, tokens).await
+    }
+
+    async fn transfer(&self, to: &str, amount: impl Into<Amount>) -> Result<SolTxnReceipt> {
+        SolWallet::transfer(self, to, amount).await
+    }
+
+    async fn transfer_token(
+        &self,
+        token: &str,
+        amount: impl Into<Amount>,
+        to: &str,
+    ) -> Result<SolTxnReceipt> {
+        SolWallet::transfer_token(self, token, amount, to).await
+    }
+
+    async fn transfer_all_tokens(&self, token: &str, to: &str) -> Result<Option<SolTxnReceipt>> {
+        SolWallet::transfer_all_tokens(self, token, to).await
+    }
+
+    async fn close(&self, to: &str) -> Result<SolTxnReceipt> {
+        SolWallet::close(self, to).await
+    }
+
+    async fn sign_and_send(&self, txn: VersionedTransaction) -> Result<SolTxnReceipt> {
+        SolWallet::sign_and_send(self, txn).await
+    }
+}
+
 pub struct SolTxnReceipt {
     pub hash: String,
     pub slot: u64,
     pub block_time: Option<i64>,
-    pub gas_used: f64,
-    pub pre_balances: Vec<f64>,
-    pub post_balances: Vec<f64>,
+    pub gas_used: TokenAmount,
+    pub pre_balances: Vec<TokenAmount>,
+    pub post_balances: Vec<TokenAmount>,
     pub pre_token_balances: Option<Vec<UiTransactionTokenBalance>>,
     pub post_token_balances: Option<Vec<UiTransactionTokenBalance>>,
     pub loaded_addresses: OptionSerializer<UiLoadedAddresses>,
@@ -481,17 +849,10 @@ impl SolTxnReceipt {
             .expect("Transaction not found");
 
         let meta = data.transaction.meta.unwrap();
-        let gas_used = (meta.fee as f64) / 1e9;
-        let pre_balances = meta
-            .pre_balances
-            .iter()
-            .map(|b| (*b as f64) / 1e9)
-            .collect();
-        let post_balances = meta
-            .post_balances
-            .iter()
-            .map(|b| (*b as f64) / 1e9)
-            .collect();
+        let lamports = |b: &u64| TokenAmount::new(U256::from(*b), NATIVE_DECIMALS);
+        let gas_used = lamports(&meta.fee);
+        let pre_balances = meta.pre_balances.iter().map(lamports).collect();
+        let post_balances = meta.post_balances.iter().map(lamports).collect();
 
         Self {
             hash: sig.to_string(),
@@ -3,6 +3,7 @@ use std::time::Duration;
 use anyhow::{Result, bail};
 use bonanca::{
     defi::CoW,
+    keyvault::PasswordProvider,
     wallets::{EvmWallet, HdWalletLoad, HdWalletView},
 };
 
@@ -14,14 +15,16 @@ use crate::{
 
 pub async fn balance(cmds: BalArgs) -> Result<()> {
     let settings = GridBotSettings::load(&cmds.json);
-    let wallet = EvmWallet::view(&settings.keyvault, &settings.rpc_url, settings.child);
+    let wallet = EvmWallet::view(&settings.keyvault, &settings.rpc_url, settings.child)?;
 
     let bal_a = wallet
         .token_balance(&settings.trading_pair.token_a.address)
-        .await?;
+        .await?
+        .to_f64();
     let bal_b = wallet
         .token_balance(&settings.trading_pair.token_b.address)
-        .await?;
+        .await?
+        .to_f64();
 
     println!(
         "Bal {}: {:.4} || Bal {}: {:.4}",
@@ -38,9 +41,14 @@ pub async fn run(cmds: RunArgs) -> Result<()> {
 
     let settings = GridBotSettings::load(&cmds.json);
     let wallet = if cmds.dry {
-        EvmWallet::view(&settings.keyvault, &settings.rpc_url, settings.child)
+        EvmWallet::view(&settings.keyvault, &settings.rpc_url, settings.child)?
     } else {
-        EvmWallet::load(&settings.keyvault, &settings.rpc_url, settings.child)
+        EvmWallet::load(
+            &settings.keyvault,
+            &settings.rpc_url,
+            settings.child,
+            &PasswordProvider::Prompt,
+        )?
     };
     let cow = CoW::new(&settings.chain)?;
 
@@ -1,6 +1,7 @@
 use anyhow::Result;
 use bonanca::defi::{Jupiter, ZeroX};
-use bonanca::wallets::{EvmWallet, HdWalletLoad, SolWallet};
+use bonanca::keyvault::PasswordProvider;
+use bonanca::wallets::AnyWallet;
 
 use crate::args::{BalArgs, RebalArgs};
 use crate::index_fund::{IndexFund, RebalTrade};
@@ -71,20 +72,21 @@ pub async fn rebalance_index_fund(cmd: RebalArgs) -> Result<()> {
 }
 
 async fn make_trades(fund: &IndexFund, trades: Vec<RebalTrade>) -> Result<()> {
-    let chain = if fund.chain.contains(":") {
-        fund.chain.split(":").next().unwrap()
-    } else {
-        &fund.chain
-    };
-
-    match (chain, fund.aggregator.name.as_str()) {
-        ("EVM", "0x") => {
-            let wallet = EvmWallet::load(&fund.keyvault, &fund.rpc_url, fund.child);
+    let wallet = AnyWallet::load(
+        fund.chain(),
+        &fund.keyvault,
+        &fund.rpc_url,
+        fund.child,
+        &PasswordProvider::Prompt,
+    )?;
+
+    match (&wallet, fund.aggregator.name.as_str()) {
+        (AnyWallet::Evm(wallet), "0x") => {
             let dex = ZeroX::new(fund.aggregator.api_key.clone(), fund.chain_id.unwrap());
 
             for trade in trades.iter() {
                 let issues = dex
-                    .check_swap(&wallet, &trade.from, &trade.to, trade.amount)
+                    .check_swap(wallet, &trade.from, &trade.to, trade.amount)
                     .await
                     .unwrap();
 
@@ -97,18 +99,17 @@ async fn make_trades(fund: &IndexFund, trades: Vec<RebalTrade>) -> Result<()> {
                 }
 
                 let _ = dex
-                    .quick_swap(&wallet, &trade.from, &trade.to, trade.amount)
+                    .quick_swap(wallet, &trade.from, &trade.to, trade.amount)
                     .await
                     .unwrap();
             }
         }
-        ("Solana", "Jupiter") => {
-            let wallet = SolWallet::load(&fund.keyvault, &fund.rpc_url, fund.child);
+        (AnyWallet::Sol(wallet), "Jupiter") => {
             let dex = Jupiter::new(fund.aggregator.api_key.clone());
 
             for trade in trades.iter() {
                 let _ = dex
-                    .quick_swap(&wallet, &trade.from, &trade.to, trade.amount)
+                    .quick_swap(wallet, &trade.from, &trade.to, trade.amount)
                     .await
                     .unwrap();
             }
@@ -7,8 +7,9 @@ use std::{
 
 use anyhow::{Ok, Result};
 use bonanca::{
+    keyvault::Chain,
     oracle::{CoinMarketCap, DefiLlama},
-    wallets::{EvmWallet, HdWalletView, SolWallet},
+    wallets::{AnyWallet, Wallet},
 };
 use serde::{Deserialize, Serialize};
 
@@ -42,44 +43,28 @@ impl IndexFund {
         fund
     }
 
-    async fn get_gas_balance(&self) -> Result<f64> {
-        let chain = if self.chain.contains(":") {
-            self.chain.split(":").next().unwrap()
-        } else {
-            &self.chain
-        };
+    pub fn chain(&self) -> Chain {
+        let chain = self.chain.split(":").next().unwrap();
 
-        match chain {
-            "EVM" => {
-                let wallet = EvmWallet::view(&self.keyvault, &self.rpc_url, self.child);
-                wallet.balance().await
-            }
-            "Solana" => {
-                let wallet = SolWallet::view(&self.keyvault, &self.rpc_url, self.child);
-                wallet.balance().await
-            }
-            _ => panic!(),
-        }
+        chain.parse().expect("Unsupported chain")
     }
 
-    async fn get_asset_balance(&self, asset: &Asset) -> Result<f64> {
-        let chain = if self.chain.contains(":") {
-            self.chain.split(":").next().unwrap()
-        } else {
-            &self.chain
-        };
+    fn view_wallet(&self) -> Result<AnyWallet> {
+        let wallet = AnyWallet::view(self.chain(), &self.keyvault, &self.rpc_url, self.child)?;
 
-        match chain {
-            "EVM" => {
-                let wallet = EvmWallet::view(&self.keyvault, &self.rpc_url, self.child);
-                wallet.token_balance(&asset.address).await
-            }
-            "Solana" => {
-                let wallet = SolWallet::view(&self.keyvault, &self.rpc_url, self.child);
-                wallet.token_balance(&asset.address).await
-            }
-            _ => panic!(),
-        }
+        Ok(wallet)
+    }
+
+    async fn get_gas_balance(&self) -> Result<f64> {
+        Ok(self.view_wallet()?.balance().await?.to_f64())
+    }
+
+    // One batched read on EVM chains instead of a call per asset
+    async fn get_asset_balances(&self, assets: &[&Asset]) -> Result<Vec<f64>> {
+        let addresses: Vec<&str> = assets.iter().map(|a| a.address.as_str()).collect();
+        let bals = self.view_wallet()?.token_balances(&addresses).await?;
+
+        Ok(bals.iter().map(|bal| bal.to_f64()).collect())
     }
 
     async fn get_asset_value(&self, asset: &Asset, amount: f64, chain: &str) -> Result<f64> {
@@ -107,10 +92,19 @@ impl IndexFund {
         let mut total = 0.0;
         let mut balances: Vec<AssetBalance> = Vec::new();
 
+        let aux_assets = self.auxiliary_assets.as_deref().unwrap_or_default();
+        let assets: Vec<&Asset> = self
+            .sectors
+            .iter()
+            .flat_map(|s| s.assets.iter())
+            .chain(aux_assets)
+            .collect();
+        let mut asset_bals = self.get_asset_balances(&assets).await?.into_iter();
+
         for sector in &self.sectors {
             let target = sector.weight / (sector.assets.len() as f64);
             for asset in &sector.assets {
-                let bal = self.get_asset_balance(asset).await?;
+                let bal = asset_bals.next().unwrap();
 
                 let usd = if bal != 0.0 {
                     self.get_asset_value(asset, bal, chain).await?
@@ -134,7 +128,7 @@ impl IndexFund {
 
         if self.auxiliary_assets.is_some() {
             for asset in self.auxiliary_assets.as_ref().unwrap() {
-                let bal = self.get_asset_balance(asset).await?;
+                let bal = asset_bals.next().unwrap();
 
                 let usd = if bal != 0.0 {
                     self.get_asset_value(asset, bal, chain).await?
@@ -1,11 +1,36 @@
 pub mod keyvault {
-    pub use bonanca_keyvault::keyvault::KeyVault;
+    pub use bonanca_keyvault::{
+        accounts::Account,
+        chains::{Chain, DerivationScheme},
+        display::MnemonicDisplay,
+        error::KeyVaultError,
+        keystore,
+        keyvault::KeyVault,
+        password::PasswordProvider,
+        secret::{PrivateKey, Seed},
+        session::UnlockedVault,
+        shamir,
+    };
 }
 
 pub mod wallets {
     pub use bonanca_wallets::{
-        HdWalletLoad, HdWalletView, WalletLoad, WalletView,
-        wallets::{evm::EvmWallet, solana::SolWallet},
+        HdWalletLoad, HdWalletView, ImportedWalletLoad, SessionWalletLoad, TransactionData, Wallet,
+        WalletLoad, WalletView,
+        amount::{Amount, TokenAmount},
+        error::WalletError,
+        fees::{FeeMode, FeePolicy, GasFees, StuckTxnPolicy},
+        multicall::{MULTICALL3, Multicall, MulticallResults},
+        nft::{NftAttribute, NftMetadata, NftMetadataApi, NftStandard},
+        nonce::{NonceManager, PendingTxn},
+        permit::{PERMIT2, SignedPermit, SignedPermit2, SignedPermit2Transfer},
+        simulate::{Revert, RevertDecoder, SendOutcome, Simulation, SimulationMode},
+        tokens::{TokenCache, TokenMetadata},
+        wallets::{
+            any::{AnyReceipt, AnyWallet},
+            evm::EvmWallet,
+            solana::{SolTxnReceipt, SolWallet},
+        },
     };
 }
 